@@ -0,0 +1,9 @@
+# 测试用命令
+fill ~ ~ ~ ~6 ~4 ~6 minecraft:stone_bricks hollow
+fill ~1 ~4 ~1 ~5 ~4 ~5 minecraft:oak_planks outline
+setblock ~3 ~1 ~0 minecraft:oak_door[facing=south,half=lower]
+setblock ~3 ~2 ~0 minecraft:oak_door[facing=south,half=upper]
+setblock ~1 ~1 ~1 minecraft:chest[facing=east]{Items:[{Slot:0b,id:"minecraft:torch",Count:16b}]}
+/execute positioned ~ ~ ~ run setblock ~5 ~1 ~5 minecraft:crafting_table
+fill ~1 ~0 ~1 ~5 ~0 ~5 minecraft:polished_andesite replace minecraft:stone_bricks
+clone ~0 ~0 ~0 ~6 ~4 ~6 ~8 ~0 ~0 masked
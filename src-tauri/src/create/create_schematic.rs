@@ -81,20 +81,37 @@ impl CreateSchematic {
         }
     }
 
-    pub fn get_palette(&self) -> Result<&Vec<Value>, SchematicError> {
-        if let Compound(root) = &self.nbt {
-            root.get("palette")
-                .and_then(|v| match v {
-                    Value::List(list) => Some(list),
-                    _ => None,
+    pub fn get_palettes(&self) -> Result<Vec<&Vec<Value>>, SchematicError> {
+        let Compound(root) = &self.nbt else {
+            return Err(SchematicError::InvalidFormat("Root is not a Compound"));
+        };
+        if let Some(Value::List(list)) = root.get("palette") {
+            return Ok(vec![list]);
+        }
+        // 原版部分结构(如沉船)使用 palettes 保存多套可替换的调色板
+        match root.get("palettes") {
+            Some(Value::List(palettes)) if !palettes.is_empty() => palettes
+                .iter()
+                .map(|v| match v {
+                    Value::List(list) => Ok(list),
+                    _ => Err(SchematicError::InvalidFormat("Palette variant is not a list")),
                 })
-                .ok_or(SchematicError::InvalidFormat(
-                    "NotFound Size is not a IntArray",
-                ))
-        } else {
-            Err(SchematicError::InvalidFormat("Root is not a Compound"))
+                .collect(),
+            _ => Err(SchematicError::InvalidFormat("NotFound palette or palettes")),
         }
     }
+
+    pub fn palette_count(&self) -> usize {
+        self.get_palettes().map(|p| p.len()).unwrap_or(0)
+    }
+
+    pub fn get_palette_variant(&self, index: usize) -> Result<&Vec<Value>, SchematicError> {
+        self.get_palettes()?
+            .get(index)
+            .copied()
+            .ok_or(SchematicError::InvalidFormat("Palette variant out of range"))
+    }
+
     pub fn get_entities(&self) -> Option<&Vec<Value>> {
         if let Compound(root) = &self.nbt {
             root.get("entities").and_then(|v| match v {
@@ -105,8 +122,8 @@ impl CreateSchematic {
             None
         }
     }
-    fn parse_palette(&self) -> Result<Vec<Arc<BlockData>>, SchematicError> {
-        let palette_list = self.get_palette()?;
+    pub fn parse_palette(&self, index: usize) -> Result<Vec<Arc<BlockData>>, SchematicError> {
+        let palette_list = self.get_palette_variant(index)?;
 
         let mut palette = Vec::with_capacity(palette_list.len());
 
@@ -138,11 +155,15 @@ impl CreateSchematic {
     }
 
     pub fn get_blocks_pos(&self) -> Result<SchematicData, SchematicError> {
+        self.get_blocks_pos_variant(0)
+    }
+
+    pub fn get_blocks_pos_variant(&self, palette_index: usize) -> Result<SchematicData, SchematicError> {
         let mut block_list = BlockStatePosList::default();
         let mut tile_entities = TileEntitiesList::default();
         tile_entities.original_type = 1;
         let blocks = self.get_pos_list()?;
-        let palette = self.parse_palette()?;
+        let palette = self.parse_palette(palette_index)?;
         let size = self.get_size()?;
 
         let sizes = match size {
@@ -196,7 +217,9 @@ impl CreateSchematic {
                 Value::Int(n) => *n as usize,
                 _ => return Err(SchematicError::InvalidFormat("State ID must be integer")),
             };
-            let block_data = &palette[state_id];
+            let block_data = palette
+                .get(state_id)
+                .ok_or(SchematicError::InvalidFormat("State ID out of palette range"))?;
             block_list.add(pos, Arc::clone(block_data));
 
             if let Some(nbt_value) = compound.get("nbt") {
@@ -1,5 +1,5 @@
 use crate::utils::block_state_pos_list::{BlockData, BlockPos, BlockStatePos};
-use crate::utils::schematic_data::{SchematicData, SchematicError};
+use crate::utils::schematic_data::{SchematicData, SchematicError, Size};
 use fastnbt::Value;
 use fastnbt::Value::Compound;
 use rayon::iter::IndexedParallelIterator;
@@ -9,8 +9,17 @@ use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use anyhow::Result;
 use crate::utils::entities::EntitiesList;
+use crate::utils::structure_tiles::{tile_schematic, StructureTile, STRUCTURE_LIMIT};
 use crate::utils::tile_entities::{TileEntities, TileEntitiesList};
 
+#[derive(Debug, Clone)]
+pub struct CreateTile {
+    pub index: (i32, i32, i32),
+    pub offset: BlockPos,
+    pub size: Size,
+    pub nbt: Value,
+}
+
 #[derive(Debug)]
 pub struct ToCreateSchematic {
     blocks: VecDeque<BlockStatePos>,
@@ -195,4 +204,36 @@ impl ToCreateSchematic {
 
         Compound(tag)
     }
+
+    // 结构方块加载时以 size 为准，切块需要写入真实尺寸
+    pub fn structure_tile(tile: &StructureTile, air: bool) -> Result<Value, SchematicError> {
+        let mut nbt = ToCreateSchematic::new(&tile.data)?.create_schematic(air);
+        if let Compound(map) = &mut nbt {
+            map.insert(
+                "size".to_string(),
+                Value::List(vec![
+                    Value::Int(tile.size.width),
+                    Value::Int(tile.size.height),
+                    Value::Int(tile.size.length),
+                ]),
+            );
+        }
+        Ok(nbt)
+    }
+
+    pub fn create_tiles(schematic: &SchematicData, air: bool) -> Result<Vec<CreateTile>, SchematicError> {
+        let tiles = tile_schematic(schematic, STRUCTURE_LIMIT);
+        tiles
+            .tiles
+            .iter()
+            .map(|tile| {
+                Ok(CreateTile {
+                    index: tile.index,
+                    offset: tile.offset,
+                    size: tile.size,
+                    nbt: Self::structure_tile(tile, air)?,
+                })
+            })
+            .collect()
+    }
 }
@@ -22,6 +22,11 @@ use tauri::{AppHandle, Manager};
 use crate::be_schematic::be_schematic::BESchematic;
 use crate::be_schematic::le_reader::read_nbt_le::load_nbt_le;
 use crate::be_schematic::le_reader::write_nbt_le::save_nbt_le;
+use crate::be_schematic::to_be_schematic::ToBESchematic;
+use crate::building_gadges::to_bg_schematic::ToBgSchematic;
+use crate::create::to_create_schematic::ToCreateSchematic;
+use crate::litematica::to_lm_schematic::ToLmSchematic;
+use crate::word_edit::to_we_schematic::ToWeSchematic;
 
 #[derive(Debug)]
 pub struct FileData {
@@ -326,6 +331,39 @@ impl FileManager {
         Ok(out_path)
     }
 
+    pub fn save_schematic_value(
+        &self,
+        id: i64,
+        data: &SchematicData,
+        version: i32,
+        sub_version: i32,
+        v_type: i32,
+    ) -> Result<PathBuf> {
+        match v_type {
+            1 => {
+                let value = ToCreateSchematic::new(data)?.create_schematic(true);
+                self.save_nbt_value(id, value, version, sub_version, v_type, true)
+            }
+            2 => {
+                let value = ToLmSchematic::new(data)?.lm_schematic(6);
+                self.save_nbt_value(id, value, version, sub_version, v_type, true)
+            }
+            3 => {
+                let value = ToWeSchematic::new(data)?.we_schematic(sub_version)?;
+                self.save_nbt_value(id, value, version, sub_version, v_type, true)
+            }
+            4 => {
+                let value = ToBgSchematic::new(data)?.bg_schematic(sub_version)?;
+                self.save_json_value(id, value, version, sub_version, v_type)
+            }
+            5 => {
+                let value = ToBESchematic::new(data)?.to_be_value();
+                self.save_nbt_le_value(id, value, version, sub_version, v_type)
+            }
+            _ => Err(anyhow!("UNK: {}", v_type)),
+        }
+    }
+
     pub fn save_nbt_value_temp(
         &self,
         data: Value,
@@ -616,6 +654,45 @@ impl FileManager {
             _ => Err(anyhow!("UNK: {}", v_type)),
         }
     }
+    pub fn save_thumbnail(&self, id: i64, version: i32, png: &[u8]) -> Result<PathBuf> {
+        let schematic_dir = self.schematic_dir(id)?;
+        let path = schematic_dir.join(format!("thumbnail_{}.png", version));
+        fs::write(&path, png)
+            .with_context(|| format!("Failed to write thumbnail: {}", path.display()))?;
+        Ok(path)
+    }
+
+    pub fn read_thumbnail(&self, id: i64, version: i32) -> Result<Option<Vec<u8>>> {
+        let path = self.schematic_dir(id)?.join(format!("thumbnail_{}.png", version));
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read(&path)?))
+    }
+
+    fn pack_colors_path(&self, key: &str) -> Result<PathBuf> {
+        let dir = self.data_dir.join("pack_colors");
+        if !dir.exists() {
+            fs::create_dir_all(&dir).context("Failed to create pack colors directory")?;
+        }
+        Ok(dir.join(format!("{}.json", key)))
+    }
+
+    pub fn save_pack_colors(&self, key: &str, json: &str) -> Result<PathBuf> {
+        let path = self.pack_colors_path(key)?;
+        fs::write(&path, json)
+            .with_context(|| format!("Failed to write pack colors: {}", path.display()))?;
+        Ok(path)
+    }
+
+    pub fn read_pack_colors(&self, key: &str) -> Result<Option<String>> {
+        let path = self.pack_colors_path(key)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read_to_string(&path)?))
+    }
+
     pub fn get_schematic_value(
         &self,
         id: i64,
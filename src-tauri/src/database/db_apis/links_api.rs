@@ -0,0 +1,92 @@
+use crate::database::db_control::DatabaseState;
+use crate::database::db_data::SchematicLink;
+use anyhow::Result;
+use r2d2::PooledConnection;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::params;
+use tauri::State;
+
+// 关联来源，重新拆分时只替换 split 一类
+pub const SPLIT_LINK: &str = "split";
+pub const MAP_ART_LINK: &str = "map_art";
+pub const TERRAIN_LINK: &str = "terrain";
+
+pub fn new_schematic_link(
+    conn: &mut PooledConnection<SqliteConnectionManager>,
+    parent_id: i64,
+    child_id: i64,
+    part_index: i32,
+    offset: [i32; 3],
+    source: &str,
+) -> Result<i64> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        r#"INSERT OR REPLACE INTO schematic_links (
+            parent_id, child_id, part_index, offset_x, offset_y, offset_z, source
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+        params![parent_id, child_id, part_index, offset[0], offset[1], offset[2], source],
+    )?;
+    let rowid = tx.last_insert_rowid();
+    tx.commit()?;
+
+    Ok(rowid)
+}
+
+/// 在同一事务里删除同来源的旧关联并写入新关联，旧的子蓝图仍保留在库里
+/// links 为 (child_id, part_index, offset)
+pub fn replace_schematic_links(
+    conn: &mut PooledConnection<SqliteConnectionManager>,
+    parent_id: i64,
+    source: &str,
+    links: &[(i64, i32, [i32; 3])],
+) -> Result<usize> {
+    let tx = conn.transaction()?;
+    let deleted = tx.execute(
+        "DELETE FROM schematic_links WHERE parent_id = ?1 AND source = ?2",
+        params![parent_id, source],
+    )?;
+    for (child_id, part_index, offset) in links {
+        tx.execute(
+            r#"INSERT OR REPLACE INTO schematic_links (
+                parent_id, child_id, part_index, offset_x, offset_y, offset_z, source
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+            params![parent_id, child_id, part_index, offset[0], offset[1], offset[2], source],
+        )?;
+    }
+    tx.commit()?;
+    Ok(deleted)
+}
+
+pub fn find_schematic_links(
+    conn: &mut PooledConnection<SqliteConnectionManager>,
+    parent_id: i64,
+) -> Result<Vec<SchematicLink>> {
+    let mut stmt = conn.prepare(
+        r#"SELECT l.* FROM schematic_links l
+        JOIN schematics s ON s.id = l.child_id
+        WHERE l.parent_id = ? AND s.is_deleted = FALSE
+        ORDER BY l.part_index"#,
+    )?;
+    let links = stmt
+        .query_map([parent_id], |row| {
+            Ok(SchematicLink {
+                id: row.get("id")?,
+                parent_id: row.get("parent_id")?,
+                child_id: row.get("child_id")?,
+                part_index: row.get("part_index")?,
+                offset: [row.get("offset_x")?, row.get("offset_y")?, row.get("offset_z")?],
+                source: row.get::<_, Option<String>>("source")?.unwrap_or_default(),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(links)
+}
+
+#[tauri::command]
+pub fn get_schematic_links(
+    db: State<'_, DatabaseState>,
+    parent_id: i64,
+) -> Result<Vec<SchematicLink>, String> {
+    let mut conn = db.0.get().map_err(|e| e.to_string())?;
+    find_schematic_links(&mut conn, parent_id).map_err(|e| e.to_string())
+}
@@ -1,5 +1,7 @@
 pub mod history_api;
+pub mod links_api;
 pub mod logs_api;
 pub mod schematic_data_api;
 pub mod schematics_api;
+pub mod theme_api;
 pub mod user_api;
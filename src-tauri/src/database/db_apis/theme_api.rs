@@ -0,0 +1,116 @@
+use crate::database::db_control::DatabaseState;
+use crate::database::db_data::ThemePreset;
+use anyhow::Result;
+use r2d2::PooledConnection;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Row};
+use tauri::State;
+
+fn theme_from_row(row: &Row) -> rusqlite::Result<ThemePreset> {
+    let pairs: String = row.get("pairs")?;
+    let column = row.as_ref().column_index("pairs")?;
+    let pairs = serde_json::from_str(&pairs).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(column, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    Ok(ThemePreset {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        description: row.get("description")?,
+        pairs,
+        builtin: row.get("builtin")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+pub fn find_theme_presets(
+    conn: &mut PooledConnection<SqliteConnectionManager>,
+) -> Result<Vec<ThemePreset>> {
+    let mut stmt = conn.prepare("SELECT * FROM theme_presets ORDER BY builtin DESC, id")?;
+    let presets = stmt
+        .query_map([], theme_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(presets)
+}
+
+pub fn find_theme_preset(
+    conn: &mut PooledConnection<SqliteConnectionManager>,
+    id: i64,
+) -> Result<ThemePreset> {
+    let preset = conn.query_row(
+        "SELECT * FROM theme_presets WHERE id = ?",
+        params![id],
+        theme_from_row,
+    )?;
+    Ok(preset)
+}
+
+/// id 为 0 时新建，否则更新，内置预设不可修改
+pub fn save_theme_preset_data(
+    conn: &mut PooledConnection<SqliteConnectionManager>,
+    preset: ThemePreset,
+) -> Result<i64> {
+    let pairs = serde_json::to_string(&preset.pairs)?;
+    let tx = conn.transaction()?;
+    let id = if preset.id == 0 {
+        tx.execute(
+            "INSERT INTO theme_presets (name, description, pairs) VALUES (?1, ?2, ?3)",
+            params![preset.name, preset.description, pairs],
+        )?;
+        tx.last_insert_rowid()
+    } else {
+        let updated = tx.execute(
+            r#"UPDATE theme_presets
+            SET
+                name = ?1,
+                description = ?2,
+                pairs = ?3,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?4 AND builtin = FALSE"#,
+            params![preset.name, preset.description, pairs, preset.id],
+        )?;
+        if updated == 0 {
+            anyhow::bail!("theme preset {} not found or builtin", preset.id);
+        }
+        preset.id
+    };
+    tx.commit()?;
+    Ok(id)
+}
+
+pub fn delete_theme_preset_data(
+    conn: &mut PooledConnection<SqliteConnectionManager>,
+    id: i64,
+) -> Result<i64> {
+    let tx = conn.transaction()?;
+    let deleted = tx.execute(
+        "DELETE FROM theme_presets WHERE id = ? AND builtin = FALSE",
+        params![id],
+    )?;
+    tx.commit()?;
+    if deleted == 0 {
+        anyhow::bail!("theme preset {} not found or builtin", id);
+    }
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn get_theme_presets(db: State<'_, DatabaseState>) -> Result<Vec<ThemePreset>, String> {
+    let mut conn = db.0.get().map_err(|e| e.to_string())?;
+    find_theme_presets(&mut conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn save_theme_preset(
+    db: State<'_, DatabaseState>,
+    preset: ThemePreset,
+) -> Result<i64, String> {
+    let mut conn = db.0.get().map_err(|e| e.to_string())?;
+    save_theme_preset_data(&mut conn, preset).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_theme_preset(db: State<'_, DatabaseState>, id: i64) -> Result<i64, String> {
+    let mut conn = db.0.get().map_err(|e| e.to_string())?;
+    delete_theme_preset_data(&mut conn, id).map_err(|e| e.to_string())
+}
@@ -129,6 +129,43 @@ pub fn init_db(app_handle: &AppHandle) -> Result<DatabaseState> {
             cloud INTEGER DEFAULT 0
         );
 
+        CREATE TABLE IF NOT EXISTS schematic_links (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            parent_id INTEGER NOT NULL,
+            child_id INTEGER NOT NULL,
+            part_index INTEGER DEFAULT 0,
+            offset_x INTEGER DEFAULT 0, -- 子蓝图在父蓝图中的位置
+            offset_y INTEGER DEFAULT 0,
+            offset_z INTEGER DEFAULT 0,
+            source TEXT DEFAULT '', -- 关联来源，取值见 links_api
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (parent_id) REFERENCES schematics (id) ON DELETE CASCADE,
+            FOREIGN KEY (child_id) REFERENCES schematics (id) ON DELETE CASCADE,
+
+            UNIQUE(parent_id, child_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_schematic_links
+        ON schematic_links(parent_id, part_index);
+
+        CREATE TABLE IF NOT EXISTS theme_presets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            description TEXT DEFAULT '',
+            pairs TEXT DEFAULT '[]', -- 材质对 [{"from":"oak","to":"dark_oak"}]
+            builtin BLOB DEFAULT FALSE,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        INSERT INTO theme_presets (name, description, pairs, builtin)
+        SELECT column1, '', column2, TRUE FROM (VALUES
+            ('橡木 → 深色橡木', '[{"from":"oak","to":"dark_oak"}]'),
+            ('石砖 → 深板岩砖', '[{"from":"stone_brick","to":"deepslate_brick"}]'),
+            ('白色 → 黑色', '[{"from":"white","to":"black"}]')
+        )
+        WHERE NOT EXISTS (SELECT 1 FROM theme_presets WHERE builtin = TRUE);
+
         INSERT INTO user_data (id, nickname, avatar, qq, accessToken, openid, schematics, cloud)
         SELECT 1, '', '', '', '', '', 0, 0
         WHERE NOT EXISTS (SELECT 1 FROM user_data WHERE id = 1);
@@ -185,6 +222,8 @@ pub fn drop_all_tables_in_transaction(conn: &Connection) -> Result<()> {
         "user_data",
         "schematics_history",
         "schematic_data",
+        "schematic_links",
+        "theme_presets",
         "schematics"
     ];
 
@@ -196,6 +235,7 @@ pub fn drop_all_tables_in_transaction(conn: &Connection) -> Result<()> {
     let indexes = [
         "idx_schematics_history",
         "idx_requirements_schematic",
+        "idx_schematic_links",
         "idx_logs_search",
         "idx_schematic_search"
     ];
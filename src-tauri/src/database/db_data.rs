@@ -51,6 +51,39 @@ pub struct UserData {
     pub cloud: i32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchematicLink {
+    pub id: i64,
+    pub parent_id: i64,
+    pub child_id: i64,
+    pub part_index: i32,
+    pub offset: [i32; 3],
+    pub source: String,
+}
+
+/// 材质对，按方块家族展开成逐个 id 的替换
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThemePair {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThemePreset {
+    #[serde(default)]
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub pairs: Vec<ThemePair>,
+    #[serde(default)]
+    pub builtin: bool,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
 fn default_type() -> i32 {
     -1
 }
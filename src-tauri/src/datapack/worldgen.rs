@@ -0,0 +1,304 @@
+use crate::create::to_create_schematic::ToCreateSchematic;
+use crate::utils::structure_tiles::{block_map, tile_schematic, StructureTile, StructureTiles, STRUCTURE_LIMIT};
+use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos, BlockStatePos};
+use crate::utils::schematic_data::{SchematicData, SchematicError};
+use crate::utils::extend_write::to_bytes_gzip;
+use crate::utils::tile_entities::TileEntities;
+use crate::word_edit::to_we_schematic::ToWeSchematic;
+use fastnbt::Value;
+use fastnbt::Value::Compound;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+// 1.21 jigsaw 结构的深度与半径上限
+const MAX_JIGSAW_DEPTH: i32 = 20;
+const MAX_DISTANCE_FROM_CENTER: i32 = 128;
+
+#[derive(Debug, Clone)]
+pub struct WorldgenOptions {
+    pub namespace: String,
+    pub pack_format: i32,
+    pub spacing: i32,
+    pub separation: i32,
+    pub biomes: Vec<String>,
+    pub include_air: bool,
+}
+
+impl Default for WorldgenOptions {
+    fn default() -> Self {
+        Self {
+            namespace: "mcs".to_string(),
+            pack_format: 48,
+            spacing: 32,
+            separation: 8,
+            biomes: vec!["#minecraft:is_overworld".to_string()],
+            include_air: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Edge {
+    X,
+    Y,
+    Z,
+}
+
+impl Edge {
+    fn orientations(&self) -> (&'static str, &'static str) {
+        match self {
+            Edge::X => ("east_up", "west_up"),
+            Edge::Y => ("up_north", "down_north"),
+            Edge::Z => ("south_up", "north_up"),
+        }
+    }
+
+    fn joint(&self) -> &'static str {
+        match self {
+            Edge::Y => "aligned",
+            _ => "rollable",
+        }
+    }
+}
+
+pub struct WorldgenStructure {
+    pub name: String,
+    pub data: SchematicData,
+}
+
+pub fn sanitize_name(name: &str) -> String {
+    let name: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "_-.".contains(c) { c } else { '_' })
+        .collect();
+    name.trim_matches('_').to_string()
+}
+
+fn piece_name(structure: &str, tile: &StructureTile) -> String {
+    format!("{}/piece_{}_{}_{}", structure, tile.index.0, tile.index.1, tile.index.2)
+}
+
+// 生成树：x 方向全连，z 方向只在 x=0 列连，y 方向只在 x=0,z=0 柱上连
+fn parent_of(index: (i32, i32, i32)) -> Option<((i32, i32, i32), Edge)> {
+    let (i, j, k) = index;
+    if i > 0 {
+        Some(((i - 1, j, k), Edge::X))
+    } else if k > 0 {
+        Some(((0, j, k - 1), Edge::Z))
+    } else if j > 0 {
+        Some(((0, j - 1, 0), Edge::Y))
+    } else {
+        None
+    }
+}
+
+fn set_jigsaw(
+    tile: &mut StructureTile,
+    pos: (i32, i32, i32),
+    orientation: &str,
+    nbt: HashMap<String, Value>,
+) {
+    let index = block_map(tile);
+    let original = index
+        .get(&pos)
+        .map(|i| tile.data.blocks.elements[*i].block.clone());
+    let final_state = original
+        .as_ref()
+        .map(|b| ToWeSchematic::block_to_string(b))
+        .unwrap_or_else(|| "minecraft:air".to_string());
+
+    let mut properties = BTreeMap::new();
+    properties.insert(Arc::<str>::from("orientation"), Arc::<str>::from(orientation));
+    let jigsaw = Arc::new(BlockData {
+        id: BlockId { name: Arc::from("minecraft:jigsaw") },
+        properties,
+    });
+    let block_pos = BlockPos { x: pos.0, y: pos.1, z: pos.2 };
+    match index.get(&pos) {
+        Some(i) => tile.data.blocks.elements[*i] = BlockStatePos::new(block_pos, jigsaw),
+        None => tile.data.blocks.elements.push_back(BlockStatePos::new(block_pos, jigsaw)),
+    }
+
+    let mut nbt = nbt;
+    nbt.insert("id".to_string(), Value::String("minecraft:jigsaw".to_string()));
+    nbt.insert("final_state".to_string(), Value::String(final_state));
+    nbt.insert("placement_priority".to_string(), Value::Int(0));
+    nbt.insert("selection_priority".to_string(), Value::Int(0));
+    let elements = &mut tile.data.tile_entities_list.elements;
+    elements.retain(|te| (te.pos.x, te.pos.y, te.pos.z) != pos);
+    elements.push(TileEntities { pos: block_pos, nbt: Compound(nbt) });
+}
+
+fn wire_jigsaws(ns: &str, structure: &str, tiles: &mut StructureTiles) {
+    let counts = tiles.counts;
+    let sizes: HashMap<(i32, i32, i32), (i32, i32, i32)> = tiles
+        .tiles
+        .iter()
+        .map(|t| (t.index, (t.size.width, t.size.height, t.size.length)))
+        .collect();
+    let names: HashMap<(i32, i32, i32), String> = tiles
+        .tiles
+        .iter()
+        .map(|t| (t.index, format!("{}:{}", ns, piece_name(structure, t))))
+        .collect();
+
+    for idx in 0..tiles.tiles.len() {
+        let index = tiles.tiles[idx].index;
+        let Some((parent, edge)) = parent_of(index) else {
+            continue;
+        };
+        let (pw, ph, pl) = sizes[&parent];
+        // 父子两侧的连接点位于同一相对坐标，只在连接轴上相差一格
+        let (parent_pos, child_pos) = match edge {
+            Edge::X => ((pw - 1, 0, 0), (0, 0, 0)),
+            Edge::Z => ((0, 0, pl - 1), (0, 0, 0)),
+            Edge::Y => ((0, ph - 1, 0), (0, 0, 0)),
+        };
+        let (parent_orientation, child_orientation) = edge.orientations();
+        let joint = Value::String(edge.joint().to_string());
+        let link_name = format!("{}_link", names[&index]);
+
+        let child = &mut tiles.tiles[idx];
+        set_jigsaw(
+            child,
+            child_pos,
+            child_orientation,
+            HashMap::from([
+                ("name".to_string(), Value::String(link_name.clone())),
+                ("target".to_string(), Value::String("minecraft:empty".to_string())),
+                ("pool".to_string(), Value::String("minecraft:empty".to_string())),
+                ("joint".to_string(), joint.clone()),
+            ]),
+        );
+
+        let parent_idx = ((parent.0 * counts.1 + parent.1) * counts.2 + parent.2) as usize;
+        let parent_tile = &mut tiles.tiles[parent_idx];
+        set_jigsaw(
+            parent_tile,
+            parent_pos,
+            parent_orientation,
+            HashMap::from([
+                ("name".to_string(), Value::String("minecraft:empty".to_string())),
+                ("target".to_string(), Value::String(link_name)),
+                ("pool".to_string(), Value::String(names[&index].clone())),
+                ("joint".to_string(), joint),
+            ]),
+        );
+    }
+}
+
+fn biomes_value(biomes: &[String]) -> serde_json::Value {
+    match biomes {
+        [] => json!("#minecraft:is_overworld"),
+        [single] if single.starts_with('#') => json!(single),
+        list => json!(list),
+    }
+}
+
+/// 生成包含结构 nbt 与 worldgen json 的数据包文件列表
+pub fn worldgen_datapack_files(
+    structures: Vec<WorldgenStructure>,
+    options: &WorldgenOptions,
+) -> Result<Vec<(String, Vec<u8>)>, SchematicError> {
+    let ns = options.namespace.as_str();
+    let structure_dir = if options.pack_format >= 45 { "structure" } else { "structures" };
+    let mut files = Vec::new();
+    let mut set_entries = Vec::new();
+
+    for structure in structures {
+        let name = structure.name;
+        let mut tiles = tile_schematic(&structure.data, STRUCTURE_LIMIT);
+        let depth = (tiles.counts.0 - 1) + (tiles.counts.1 - 1) + (tiles.counts.2 - 1);
+        if depth > MAX_JIGSAW_DEPTH {
+            return Err(SchematicError::InvalidFormat(
+                "Structure is too large for jigsaw generation",
+            ));
+        }
+        // 原版从起始块包围盒中心量起，起始块在角上，需覆盖到对面最远的边，超出部分会被截掉
+        let (start_width, start_length) = tiles
+            .get(0, 0, 0)
+            .map(|t| (t.size.width, t.size.length))
+            .unwrap_or((0, 0));
+        let extent = (tiles.size.width - start_width / 2).max(tiles.size.length - start_length / 2);
+        if extent > MAX_DISTANCE_FROM_CENTER {
+            return Err(SchematicError::InvalidFormat(
+                "Structure is wider than the jigsaw max_distance_from_center limit",
+            ));
+        }
+        wire_jigsaws(ns, &name, &mut tiles);
+
+        for tile in &tiles.tiles {
+            let piece = piece_name(&name, tile);
+            let nbt = ToCreateSchematic::structure_tile(tile, options.include_air)?;
+            files.push((
+                format!("data/{}/{}/{}.nbt", ns, structure_dir, piece),
+                to_bytes_gzip(&nbt)?,
+            ));
+            let pool = json!({
+                "fallback": "minecraft:empty",
+                "elements": [{
+                    "weight": 1,
+                    "element": {
+                        "element_type": "minecraft:single_pool_element",
+                        "location": format!("{}:{}", ns, piece),
+                        "projection": "rigid",
+                        "processors": "minecraft:empty"
+                    }
+                }]
+            });
+            files.push((
+                format!("data/{}/worldgen/template_pool/{}.json", ns, piece),
+                serde_json::to_vec_pretty(&pool)?,
+            ));
+        }
+
+        let start = tiles.get(0, 0, 0).map(|t| piece_name(&name, t)).unwrap_or_default();
+        let radius = (extent + 16).min(MAX_DISTANCE_FROM_CENTER);
+        let worldgen = json!({
+            "type": "minecraft:jigsaw",
+            "biomes": biomes_value(&options.biomes),
+            "step": "surface_structures",
+            "spawn_overrides": {},
+            "terrain_adaptation": "beard_thin",
+            "start_pool": format!("{}:{}", ns, start),
+            "size": depth.max(1),
+            "start_height": { "absolute": 0 },
+            "project_start_to_heightmap": "WORLD_SURFACE_WG",
+            "max_distance_from_center": radius,
+            "use_expansion_hack": false
+        });
+        files.push((
+            format!("data/{}/worldgen/structure/{}.json", ns, name),
+            serde_json::to_vec_pretty(&worldgen)?,
+        ));
+        set_entries.push(json!({ "structure": format!("{}:{}", ns, name), "weight": 1 }));
+    }
+
+    let salt = ns.bytes().fold(0i64, |acc, b| (acc * 31 + b as i64) % 2_147_483_647);
+    let spacing = options.spacing.clamp(1, 4096);
+    let separation = options.separation.clamp(0, spacing - 1);
+    let structure_set = json!({
+        "structures": set_entries,
+        "placement": {
+            "type": "minecraft:random_spread",
+            "spacing": spacing,
+            "separation": separation,
+            "salt": salt
+        }
+    });
+    files.push((
+        format!("data/{}/worldgen/structure_set/{}.json", ns, ns),
+        serde_json::to_vec_pretty(&structure_set)?,
+    ));
+
+    let mcmeta = json!({
+        "pack": {
+            "pack_format": options.pack_format,
+            "description": format!("MCSTools structures ({})", ns)
+        }
+    });
+    files.insert(0, ("pack.mcmeta".to_string(), serde_json::to_vec_pretty(&mcmeta)?));
+    Ok(files)
+}
@@ -0,0 +1,502 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use image::RgbaImage;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// 颜色统一为 0-255 的浮点
+#[derive(Debug, Clone)]
+pub struct ImportedTriangle {
+    pub positions: [[f32; 3]; 3],
+    pub uvs: Option<[[f32; 2]; 3]>,
+    pub colors: Option<[[f32; 3]; 3]>,
+    pub material: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedMaterial {
+    pub color: [f32; 3],
+    pub texture: Option<RgbaImage>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportedMesh {
+    pub triangles: Vec<ImportedTriangle>,
+    pub materials: Vec<ImportedMaterial>,
+}
+
+impl ImportedMesh {
+    /// 重心坐标处的颜色：材质贴图 > 顶点色 > 材质颜色，都没有时返回 None
+    pub fn color_at(&self, triangle: &ImportedTriangle, bary: [f32; 3]) -> Option<[f32; 3]> {
+        let material = triangle.material.and_then(|m| self.materials.get(m));
+        if let (Some(texture), Some(uvs)) = (material.and_then(|m| m.texture.as_ref()), triangle.uvs) {
+            let u = uvs[0][0] * bary[0] + uvs[1][0] * bary[1] + uvs[2][0] * bary[2];
+            let v = uvs[0][1] * bary[0] + uvs[1][1] * bary[1] + uvs[2][1] * bary[2];
+            let (w, h) = (texture.width(), texture.height());
+            let x = ((u.rem_euclid(1.0) * w as f32) as u32).min(w - 1);
+            let y = ((v.rem_euclid(1.0) * h as f32) as u32).min(h - 1);
+            let p = texture.get_pixel(x, y).0;
+            if p[3] < 128 {
+                return None;
+            }
+            let tint = material.map(|m| m.color).unwrap_or([255.0; 3]);
+            return Some([0, 1, 2].map(|i| p[i] as f32 * tint[i] / 255.0));
+        }
+        if let Some(colors) = triangle.colors {
+            return Some([0, 1, 2].map(|i| colors[0][i] * bary[0] + colors[1][i] * bary[1] + colors[2][i] * bary[2]));
+        }
+        material.map(|m| m.color)
+    }
+}
+
+pub fn load_model(path: &Path) -> Result<ImportedMesh> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mesh = match extension.as_str() {
+        "obj" => load_obj(path)?,
+        "stl" => load_stl(&fs::read(path)?)?,
+        "gltf" => {
+            let document: JsonValue = serde_json::from_slice(&fs::read(path)?)?;
+            load_gltf(&document, None, path.parent())?
+        }
+        "glb" => load_glb(&fs::read(path)?, path.parent())?,
+        other => anyhow::bail!("unsupported model format: {}", other),
+    };
+    if mesh.triangles.is_empty() {
+        anyhow::bail!("model contains no triangles");
+    }
+    Ok(mesh)
+}
+
+fn floats(parts: &[&str]) -> Vec<f32> {
+    parts.iter().filter_map(|p| p.parse().ok()).collect()
+}
+
+fn load_texture(path: &Path) -> Option<RgbaImage> {
+    Some(image::load_from_memory(&fs::read(path).ok()?).ok()?.to_rgba8())
+}
+
+fn load_mtl(path: &Path, materials: &mut Vec<ImportedMaterial>, names: &mut HashMap<String, usize>) {
+    let Ok(text) = fs::read_to_string(path) else {
+        return;
+    };
+    let dir = path.parent().unwrap_or(Path::new(""));
+    for line in text.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        match parts.as_slice() {
+            ["newmtl", name, ..] => {
+                names.insert(name.to_string(), materials.len());
+                materials.push(ImportedMaterial { color: [255.0; 3], texture: None });
+            }
+            ["Kd", rest @ ..] => {
+                if let (Some(m), [r, g, b, ..]) = (materials.last_mut(), floats(rest).as_slice()) {
+                    m.color = [r * 255.0, g * 255.0, b * 255.0];
+                }
+            }
+            ["map_Kd", .., file] => {
+                if let Some(m) = materials.last_mut() {
+                    m.texture = load_texture(&dir.join(file));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn load_obj(path: &Path) -> Result<ImportedMesh> {
+    let text = fs::read_to_string(path)?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut colors: Vec<Option<[f32; 3]>> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut mesh = ImportedMesh::default();
+    let mut names = HashMap::new();
+    let mut material = None;
+
+    // OBJ 下标从 1 开始，负数表示从末尾倒数
+    let resolve = |index: &str, len: usize| -> Option<usize> {
+        let i: i64 = index.parse().ok()?;
+        let i = if i < 0 { len as i64 + i } else { i - 1 };
+        (0..len as i64).contains(&i).then_some(i as usize)
+    };
+
+    for line in text.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        match parts.as_slice() {
+            ["v", rest @ ..] => {
+                let v = floats(rest);
+                if v.len() < 3 {
+                    continue;
+                }
+                positions.push([v[0], v[1], v[2]]);
+                // 顶点色扩展：v x y z r g b，数值为 0-1
+                colors.push((v.len() >= 6).then(|| [v[3] * 255.0, v[4] * 255.0, v[5] * 255.0]));
+            }
+            ["vt", rest @ ..] => {
+                let v = floats(rest);
+                if v.len() >= 2 {
+                    // 贴图坐标原点在左下角
+                    uvs.push([v[0], 1.0 - v[1]]);
+                }
+            }
+            ["mtllib", files @ ..] => {
+                for file in files {
+                    load_mtl(&dir.join(file), &mut mesh.materials, &mut names);
+                }
+            }
+            ["usemtl", name, ..] => material = names.get(*name).copied(),
+            ["f", corners @ ..] => {
+                let corners: Vec<(usize, Option<usize>)> = corners
+                    .iter()
+                    .filter_map(|c| {
+                        let mut refs = c.split('/');
+                        let v = resolve(refs.next()?, positions.len())?;
+                        let t = refs.next().and_then(|t| resolve(t, uvs.len()));
+                        Some((v, t))
+                    })
+                    .collect();
+                for i in 1..corners.len().saturating_sub(1) {
+                    let tri = [corners[0], corners[i], corners[i + 1]];
+                    let vertex_colors = tri.map(|(v, _)| colors[v]);
+                    mesh.triangles.push(ImportedTriangle {
+                        positions: tri.map(|(v, _)| positions[v]),
+                        uvs: tri
+                            .iter()
+                            .all(|(_, t)| t.is_some())
+                            .then(|| tri.map(|(_, t)| uvs[t.unwrap_or(0)])),
+                        colors: vertex_colors
+                            .iter()
+                            .all(|c| c.is_some())
+                            .then(|| vertex_colors.map(|c| c.unwrap_or([255.0; 3]))),
+                        material,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(mesh)
+}
+
+fn load_stl(bytes: &[u8]) -> Result<ImportedMesh> {
+    let mut mesh = ImportedMesh::default();
+    let binary_count = bytes
+        .get(80..84)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+    if let Some(count) = binary_count.filter(|c| 84 + c * 50 == bytes.len()) {
+        for i in 0..count {
+            let face = &bytes[84 + i * 50..84 + (i + 1) * 50];
+            let f = |o: usize| f32::from_le_bytes([face[o], face[o + 1], face[o + 2], face[o + 3]]);
+            let vertex = |v: usize| [f(12 + v * 12), f(16 + v * 12), f(20 + v * 12)];
+            mesh.triangles.push(ImportedTriangle {
+                positions: [vertex(0), vertex(1), vertex(2)],
+                uvs: None,
+                colors: None,
+                material: None,
+            });
+        }
+        return Ok(mesh);
+    }
+
+    let text = String::from_utf8_lossy(bytes);
+    let mut facet = Vec::with_capacity(3);
+    for line in text.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if let ["vertex", rest @ ..] = parts.as_slice() {
+            let v = floats(rest);
+            if v.len() >= 3 {
+                facet.push([v[0], v[1], v[2]]);
+            }
+        } else if parts.first() == Some(&"endfacet") {
+            if facet.len() == 3 {
+                mesh.triangles.push(ImportedTriangle {
+                    positions: [facet[0], facet[1], facet[2]],
+                    uvs: None,
+                    colors: None,
+                    material: None,
+                });
+            }
+            facet.clear();
+        }
+    }
+    Ok(mesh)
+}
+
+fn load_glb(bytes: &[u8], dir: Option<&Path>) -> Result<ImportedMesh> {
+    if bytes.len() < 20 || &bytes[0..4] != b"glTF" {
+        anyhow::bail!("invalid glb header");
+    }
+    let u32_at = |o: usize| -> Option<usize> {
+        Some(u32::from_le_bytes(bytes.get(o..o + 4)?.try_into().ok()?) as usize)
+    };
+    let mut offset = 12;
+    let mut document = None;
+    let mut bin = None;
+    while let (Some(length), Some(kind)) = (u32_at(offset), bytes.get(offset + 4..offset + 8)) {
+        let chunk = bytes.get(offset + 8..offset + 8 + length).context("truncated glb chunk")?;
+        match kind {
+            b"JSON" => document = Some(serde_json::from_slice::<JsonValue>(chunk)?),
+            b"BIN\0" => bin = Some(chunk.to_vec()),
+            _ => {}
+        }
+        offset += 8 + length;
+    }
+    load_gltf(&document.context("glb has no JSON chunk")?, bin, dir)
+}
+
+type Matrix = [f32; 16];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+
+/// 列主序矩阵相乘
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut out = [0.0; 16];
+    for c in 0..4 {
+        for r in 0..4 {
+            out[c * 4 + r] = (0..4).map(|k| a[k * 4 + r] * b[c * 4 + k]).sum();
+        }
+    }
+    out
+}
+
+fn transform(m: &Matrix, p: [f32; 3]) -> [f32; 3] {
+    [0, 1, 2].map(|r| m[r] * p[0] + m[4 + r] * p[1] + m[8 + r] * p[2] + m[12 + r])
+}
+
+fn node_matrix(node: &JsonValue) -> Matrix {
+    let numbers = |key: &str| -> Option<Vec<f32>> {
+        Some(node.get(key)?.as_array()?.iter().filter_map(|v| v.as_f64()).map(|v| v as f32).collect())
+    };
+    if let Some(m) = numbers("matrix").filter(|m| m.len() == 16) {
+        return m.try_into().unwrap_or(IDENTITY);
+    }
+    let t = numbers("translation").filter(|v| v.len() == 3).unwrap_or(vec![0.0; 3]);
+    let q = numbers("rotation").filter(|v| v.len() == 4).unwrap_or(vec![0.0, 0.0, 0.0, 1.0]);
+    let s = numbers("scale").filter(|v| v.len() == 3).unwrap_or(vec![1.0; 3]);
+    let (x, y, z, w) = (q[0], q[1], q[2], q[3]);
+    [
+        (1.0 - 2.0 * (y * y + z * z)) * s[0],
+        (2.0 * (x * y + z * w)) * s[0],
+        (2.0 * (x * z - y * w)) * s[0],
+        0.0,
+        (2.0 * (x * y - z * w)) * s[1],
+        (1.0 - 2.0 * (x * x + z * z)) * s[1],
+        (2.0 * (y * z + x * w)) * s[1],
+        0.0,
+        (2.0 * (x * z + y * w)) * s[2],
+        (2.0 * (y * z - x * w)) * s[2],
+        (1.0 - 2.0 * (x * x + y * y)) * s[2],
+        0.0,
+        t[0],
+        t[1],
+        t[2],
+        1.0,
+    ]
+}
+
+struct Gltf<'a> {
+    document: &'a JsonValue,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl Gltf<'_> {
+    fn view(&self, index: usize) -> Option<(&[u8], usize)> {
+        let view = self.document.get("bufferViews")?.get(index)?;
+        let buffer = self.buffers.get(view.get("buffer")?.as_u64()? as usize)?;
+        let offset = view.get("byteOffset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        let length = view.get("byteLength")?.as_u64()? as usize;
+        let stride = view.get("byteStride").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        Some((buffer.get(offset..offset + length)?, stride))
+    }
+
+    /// 读取 accessor 为浮点数组，归一化整数按 glTF 规则换算到 0-1
+    fn accessor(&self, index: usize) -> Option<Vec<Vec<f32>>> {
+        let accessor = self.document.get("accessors")?.get(index)?;
+        let count = accessor.get("count")?.as_u64()? as usize;
+        let components = match accessor.get("type")?.as_str()? {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            _ => return None,
+        };
+        let component_type = accessor.get("componentType")?.as_u64()?;
+        let normalized = accessor.get("normalized").and_then(|v| v.as_bool()).unwrap_or(false);
+        let size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => return None,
+        };
+        let (data, stride) = self.view(accessor.get("bufferView")?.as_u64()? as usize)?;
+        let offset = accessor.get("byteOffset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        let stride = if stride == 0 { size * components } else { stride };
+        let read = |o: usize| -> Option<f32> {
+            let b = data.get(o..o + size)?;
+            Some(match component_type {
+                5120 if normalized => (b[0] as i8 as f32 / 127.0).max(-1.0),
+                5120 => b[0] as i8 as f32,
+                5121 if normalized => b[0] as f32 / 255.0,
+                5121 => b[0] as f32,
+                5122 if normalized => (i16::from_le_bytes([b[0], b[1]]) as f32 / 32767.0).max(-1.0),
+                5122 => i16::from_le_bytes([b[0], b[1]]) as f32,
+                5123 if normalized => u16::from_le_bytes([b[0], b[1]]) as f32 / 65535.0,
+                5123 => u16::from_le_bytes([b[0], b[1]]) as f32,
+                5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32,
+                _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            })
+        };
+        (0..count)
+            .map(|i| (0..components).map(|c| read(offset + i * stride + c * size)).collect())
+            .collect()
+    }
+
+    fn image(&self, index: usize, dir: Option<&Path>) -> Option<RgbaImage> {
+        let image = self.document.get("images")?.get(index)?;
+        let bytes = if let Some(view) = image.get("bufferView").and_then(|v| v.as_u64()) {
+            self.view(view as usize)?.0.to_vec()
+        } else {
+            load_uri(image.get("uri")?.as_str()?, dir)?
+        };
+        Some(image::load_from_memory(&bytes).ok()?.to_rgba8())
+    }
+}
+
+fn load_uri(uri: &str, dir: Option<&Path>) -> Option<Vec<u8>> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (_, encoded) = data.split_once(";base64,")?;
+        return base64::engine::general_purpose::STANDARD.decode(encoded).ok();
+    }
+    fs::read(dir?.join(uri)).ok()
+}
+
+fn load_gltf(document: &JsonValue, bin: Option<Vec<u8>>, dir: Option<&Path>) -> Result<ImportedMesh> {
+    let mut bin = bin;
+    let buffers = document
+        .get("buffers")
+        .and_then(|b| b.as_array())
+        .map(|list| {
+            list.iter()
+                .map(|b| match b.get("uri").and_then(|u| u.as_str()) {
+                    Some(uri) => load_uri(uri, dir).unwrap_or_default(),
+                    None => bin.take().unwrap_or_default(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let gltf = Gltf { document, buffers };
+
+    let mut mesh = ImportedMesh::default();
+    if let Some(materials) = document.get("materials").and_then(|m| m.as_array()) {
+        for material in materials {
+            let pbr = material.get("pbrMetallicRoughness");
+            let factor: Vec<f32> = pbr
+                .and_then(|p| p.get("baseColorFactor"))
+                .and_then(|f| f.as_array())
+                .map(|f| f.iter().filter_map(|v| v.as_f64()).map(|v| v as f32).collect())
+                .unwrap_or_default();
+            let color = match factor.as_slice() {
+                [r, g, b, ..] => [r * 255.0, g * 255.0, b * 255.0],
+                _ => [255.0; 3],
+            };
+            let texture = pbr
+                .and_then(|p| p.get("baseColorTexture"))
+                .and_then(|t| t.get("index"))
+                .and_then(|i| document.get("textures")?.get(i.as_u64()? as usize))
+                .and_then(|t| t.get("source")?.as_u64())
+                .and_then(|source| gltf.image(source as usize, dir));
+            mesh.materials.push(ImportedMaterial { color, texture });
+        }
+    }
+
+    // 从场景根节点遍历，累积节点变换
+    let nodes = document.get("nodes").and_then(|n| n.as_array()).cloned().unwrap_or_default();
+    let scene = document.get("scene").and_then(|s| s.as_u64()).unwrap_or(0) as usize;
+    let roots: Vec<usize> = document
+        .get("scenes")
+        .and_then(|s| s.get(scene))
+        .and_then(|s| s.get("nodes"))
+        .and_then(|n| n.as_array())
+        .map(|n| n.iter().filter_map(|v| v.as_u64()).map(|v| v as usize).collect())
+        .unwrap_or_else(|| (0..nodes.len()).collect());
+    let mut stack: Vec<(usize, Matrix, usize)> = roots.into_iter().map(|n| (n, IDENTITY, 0)).collect();
+
+    while let Some((index, parent, depth)) = stack.pop() {
+        let Some(node) = nodes.get(index) else {
+            continue;
+        };
+        if depth > 64 {
+            continue;
+        }
+        let matrix = multiply(&parent, &node_matrix(node));
+        if let Some(children) = node.get("children").and_then(|c| c.as_array()) {
+            for child in children.iter().filter_map(|c| c.as_u64()) {
+                stack.push((child as usize, matrix, depth + 1));
+            }
+        }
+        let Some(primitives) = node
+            .get("mesh")
+            .and_then(|m| document.get("meshes")?.get(m.as_u64()? as usize))
+            .and_then(|m| m.get("primitives"))
+            .and_then(|p| p.as_array())
+        else {
+            continue;
+        };
+        for primitive in primitives {
+            if primitive.get("mode").and_then(|m| m.as_u64()).unwrap_or(4) != 4 {
+                continue;
+            }
+            let attributes = primitive.get("attributes");
+            let attribute = |name: &str| -> Option<Vec<Vec<f32>>> {
+                gltf.accessor(attributes?.get(name)?.as_u64()? as usize)
+            };
+            let Some(positions) = attribute("POSITION") else {
+                continue;
+            };
+            if positions.iter().any(|p| p.len() < 3) {
+                anyhow::bail!("POSITION accessor must be VEC3");
+            }
+            let positions: Vec<[f32; 3]> = positions
+                .iter()
+                .map(|p| transform(&matrix, [p[0], p[1], p[2]]))
+                .collect();
+            let uvs = attribute("TEXCOORD_0");
+            let colors = attribute("COLOR_0");
+            let indices: Vec<usize> = match primitive.get("indices").and_then(|i| i.as_u64()) {
+                Some(i) => gltf
+                    .accessor(i as usize)
+                    .context("invalid index accessor")?
+                    .iter()
+                    .map(|v| v[0] as usize)
+                    .collect(),
+                None => (0..positions.len()).collect(),
+            };
+            let material = primitive.get("material").and_then(|m| m.as_u64()).map(|m| m as usize);
+            for tri in indices.chunks_exact(3) {
+                if tri.iter().any(|&i| i >= positions.len()) {
+                    continue;
+                }
+                mesh.triangles.push(ImportedTriangle {
+                    positions: [positions[tri[0]], positions[tri[1]], positions[tri[2]]],
+                    uvs: uvs.as_ref().and_then(|uv| {
+                        let get = |i: usize| Some([*uv.get(i)?.first()?, *uv.get(i)?.get(1)?]);
+                        Some([get(tri[0])?, get(tri[1])?, get(tri[2])?])
+                    }),
+                    colors: colors.as_ref().and_then(|c| {
+                        let get = |i: usize| {
+                            let v = c.get(i)?;
+                            Some([*v.first()? * 255.0, *v.get(1)? * 255.0, *v.get(2)? * 255.0])
+                        };
+                        Some([get(tri[0])?, get(tri[1])?, get(tri[2])?])
+                    }),
+                    material,
+                });
+            }
+        }
+    }
+    Ok(mesh)
+}
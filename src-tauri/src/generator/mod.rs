@@ -0,0 +1,9 @@
+pub mod mesh_import;
+pub mod mural;
+pub mod note_blocks;
+pub mod palette;
+pub mod placement;
+pub mod shapes;
+pub mod terrain;
+pub mod text;
+pub mod voxel;
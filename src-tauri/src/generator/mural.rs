@@ -0,0 +1,109 @@
+use crate::generator::palette::{block_palette, PaletteFace};
+use crate::generator::placement::placed_schematic;
+use crate::map_art::generator::{prepare_image, quantize, DitherMode, MapArtOptions};
+use crate::map_art::palette::MapTone;
+use crate::utils::block_state_pos_list::BlockData;
+use crate::utils::minecraft_data::map_art_data::MapArtsData;
+use crate::utils::schematic_data::SchematicData;
+use anyhow::Result;
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MuralOptions {
+    pub width: u32,
+    pub height: u32,
+    pub rotation: u32,
+    pub dither: DitherMode,
+    // 观看者所在的一侧，up/down 为地板与天花板
+    pub facing: String,
+    // 浮雕层数，1 为平面；亮的像素更靠近观看者
+    pub depth: u32,
+    pub invert_depth: bool,
+    // 浮雕后方填实，否则只放最前面一层
+    pub fill: bool,
+    pub blocks: Vec<String>,
+    // 用户标记为不可用的方块
+    pub exclude: Vec<String>,
+}
+
+impl Default for MuralOptions {
+    fn default() -> Self {
+        Self {
+            width: 128,
+            height: 128,
+            rotation: 0,
+            dither: DitherMode::FloydSteinberg,
+            facing: "south".to_string(),
+            depth: 1,
+            invert_depth: false,
+            fill: true,
+            blocks: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+pub struct Mural {
+    pub data: SchematicData,
+    pub preview: RgbaImage,
+}
+
+/// 图片向右、向上以及朝向观看者的方向
+pub fn frame(facing: &str) -> Result<([i32; 3], [i32; 3], [i32; 3])> {
+    Ok(match facing.to_lowercase().as_str() {
+        "south" => ([1, 0, 0], [0, 1, 0], [0, 0, 1]),
+        "north" => ([-1, 0, 0], [0, 1, 0], [0, 0, -1]),
+        "east" => ([0, 0, -1], [0, 1, 0], [1, 0, 0]),
+        "west" => ([0, 0, 1], [0, 1, 0], [-1, 0, 0]),
+        "up" => ([1, 0, 0], [0, 0, -1], [0, 1, 0]),
+        "down" => ([1, 0, 0], [0, 0, 1], [0, -1, 0]),
+        other => anyhow::bail!("unknown mural facing: {}", other),
+    })
+}
+
+pub fn generate_mural(bytes: &[u8], map_arts: &MapArtsData, options: &MuralOptions) -> Result<Mural> {
+    let (right, up, out) = frame(&options.facing)?;
+    let palette = block_palette(map_arts, &options.blocks, &options.exclude, PaletteFace::Side)?;
+    let art_options = MapArtOptions {
+        width: options.width,
+        height: options.height,
+        rotation: options.rotation,
+        dither: options.dither,
+        ..Default::default()
+    };
+    let image = prepare_image(bytes, &art_options)?;
+    let (width, height) = (image.width(), image.height());
+    let pixels = quantize(&image, &palette, &art_options).pixels;
+
+    let blocks: Vec<Arc<BlockData>> = palette.colors.iter().map(|c| BlockData::from_name(&c.block)).collect();
+    let layers = options.depth.max(1);
+    let mut placed: Vec<([i32; 3], Arc<BlockData>)> = Vec::new();
+    let mut preview = RgbaImage::new(width, height);
+
+    for iy in 0..height {
+        for ix in 0..width {
+            let Some((entry, _)) = pixels[(iy * width + ix) as usize] else {
+                continue;
+            };
+            let c = palette.colors[entry].tone(MapTone::Normal);
+            preview.put_pixel(ix, iy, Rgba([c[0], c[1], c[2], 255]));
+
+            let p = image.get_pixel(ix, iy).0;
+            let luma = (0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32) / 255.0;
+            let luma = if options.invert_depth { 1.0 - luma } else { luma };
+            let front = (luma * (layers - 1) as f32).round() as i32;
+            let back = if options.fill { 0 } else { front };
+            let (u, v) = (ix as i32, (height - 1 - iy) as i32);
+            for d in back..=front {
+                let pos = [0, 1, 2].map(|a| right[a] * u + up[a] * v + out[a] * d);
+                placed.push((pos, blocks[entry].clone()));
+            }
+        }
+    }
+
+    let data = placed_schematic(placed);
+    Ok(Mural { data, preview })
+}
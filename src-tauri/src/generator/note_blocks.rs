@@ -0,0 +1,587 @@
+use crate::generator::placement::placed_schematic;
+use crate::utils::block_state_pos_list::BlockData;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::schematic_data::SchematicData;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct NbsNote {
+    pub tick: u32,
+    pub layer: u32,
+    pub instrument: u8,
+    pub key: u8,
+    pub velocity: u8,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NbsSong {
+    pub name: String,
+    pub author: String,
+    pub original_author: String,
+    pub description: String,
+    // 每秒 tick 数
+    pub tempo: f32,
+    pub length: u32,
+    pub layer_volumes: Vec<u8>,
+    pub notes: Vec<NbsNote>,
+}
+
+struct NbsReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> NbsReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self.pos + n;
+        let slice = self.bytes.get(self.pos..end).context("Unexpected end of nbs file")?;
+        self.pos = end;
+        Ok(slice)
+    }
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into()?))
+    }
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+    fn string(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        Ok(String::from_utf8_lossy(self.take(len)?).to_string())
+    }
+}
+
+/// 兼容旧版(无版本号)与 OpenNBS 1-5 版格式
+pub fn parse_nbs(bytes: &[u8]) -> Result<NbsSong> {
+    let mut r = NbsReader { bytes, pos: 0 };
+    let mut song = NbsSong::default();
+    let first = r.u16()?;
+    let version = if first == 0 { r.u8()? } else { 0 };
+    if version > 0 {
+        r.u8()?; // 原版乐器数量
+        if version >= 3 {
+            song.length = r.u16()? as u32;
+        }
+    } else {
+        song.length = first as u32;
+    }
+    let layer_count = r.u16()? as usize;
+    song.name = r.string()?;
+    song.author = r.string()?;
+    song.original_author = r.string()?;
+    song.description = r.string()?;
+    song.tempo = r.u16()? as f32 / 100.0;
+    r.take(3)?; // 自动保存、自动保存间隔、拍号
+    r.take(20)?; // 编辑统计
+    r.string()?; // 导入的 midi 文件名
+    if version >= 4 {
+        r.take(4)?; // 循环设置
+    }
+
+    let mut tick: i64 = -1;
+    loop {
+        let jump = r.u16()?;
+        if jump == 0 {
+            break;
+        }
+        tick += jump as i64;
+        let mut layer: i64 = -1;
+        loop {
+            let jump = r.u16()?;
+            if jump == 0 {
+                break;
+            }
+            layer += jump as i64;
+            let instrument = r.u8()?;
+            let key = r.u8()?;
+            let velocity = if version >= 4 {
+                let velocity = r.u8()?;
+                r.take(3)?; // 声像、微调音高
+                velocity
+            } else {
+                100
+            };
+            song.notes.push(NbsNote { tick: tick as u32, layer: layer as u32, instrument, key, velocity });
+        }
+    }
+    song.length = song.length.max(song.notes.iter().map(|n| n.tick + 1).max().unwrap_or(0));
+
+    // 旧文件可能缺少图层信息，缺省音量 100
+    song.layer_volumes = vec![100; layer_count];
+    read_layers(&mut r, version, &mut song.layer_volumes).ok();
+    if song.tempo <= 0.0 {
+        song.tempo = 10.0;
+    }
+    Ok(song)
+}
+
+fn read_layers(r: &mut NbsReader, version: u8, volumes: &mut [u8]) -> Result<()> {
+    for volume in volumes.iter_mut() {
+        r.string()?; // 图层名
+        if version >= 4 {
+            r.u8()?; // 锁定
+        }
+        *volume = r.u8()?;
+        if version >= 2 {
+            r.u8()?; // 声像
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteLayout {
+    // 一条主线，和弦按两音一条并排
+    Linear,
+    // 同 linear，但每 fold_length 格往返折叠
+    Folded,
+    // 每个 NBS 图层一条线路
+    MultiTrack,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoteBlockOptions {
+    pub layout: NoteLayout,
+    pub fold_length: u32,
+    // 线路底座、音符站与启动线所用方块
+    pub support_block: String,
+}
+
+impl Default for NoteBlockOptions {
+    fn default() -> Self {
+        Self { layout: NoteLayout::Linear, fold_length: 32, support_block: "stone".to_string() }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SongInfo {
+    pub name: String,
+    pub author: String,
+    pub tempo: f32,
+    pub length: u32,
+    pub seconds: f32,
+    pub notes: usize,
+    pub placed: usize,
+    // 静音、或同一红石刻超过两个音而放不下的音符
+    pub dropped: usize,
+    // 超出音符盒音域，按八度移入的音符
+    pub transposed: usize,
+    pub lanes: usize,
+}
+
+pub struct NoteBlockSong {
+    pub data: SchematicData,
+    pub info: SongInfo,
+}
+
+/// NBS 乐器编号对应的音色名及其下方方块
+const INSTRUMENTS: [(&str, &str); 16] = [
+    ("harp", "dirt"),
+    ("bass", "oak_planks"),
+    ("basedrum", "stone"),
+    ("snare", "sand"),
+    ("hat", "glass"),
+    ("guitar", "white_wool"),
+    ("flute", "clay"),
+    ("bell", "gold_block"),
+    ("chime", "packed_ice"),
+    ("xylophone", "bone_block"),
+    ("iron_xylophone", "iron_block"),
+    ("cow_bell", "soul_sand"),
+    ("didgeridoo", "pumpkin"),
+    ("bit", "emerald_block"),
+    ("banjo", "hay_block"),
+    ("pling", "glowstone"),
+];
+
+#[derive(Debug, Clone, Copy)]
+struct Note {
+    instrument: usize,
+    pitch: u8,
+}
+
+/// 一条线路上按时间(红石刻)排列的音符站，每站最多两个音
+type Lane = Vec<(u32, Vec<Note>)>;
+
+fn with_properties(name: &str, properties: &[(&str, String)]) -> Arc<BlockData> {
+    let base = BlockData::from_name(name);
+    let properties: BTreeMap<Arc<str>, Arc<str>> =
+        properties.iter().map(|(k, v)| (Arc::from(*k), Arc::from(v.as_str()))).collect();
+    Arc::new(BlockData { id: base.id.clone(), properties })
+}
+
+fn direction_name(d: [i32; 2]) -> &'static str {
+    match d {
+        [1, 0] => "east",
+        [-1, 0] => "west",
+        [0, 1] => "south",
+        _ => "north",
+    }
+}
+
+/// 中继器的 facing 指向输入端
+fn repeater(delay: u32, towards: [i32; 2]) -> Arc<BlockData> {
+    with_properties(
+        "repeater",
+        &[
+            ("delay", delay.to_string()),
+            ("facing", direction_name([-towards[0], -towards[1]]).to_string()),
+            ("locked", "false".to_string()),
+            ("powered", "false".to_string()),
+        ],
+    )
+}
+
+fn wire(connections: &[[i32; 2]]) -> Arc<BlockData> {
+    let side = |d: [i32; 2]| if connections.contains(&d) { "side" } else { "none" }.to_string();
+    with_properties(
+        "redstone_wire",
+        &[
+            ("east", side([1, 0])),
+            ("north", side([0, -1])),
+            ("power", "0".to_string()),
+            ("south", side([0, 1])),
+            ("west", side([-1, 0])),
+        ],
+    )
+}
+
+fn note_block(note: Note) -> Arc<BlockData> {
+    with_properties(
+        "note_block",
+        &[
+            ("instrument", INSTRUMENTS[note.instrument].0.to_string()),
+            ("note", note.pitch.to_string()),
+            ("powered", "false".to_string()),
+        ],
+    )
+}
+
+struct World {
+    blocks: HashMap<[i32; 3], Arc<BlockData>>,
+    support: Arc<BlockData>,
+}
+
+impl World {
+    fn put(&mut self, x: i32, y: i32, z: i32, state: Arc<BlockData>) {
+        self.blocks.insert([x, y, z], state);
+    }
+
+    /// 线路所在层 y=1，底座 y=0
+    fn put_line(&mut self, x: i32, z: i32, state: Arc<BlockData>) {
+        self.put(x, 1, z, state);
+        self.put(x, 0, z, self.support.clone());
+    }
+
+    fn put_note(&mut self, x: i32, z: i32, note: Note) {
+        let (_, below) = INSTRUMENTS[note.instrument];
+        self.put(x, 1, z, note_block(note));
+        self.put(x, 0, z, BlockData::from_name(below));
+        if below == "sand" {
+            self.put(x, -1, z, self.support.clone());
+        }
+    }
+}
+
+/// 一组并排线路的几何：第 r 行第 t 条线路的 z，偶数行向东、奇数行向西，
+/// 折返时内外圈顺序对调，红石粉 U 形弯不增加延迟
+struct Group {
+    base: i32,
+    lanes: i32,
+    fold: Option<i32>,
+}
+
+impl Group {
+    fn slot(&self, row: i32, lane: i32) -> i32 {
+        if row % 2 == 0 { lane } else { self.lanes - 1 - lane }
+    }
+
+    fn z(&self, row: i32, lane: i32) -> i32 {
+        self.base + 3 * (row * self.lanes + self.slot(row, lane)) + 1
+    }
+
+    fn x(&self, row: i32, col: i32) -> i32 {
+        match self.fold {
+            Some(fold) if row % 2 == 1 => fold - 1 - col,
+            _ => col,
+        }
+    }
+
+    fn dir(&self, row: i32) -> [i32; 2] {
+        if row % 2 == 0 { [1, 0] } else { [-1, 0] }
+    }
+}
+
+struct LaneWriter<'a> {
+    world: &'a mut World,
+    group: &'a Group,
+    lane: i32,
+    row: i32,
+    col: i32,
+}
+
+impl LaneWriter<'_> {
+    fn cell(&self) -> (i32, i32) {
+        (self.group.x(self.row, self.col), self.group.z(self.row, self.lane))
+    }
+
+    /// 当前行剩余不足 need 格时折返
+    fn reserve(&mut self, need: i32) {
+        let Some(fold) = self.group.fold else {
+            return;
+        };
+        if self.col + need <= fold {
+            return;
+        }
+        let dir = self.group.dir(self.row);
+        let (x_end, z_from) = (self.group.x(self.row, fold - 1), self.group.z(self.row, self.lane));
+        let z_to = self.group.z(self.row + 1, self.lane);
+        let turn_x = x_end + dir[0] * (1 + 2 * (self.group.lanes - 1 - self.group.slot(self.row, self.lane)));
+
+        let mut path = Vec::new();
+        let (mut x, _) = self.cell();
+        while x != turn_x {
+            path.push([x, z_from]);
+            x += dir[0];
+        }
+        for z in z_from..=z_to {
+            path.push([turn_x, z]);
+        }
+        let mut x = turn_x - dir[0];
+        while x != x_end {
+            path.push([x, z_to]);
+            x -= dir[0];
+        }
+        let (prev, next) = ([path[0][0] - dir[0], z_from], [x_end, z_to]);
+        for i in 0..path.len() {
+            let before = if i == 0 { prev } else { path[i - 1] };
+            let after = if i + 1 == path.len() { next } else { path[i + 1] };
+            let p = path[i];
+            let links = [[before[0] - p[0], before[1] - p[1]], [after[0] - p[0], after[1] - p[1]]];
+            self.world.put_line(p[0], p[1], wire(&links));
+        }
+        self.row += 1;
+        self.col = 0;
+    }
+
+    fn repeater(&mut self, delay: u32, last: bool) {
+        self.reserve(if last { 2 } else { 1 });
+        let (x, z) = self.cell();
+        self.world.put_line(x, z, repeater(delay, self.group.dir(self.row)));
+        self.col += 1;
+    }
+
+    /// 被中继器激活的实心方块，两侧各挂一个音符盒
+    fn station(&mut self, notes: &[Note]) {
+        let (x, z) = self.cell();
+        self.world.put_line(x, z, self.world.support.clone());
+        for (note, side) in notes.iter().zip([-1, 1]) {
+            self.world.put_note(x, z + side, *note);
+        }
+        self.col += 1;
+    }
+}
+
+/// 拆成若干个 1-4 刻的中继器
+fn split_delay(gap: u32) -> Vec<u32> {
+    let count = gap.div_ceil(4).max(1);
+    (0..count).map(|i| gap / count + u32::from(i < gap % count)).collect()
+}
+
+pub fn generate_note_blocks(song: &NbsSong, options: &NoteBlockOptions) -> Result<NoteBlockSong> {
+    let mut info = SongInfo {
+        name: song.name.clone(),
+        author: song.author.clone(),
+        tempo: song.tempo,
+        length: song.length,
+        seconds: song.length as f32 / song.tempo,
+        notes: song.notes.len(),
+        ..Default::default()
+    };
+
+    // 换算成红石刻(0.1 秒)，快于 10 tick/s 的曲子相邻 tick 会合并
+    let mut slots: BTreeMap<(u32, u32), Vec<Note>> = BTreeMap::new();
+    for n in &song.notes {
+        let volume = song.layer_volumes.get(n.layer as usize).copied().unwrap_or(100);
+        if n.velocity == 0 || volume == 0 {
+            info.dropped += 1;
+            continue;
+        }
+        let mut key = n.key as i32;
+        if !(33..=57).contains(&key) {
+            info.transposed += 1;
+            while key < 33 {
+                key += 12;
+            }
+            while key > 57 {
+                key -= 12;
+            }
+        }
+        let time = (n.tick as f32 * 10.0 / song.tempo).round() as u32;
+        let track = if options.layout == NoteLayout::MultiTrack { n.layer } else { 0 };
+        // 自定义乐器没有对应音色，按竖琴处理
+        let instrument = if (n.instrument as usize) < INSTRUMENTS.len() { n.instrument as usize } else { 0 };
+        let note = Note { instrument, pitch: (key - 33) as u8 };
+        slots.entry((track, time)).or_default().push(note);
+    }
+
+    let mut lanes: BTreeMap<(u32, usize), Lane> = BTreeMap::new();
+    for ((track, time), notes) in slots {
+        let notes = if options.layout == NoteLayout::MultiTrack && notes.len() > 2 {
+            info.dropped += notes.len() - 2;
+            &notes[..2]
+        } else {
+            &notes[..]
+        };
+        for (i, chunk) in notes.chunks(2).enumerate() {
+            lanes.entry((track, i)).or_default().push((time, chunk.to_vec()));
+        }
+    }
+    let lanes: Vec<Lane> = lanes.into_values().collect();
+    if lanes.is_empty() {
+        anyhow::bail!("song has no playable notes");
+    }
+    info.lanes = lanes.len();
+    info.placed = lanes.iter().flat_map(|l| l.iter()).map(|(_, n)| n.len()).sum();
+
+    let folded = options.layout == NoteLayout::Folded;
+    let fold = folded.then_some(options.fold_length.max(4) as i32);
+    let per_group = if folded { 2 } else { lanes.len() };
+    // 折叠时启动线让出西侧折返所需的列
+    let tap: u32 = if folded { 4 } else { 0 };
+    let feeder_x = -1 - tap as i32;
+    let groups: Vec<&[Lane]> = lanes.chunks(per_group).collect();
+
+    // 启动线沿 z 依次经过各线路起点，越靠后到得越晚，所有线路统一延后 lead 刻开始；
+    // 折叠行数又受 lead 影响，从小往大迭代到稳定
+    let mut lead = 1;
+    let plan = loop {
+        let plan = plan_groups(&groups, fold, lead, tap);
+        let needed = plan.arrival.values().max().copied().unwrap_or(0) + 1;
+        if needed <= lead {
+            break plan;
+        }
+        lead = needed;
+    };
+
+    let mut world = World { blocks: HashMap::new(), support: BlockData::from_name(&options.support_block) };
+    let first = plan.starts[0];
+    let last = *plan.starts.last().unwrap_or(&first);
+    for z in first..=last {
+        if plan.starts.contains(&z) {
+            world.put_line(feeder_x, z, world.support.clone());
+            for x in feeder_x + 1..0 {
+                world.put_line(x, z, repeater(1, [1, 0]));
+            }
+        } else {
+            world.put_line(feeder_x, z, repeater(1, [0, 1]));
+        }
+    }
+    let button = [("face", "wall".to_string()), ("facing", "north".to_string()), ("powered", "false".to_string())];
+    world.put(feeder_x, 1, first - 1, with_properties("stone_button", &button));
+
+    for (group, group_lanes) in plan.groups.iter().zip(&groups) {
+        for (t, lane) in group_lanes.iter().enumerate() {
+            let start = plan.arrival[&group.z(0, t as i32)];
+            let mut writer = LaneWriter { world: &mut world, group, lane: t as i32, row: 0, col: 0 };
+            for (delays, notes) in lane_delays(lane, start, lead).iter().zip(lane) {
+                for (i, delay) in delays.iter().enumerate() {
+                    writer.repeater(*delay, i + 1 == delays.len());
+                }
+                writer.station(&notes.1);
+            }
+        }
+    }
+
+    let mut positions: Vec<_> = world.blocks.into_iter().collect();
+    positions.sort_by_key(|(p, _)| (p[1], p[2], p[0]));
+    let data = placed_schematic(positions);
+    Ok(NoteBlockSong { data, info })
+}
+
+/// 每个音符站之前的中继器延迟
+fn lane_delays(lane: &Lane, start: u32, lead: u32) -> Vec<Vec<u32>> {
+    let mut cursor = start;
+    lane.iter()
+        .map(|(time, _)| {
+            let delays = split_delay((lead + time).saturating_sub(cursor));
+            cursor = lead + time;
+            delays
+        })
+        .collect()
+}
+
+/// 与 LaneWriter 相同的换行规则，算出线路占用的行数
+fn count_rows(delays: &[Vec<u32>], fold: i32) -> i32 {
+    let (mut row, mut col) = (0, 0);
+    for station in delays {
+        for i in 0..station.len() {
+            let need = if i + 1 == station.len() { 2 } else { 1 };
+            if col + need > fold {
+                row += 1;
+                col = 0;
+            }
+            col += 1;
+        }
+        col += 1;
+    }
+    row + 1
+}
+
+struct Plan {
+    groups: Vec<Group>,
+    // 各线路起点 z，按启动线经过的顺序
+    starts: Vec<i32>,
+    // 起点 z -> 信号到达线路第一个中继器的时间
+    arrival: HashMap<i32, u32>,
+}
+
+fn plan_groups(groups: &[&[Lane]], fold: Option<i32>, lead: u32, tap: u32) -> Plan {
+    let mut plan = Plan { groups: Vec::new(), starts: Vec::new(), arrival: HashMap::new() };
+    let mut base = 0;
+    for lanes in groups {
+        let group = Group { base, lanes: lanes.len() as i32, fold };
+        let mut rows = 1;
+        for (t, lane) in lanes.iter().enumerate() {
+            let z = group.z(0, t as i32);
+            let first = plan.starts.first().copied().unwrap_or(z);
+            // 启动线上除各起点外每格一个 1 刻中继器
+            let arrival = (z - first) as u32 - plan.starts.len() as u32 + tap;
+            plan.starts.push(z);
+            plan.arrival.insert(z, arrival);
+            if let Some(fold) = fold {
+                rows = rows.max(count_rows(&lane_delays(lane, arrival, lead), fold));
+            }
+        }
+        base += rows * group.lanes * 3 + 3;
+        plan.groups.push(group);
+    }
+    plan
+}
+
+/// 用方块注册表检查生成结果，音高与延迟也要在合法范围内
+pub fn validate_circuit(data: &SchematicData, je_blocks: &BlocksData) -> Result<()> {
+    for element in &data.blocks.elements {
+        let state = &element.block;
+        if !je_blocks.contains(&state.id.name) {
+            anyhow::bail!("unknown block {} at {:?}", state.id.name, element.pos);
+        }
+        let value = |key: &str| state.properties.get(key).and_then(|v| v.parse::<u32>().ok());
+        let valid = match state.id.name.as_ref() {
+            "minecraft:note_block" => value("note").is_some_and(|n| n <= 24),
+            "minecraft:repeater" => value("delay").is_some_and(|d| (1..=4).contains(&d)),
+            _ => true,
+        };
+        if !valid {
+            anyhow::bail!("invalid block state {:?} at {:?}", state.properties, element.pos);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use crate::map_art::palette::{MapPalette, PaletteColor};
+use crate::utils::minecraft_data::map_art_data::{BlockColorData, MapArtsData};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// 取色方式：墙面看到的是侧面采样，立体雕塑用整体平均色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteFace {
+    #[default]
+    Side,
+    Average,
+}
+
+fn rgb(values: &[u8]) -> Option<[u8; 3]> {
+    Some([*values.first()?, *values.get(1)?, *values.get(2)?])
+}
+
+fn face_color(data: &BlockColorData, face: PaletteFace) -> Option<[u8; 3]> {
+    match face {
+        PaletteFace::Side => {
+            let (a, b) = (rgb(&data.left_top)?, rgb(&data.right_bottom)?);
+            Some([0, 1, 2].map(|i| ((a[i] as u16 + b[i] as u16) / 2) as u8))
+        }
+        PaletteFace::Average => rgb(&data.average),
+    }
+}
+
+/// 单个方块在地图画数据中的颜色
+pub fn block_color(map_arts: &MapArtsData, name: &str, face: PaletteFace) -> Option<[u8; 3]> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let data = map_arts.categories.values().find_map(|c| c.get(name))?;
+    face_color(data, face)
+}
+
+/// sRGB（D65 白点）转 CIELAB
+pub fn rgb_to_lab(color: [u8; 3]) -> [f32; 3] {
+    let linear = color.map(|c| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    });
+    let [r, g, b] = linear;
+    let xyz = [
+        (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047,
+        0.2126 * r + 0.7152 * g + 0.0722 * b,
+        (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883,
+    ];
+    let f = xyz.map(|t| {
+        if t > 216.0 / 24389.0 { t.cbrt() } else { (24389.0 / 27.0 * t + 16.0) / 116.0 }
+    });
+    [116.0 * f[1] - 16.0, 500.0 * (f[0] - f[1]), 200.0 * (f[1] - f[2])]
+}
+
+/// CIE76 色差
+pub fn delta_e(a: [f32; 3], b: [f32; 3]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+/// 不带明暗的方块调色板，三档颜色相同；blocks 为空时使用全部方块
+pub fn block_palette(
+    map_arts: &MapArtsData,
+    blocks: &[String],
+    exclude: &[String],
+    face: PaletteFace,
+) -> Result<MapPalette> {
+    let strip = |name: &str| name.strip_prefix("minecraft:").unwrap_or(name).to_string();
+    let exclude: Vec<String> = exclude.iter().map(|b| strip(b)).collect();
+    let mut names: Vec<String> = if blocks.is_empty() {
+        map_arts.categories.values().flat_map(|c| c.keys().cloned()).collect()
+    } else {
+        blocks.iter().map(|b| strip(b)).collect()
+    };
+    names.sort();
+    names.dedup();
+
+    let colors: Vec<PaletteColor> = names
+        .into_iter()
+        .filter(|name| !exclude.contains(name))
+        .filter_map(|name| {
+            let data = map_arts.categories.values().find_map(|c| c.get(&name))?;
+            let color = face_color(data, face)?;
+            Some(PaletteColor { block: name, tones: [color; 3] })
+        })
+        .collect();
+    if colors.is_empty() {
+        anyhow::bail!("no usable blocks left in the palette");
+    }
+    Ok(MapPalette { colors })
+}
@@ -0,0 +1,21 @@
+use crate::utils::block_state_pos_list::{BlockData, BlockPos, BlockStatePosList};
+use crate::utils::entities::EntitiesList;
+use crate::utils::schematic_data::{SchematicData, Size};
+use crate::utils::tile_entities::TileEntitiesList;
+use std::sync::Arc;
+
+/// 把任意坐标上的方块平移到最小角为原点，尺寸取包围盒，保持传入顺序
+pub fn placed_schematic(placed: Vec<([i32; 3], Arc<BlockData>)>) -> SchematicData {
+    let min = placed
+        .iter()
+        .fold([i32::MAX; 3], |m, (p, _)| [m[0].min(p[0]), m[1].min(p[1]), m[2].min(p[2])]);
+    let mut max = [0i32; 3];
+    let mut list = BlockStatePosList::default();
+    for (p, state) in placed {
+        let pos = BlockPos { x: p[0] - min[0], y: p[1] - min[1], z: p[2] - min[2] };
+        max = [max[0].max(pos.x), max[1].max(pos.y), max[2].max(pos.z)];
+        list.add(pos, state);
+    }
+    let size = Size { width: max[0] + 1, height: max[1] + 1, length: max[2] + 1 };
+    SchematicData::new(list, TileEntitiesList::default(), EntitiesList::default(), size)
+}
@@ -0,0 +1,277 @@
+use crate::generator::placement::placed_schematic;
+use crate::utils::block_state_pos_list::BlockData;
+use crate::utils::schematic_data::SchematicData;
+use anyhow::Result;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShapeKind {
+    Sphere,
+    Cylinder,
+    Torus,
+    Dome,
+    Arch,
+    Spiral,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightedBlock {
+    pub block: String,
+    pub weight: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShapeOptions {
+    pub shape: ShapeKind,
+    // 球/穹顶/圆柱半径，圆环与螺旋为中心线半径，拱为外半径
+    pub radius: f32,
+    // 圆环、螺旋的管半径，拱的厚度
+    pub minor_radius: f32,
+    // 圆柱、螺旋的高度
+    pub height: u32,
+    // 拱的纵深
+    pub length: u32,
+    // 螺旋圈数
+    pub turns: f32,
+    pub hollow: bool,
+    // 空心时的壁厚
+    pub thickness: u32,
+    // 空心时被截断的一面也封上，例如圆柱的上下底、穹顶的地面
+    pub capped: bool,
+    pub pattern: Vec<WeightedBlock>,
+    pub seed: u64,
+    // 形状的竖直轴 x/y/z
+    pub axis: String,
+    // 设置后外表面的台阶处放置朝向中心的楼梯，仅 y 轴生效
+    pub stair_block: Option<String>,
+}
+
+impl Default for ShapeOptions {
+    fn default() -> Self {
+        Self {
+            shape: ShapeKind::Sphere,
+            radius: 8.0,
+            minor_radius: 3.0,
+            height: 16,
+            length: 8,
+            turns: 2.0,
+            hollow: false,
+            thickness: 1,
+            capped: false,
+            pattern: vec![WeightedBlock { block: "stone".to_string(), weight: 1 }],
+            seed: 0,
+            axis: "y".to_string(),
+            stair_block: None,
+        }
+    }
+}
+
+impl ShapeOptions {
+    /// 形状坐标系下的包围盒，y 为竖直轴
+    fn bounds(&self) -> ([i32; 3], [i32; 3]) {
+        let r = self.radius.max(0.0);
+        let m = self.minor_radius.max(0.0);
+        let h = self.height.max(1) as f32 - 1.0;
+        let (min, max) = match self.shape {
+            ShapeKind::Sphere => ([-r, -r, -r], [r, r, r]),
+            ShapeKind::Dome => ([-r, 0.0, -r], [r, r, r]),
+            ShapeKind::Cylinder => ([-r, 0.0, -r], [r, h, r]),
+            ShapeKind::Torus => ([-(r + m), -m, -(r + m)], [r + m, m, r + m]),
+            ShapeKind::Arch => ([-r, 0.0, 0.0], [r, r, self.length.max(1) as f32 - 1.0]),
+            ShapeKind::Spiral => ([-(r + m), -m, -(r + m)], [r + m, h + m, r + m]),
+        };
+        (min.map(|v| v.floor() as i32), max.map(|v| v.ceil() as i32))
+    }
+
+    /// 未截断的距离场，小于等于 0 在形状内
+    fn shell(&self, p: [f32; 3]) -> f32 {
+        let [x, y, z] = p;
+        let r = self.radius.max(0.0);
+        let m = self.minor_radius.max(0.0);
+        match self.shape {
+            ShapeKind::Sphere | ShapeKind::Dome => (x * x + y * y + z * z).sqrt() - r,
+            ShapeKind::Cylinder => (x * x + z * z).sqrt() - r,
+            ShapeKind::Torus => {
+                let q = (x * x + z * z).sqrt() - r;
+                (q * q + y * y).sqrt() - m
+            }
+            ShapeKind::Arch => {
+                let half = m.min(r) / 2.0;
+                ((x * x + y * y).sqrt() - (r - half)).abs() - half
+            }
+            ShapeKind::Spiral => {
+                let turns = self.turns.max(0.01);
+                let pitch = (self.height.max(1) as f32 - 1.0) / turns;
+                let q = (x * x + z * z).sqrt() - r;
+                let angle = z.atan2(x).rem_euclid(TAU) / TAU;
+                // 同一角度上每圈各有一段，取最近的一段
+                let guess = if pitch > 0.0 { (y / pitch - angle).round() } else { 0.0 };
+                (guess as i32 - 1..=guess as i32 + 1)
+                    .map(|k| angle + k as f32)
+                    .filter(|t| (0.0..=turns).contains(t))
+                    .map(|t| (q * q + (y - t * pitch).powi(2)).sqrt() - m)
+                    .fold(f32::MAX, f32::min)
+            }
+        }
+    }
+
+    /// 截断平面，穹顶只保留上半、圆柱与拱限制高度/纵深
+    fn clip(&self, p: [i32; 3]) -> bool {
+        let h = self.height.max(1) as i32;
+        match self.shape {
+            ShapeKind::Dome => p[1] >= 0,
+            ShapeKind::Arch => p[1] >= 0 && (0..self.length.max(1) as i32).contains(&p[2]),
+            ShapeKind::Cylinder => (0..h).contains(&p[1]),
+            _ => true,
+        }
+    }
+
+    fn inside(&self, p: [i32; 3]) -> bool {
+        self.clip(p) && self.shell(p.map(|v| v as f32)) <= 0.0
+    }
+
+    /// 空心时邻格在外部才算外表面，截断面只在 capped 时算
+    fn outside(&self, p: [i32; 3]) -> bool {
+        self.shell(p.map(|v| v as f32)) > 0.0 || (self.capped && !self.clip(p))
+    }
+}
+
+const NEIGHBORS: [[i32; 3]; 6] = [[1, 0, 0], [-1, 0, 0], [0, 1, 0], [0, -1, 0], [0, 0, 1], [0, 0, -1]];
+
+fn add(a: [i32; 3], b: [i32; 3]) -> [i32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+/// 楼梯的高侧朝向中心，外露面朝上放下半砖位置，朝下放上半
+pub fn stair(name: &str, facing: &str, half: &str) -> Arc<BlockData> {
+    let base = BlockData::from_name(name);
+    let properties: BTreeMap<Arc<str>, Arc<str>> = [
+        ("facing", facing),
+        ("half", half),
+        ("shape", "straight"),
+        ("waterlogged", "false"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (Arc::from(k), Arc::from(v)))
+    .collect();
+    Arc::new(BlockData { id: base.id.clone(), properties })
+}
+
+fn stair_at(options: &ShapeOptions, cells: &HashMap<[i32; 3], usize>, p: [i32; 3], name: &str) -> Option<Arc<BlockData>> {
+    let vertical = if !cells.contains_key(&add(p, [0, 1, 0])) && options.outside(add(p, [0, 1, 0])) {
+        "bottom"
+    } else if !cells.contains_key(&add(p, [0, -1, 0])) && options.outside(add(p, [0, -1, 0])) {
+        "top"
+    } else {
+        return None;
+    };
+    let f = p.map(|v| v as f32);
+    let gradient = |a: usize| {
+        let (mut hi, mut lo) = (f, f);
+        hi[a] += 0.5;
+        lo[a] -= 0.5;
+        options.shell(hi) - options.shell(lo)
+    };
+    let (gx, gz) = (gradient(0), gradient(2));
+    // 外法线的主要水平方向，楼梯朝向与之相反
+    let (step, facing) = if gx.abs() >= gz.abs() {
+        if gx > 0.0 { ([1, 0, 0], "west") } else { ([-1, 0, 0], "east") }
+    } else if gz > 0.0 {
+        ([0, 0, 1], "north")
+    } else {
+        ([0, 0, -1], "south")
+    };
+    if gx == 0.0 && gz == 0.0 {
+        return None;
+    }
+    let side = add(p, step);
+    if cells.contains_key(&side) || !options.outside(side) {
+        return None;
+    }
+    Some(stair(name, facing, vertical))
+}
+
+pub fn generate_shape(options: &ShapeOptions) -> Result<SchematicData> {
+    if options.pattern.is_empty() {
+        anyhow::bail!("shape pattern is empty");
+    }
+    let weights = WeightedIndex::new(options.pattern.iter().map(|b| b.weight))
+        .map_err(|e| anyhow::anyhow!("invalid pattern weights: {}", e))?;
+    let blocks: Vec<Arc<BlockData>> = options.pattern.iter().map(|b| BlockData::from_name(&b.block)).collect();
+    let (min, max) = options.bounds();
+
+    let mut cells: HashMap<[i32; 3], usize> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for y in min[1]..=max[1] {
+        for z in min[2]..=max[2] {
+            for x in min[0]..=max[0] {
+                let p = [x, y, z];
+                if !options.inside(p) {
+                    continue;
+                }
+                if options.hollow && NEIGHBORS.iter().any(|n| options.outside(add(p, *n))) {
+                    queue.push_back(p);
+                    cells.insert(p, 0);
+                } else {
+                    cells.insert(p, usize::MAX);
+                }
+            }
+        }
+    }
+
+    if options.hollow {
+        // 从外表面向内逐层推进，深度不足壁厚的保留
+        let thickness = options.thickness.max(1) as usize;
+        while let Some(p) = queue.pop_front() {
+            let depth = cells[&p] + 1;
+            if depth >= thickness {
+                continue;
+            }
+            for n in NEIGHBORS {
+                let next = add(p, n);
+                if let Some(d) = cells.get_mut(&next) {
+                    if *d == usize::MAX {
+                        *d = depth;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        cells.retain(|_, d| *d != usize::MAX);
+    }
+
+    let stairs = match &options.stair_block {
+        Some(name) if options.axis.eq_ignore_ascii_case("y") => Some(name.as_str()),
+        _ => None,
+    };
+    let mut positions: Vec<[i32; 3]> = cells.keys().copied().collect();
+    positions.sort_by_key(|p| (p[1], p[2], p[0]));
+
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut placed = Vec::with_capacity(positions.len());
+    for p in positions {
+        let state = match stairs.and_then(|name| stair_at(options, &cells, p, name)) {
+            Some(state) => state,
+            None => blocks[weights.sample(&mut rng)].clone(),
+        };
+        let world = match options.axis.to_lowercase().as_str() {
+            "x" => [p[1], p[0], p[2]],
+            "z" => [p[0], p[2], p[1]],
+            _ => p,
+        };
+        placed.push((world, state));
+    }
+    if placed.is_empty() {
+        anyhow::bail!("shape is empty, check the size options");
+    }
+
+    Ok(placed_schematic(placed))
+}
@@ -0,0 +1,228 @@
+use crate::utils::block_state_pos_list::{BlockData, BlockPos, BlockStatePosList};
+use crate::utils::entities::EntitiesList;
+use crate::utils::schematic_data::{SchematicData, Size};
+use crate::utils::tile_entities::TileEntitiesList;
+use anyhow::Result;
+use image::imageops::FilterType;
+use image::{GrayImage, ImageBuffer, Luma};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// 按高度区间决定地表，区间为地表方块的 y(含)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeightLayer {
+    pub min_height: i32,
+    pub max_height: i32,
+    pub surface: String,
+    pub subsurface: String,
+    pub depth: u32,
+}
+
+impl Default for HeightLayer {
+    fn default() -> Self {
+        Self {
+            min_height: i32::MIN,
+            max_height: i32::MAX,
+            surface: "grass_block".to_string(),
+            subsurface: "dirt".to_string(),
+            depth: 3,
+        }
+    }
+}
+
+/// 地表/生物群系遮罩图，亮度超过阈值的位置改用此层，后面的遮罩覆盖前面的
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerrainMask {
+    pub image: Vec<u8>,
+    pub threshold: u8,
+    pub surface: String,
+    pub subsurface: String,
+    pub depth: u32,
+}
+
+impl Default for TerrainMask {
+    fn default() -> Self {
+        Self {
+            image: Vec::new(),
+            threshold: 128,
+            surface: "sand".to_string(),
+            subsurface: "sandstone".to_string(),
+            depth: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerrainOptions {
+    pub width: u32,
+    pub length: u32,
+    // 白色对应的高度，黑色为 base_height
+    pub vertical_scale: f32,
+    pub base_height: i32,
+    pub fill_block: String,
+    pub layers: Vec<HeightLayer>,
+    pub masks: Vec<TerrainMask>,
+    // 低于水位的空位填水
+    pub water_level: Option<i32>,
+    pub water_block: String,
+    // 大于 0 时按 chunk_size×chunk_size 切分
+    pub chunk_size: u32,
+}
+
+impl Default for TerrainOptions {
+    fn default() -> Self {
+        Self {
+            width: 0,
+            length: 0,
+            vertical_scale: 64.0,
+            base_height: 1,
+            fill_block: "stone".to_string(),
+            layers: vec![HeightLayer::default()],
+            masks: Vec::new(),
+            water_level: None,
+            water_block: "water".to_string(),
+            chunk_size: 0,
+        }
+    }
+}
+
+pub struct TerrainChunk {
+    pub index: usize,
+    pub offset: BlockPos,
+    pub data: SchematicData,
+}
+
+struct Surface {
+    surface: Arc<BlockData>,
+    subsurface: Arc<BlockData>,
+    depth: i32,
+}
+
+fn load_gray(bytes: &[u8], width: u32, length: u32) -> Result<ImageBuffer<Luma<u16>, Vec<u16>>> {
+    let image = image::load_from_memory(bytes)?;
+    let (w, h) = (image.width().max(1), image.height().max(1));
+    let (tw, th) = match (width, length) {
+        (0, 0) => (w, h),
+        (0, th) => ((w as u64 * th as u64 / h as u64).max(1) as u32, th),
+        (tw, 0) => (tw, (h as u64 * tw as u64 / w as u64).max(1) as u32),
+        size => size,
+    };
+    let image = if (tw, th) == (w, h) { image } else { image.resize_exact(tw, th, FilterType::Triangle) };
+    Ok(image.to_luma16())
+}
+
+fn load_mask(bytes: &[u8], width: u32, length: u32) -> Result<GrayImage> {
+    let image = image::load_from_memory(bytes)?;
+    let image = if (image.width(), image.height()) == (width, length) {
+        image
+    } else {
+        image.resize_exact(width, length, FilterType::Nearest)
+    };
+    Ok(image.to_luma8())
+}
+
+pub fn generate_terrain(heightmap: &[u8], options: &TerrainOptions) -> Result<SchematicData> {
+    let heights = load_gray(heightmap, options.width, options.length)?;
+    let (width, length) = (heights.width(), heights.height());
+    let masks = options
+        .masks
+        .iter()
+        .map(|m| load_mask(&m.image, width, length))
+        .collect::<Result<Vec<_>>>()?;
+
+    let surface = |s: &str, sub: &str, depth: u32| Surface {
+        surface: BlockData::from_name(s),
+        subsurface: BlockData::from_name(sub),
+        depth: depth as i32,
+    };
+    let layers: Vec<(i32, i32, Surface)> = options
+        .layers
+        .iter()
+        .map(|l| (l.min_height, l.max_height, surface(&l.surface, &l.subsurface, l.depth)))
+        .collect();
+    let mask_layers: Vec<Surface> = options
+        .masks
+        .iter()
+        .map(|m| surface(&m.surface, &m.subsurface, m.depth))
+        .collect();
+    let fill = BlockData::from_name(&options.fill_block);
+    let water = BlockData::from_name(&options.water_block);
+    let default_layer = surface(&options.fill_block, &options.fill_block, 0);
+
+    let mut list = BlockStatePosList::default();
+    let mut top = 0;
+    for z in 0..length {
+        for x in 0..width {
+            let value = heights.get_pixel(x, z).0[0] as f32 / u16::MAX as f32;
+            let h = options.base_height.max(0) + (value * options.vertical_scale).round() as i32;
+            let masked = masks
+                .iter()
+                .zip(&options.masks)
+                .rposition(|(mask, m)| mask.get_pixel(x, z).0[0] > m.threshold);
+            let layer = match masked {
+                Some(i) => &mask_layers[i],
+                None => layers
+                    .iter()
+                    .find(|(min, max, _)| (*min..=*max).contains(&h))
+                    .map(|(_, _, s)| s)
+                    .unwrap_or(&default_layer),
+            };
+            for y in 0..=h {
+                let state = if y == h {
+                    &layer.surface
+                } else if y >= h - layer.depth {
+                    &layer.subsurface
+                } else {
+                    &fill
+                };
+                list.add(BlockPos { x: x as i32, y, z: z as i32 }, state.clone());
+            }
+            top = top.max(h);
+            if let Some(level) = options.water_level {
+                for y in h + 1..=level {
+                    list.add(BlockPos { x: x as i32, y, z: z as i32 }, water.clone());
+                }
+                top = top.max(level);
+            }
+        }
+    }
+
+    let size = Size { width: width as i32, height: top + 1, length: length as i32 };
+    Ok(SchematicData::new(list, TileEntitiesList::default(), EntitiesList::default(), size))
+}
+
+/// 按 x/z 切成整列的地块，offset 为地块在整体中的位置
+pub fn chunk_terrain(data: &SchematicData, chunk_size: u32) -> Vec<TerrainChunk> {
+    let chunk = chunk_size.max(1) as i32;
+    let columns = (data.size.width + chunk - 1) / chunk;
+    let rows = (data.size.length + chunk - 1) / chunk;
+    let mut lists: Vec<BlockStatePosList> = (0..columns * rows).map(|_| BlockStatePosList::default()).collect();
+    for element in &data.blocks.elements {
+        let (cx, cz) = (element.pos.x / chunk, element.pos.z / chunk);
+        let pos = BlockPos { x: element.pos.x - cx * chunk, y: element.pos.y, z: element.pos.z - cz * chunk };
+        lists[(cz * columns + cx) as usize].add(pos, element.block.clone());
+    }
+
+    lists
+        .into_iter()
+        .enumerate()
+        .filter(|(_, list)| !list.elements.is_empty())
+        .map(|(index, list)| {
+            let (cx, cz) = (index as i32 % columns, index as i32 / columns);
+            let top = list.elements.iter().map(|e| e.pos.y).max().unwrap_or(0);
+            let size = Size {
+                width: (data.size.width - cx * chunk).min(chunk),
+                height: top + 1,
+                length: (data.size.length - cz * chunk).min(chunk),
+            };
+            TerrainChunk {
+                index,
+                offset: BlockPos { x: cx * chunk, y: 0, z: cz * chunk },
+                data: SchematicData::new(list, TileEntitiesList::default(), EntitiesList::default(), size),
+            }
+        })
+        .collect()
+}
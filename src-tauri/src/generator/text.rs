@@ -0,0 +1,400 @@
+use crate::generator::mural::frame;
+use crate::generator::placement::placed_schematic;
+use crate::generator::shapes::stair;
+use crate::utils::block_state_pos_list::BlockData;
+use crate::utils::schematic_data::SchematicData;
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextOptions {
+    pub text: String,
+    // 内置点阵字体 5x7 / 3x5，设置 font_path 时使用 TTF/OTF
+    pub font: String,
+    pub font_path: Option<String>,
+    // TTF 每行的方块高度
+    pub height: u32,
+    // 点阵字体每个像素放大的倍数
+    pub scale: u32,
+    pub letter_spacing: u32,
+    pub line_spacing: u32,
+    // 文字挤出层数，有背景时文字凸出于背景板
+    pub depth: u32,
+    pub facing: String,
+    pub foreground: String,
+    pub background: Option<String>,
+    pub outline: Option<String>,
+    // 文字与背景板边缘的距离
+    pub padding: u32,
+    // 墙面文字用楼梯/半砖平滑斜边，地面文字无效
+    pub smooth: bool,
+    pub stair_block: Option<String>,
+    pub slab_block: Option<String>,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            font: "5x7".to_string(),
+            font_path: None,
+            height: 16,
+            scale: 1,
+            letter_spacing: 1,
+            line_spacing: 1,
+            depth: 1,
+            facing: "south".to_string(),
+            foreground: "white_concrete".to_string(),
+            background: None,
+            outline: None,
+            padding: 1,
+            smooth: false,
+            stair_block: None,
+            slab_block: None,
+        }
+    }
+}
+
+/// 文字平面上一格的形状，stair 的 right 表示高侧在图片右方
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cell {
+    Empty,
+    Full,
+    Slab { top: bool },
+    Stair { right: bool, top: bool },
+}
+
+struct Canvas {
+    width: usize,
+    height: usize,
+    // 自上而下逐行
+    cells: Vec<Cell>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self { width, height, cells: vec![Cell::Empty; width * height] }
+    }
+
+    fn get(&self, x: i64, y: i64) -> Cell {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return Cell::Empty;
+        }
+        self.cells[y as usize * self.width + x as usize]
+    }
+
+    fn filled(&self, x: i64, y: i64) -> bool {
+        self.get(x, y) != Cell::Empty
+    }
+}
+
+// 经典 5x7 点阵，每字 5 列，低位在上，ASCII 32..=126
+const FONT_5X7: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5F, 0x00, 0x00], [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], [0x24, 0x2A, 0x7F, 0x2A, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x55, 0x22, 0x50], [0x00, 0x05, 0x03, 0x00, 0x00], [0x00, 0x1C, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1C, 0x00], [0x08, 0x2A, 0x1C, 0x2A, 0x08], [0x08, 0x08, 0x3E, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02], [0x3E, 0x51, 0x49, 0x45, 0x3E], [0x00, 0x42, 0x7F, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4B, 0x31], [0x18, 0x14, 0x12, 0x7F, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39], [0x3C, 0x4A, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1E], [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00], [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06], [0x32, 0x49, 0x79, 0x41, 0x3E],
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], [0x7F, 0x49, 0x49, 0x49, 0x36], [0x3E, 0x41, 0x41, 0x41, 0x22],
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], [0x7F, 0x49, 0x49, 0x49, 0x41], [0x7F, 0x09, 0x09, 0x01, 0x01],
+    [0x3E, 0x41, 0x41, 0x51, 0x32], [0x7F, 0x08, 0x08, 0x08, 0x7F], [0x00, 0x41, 0x7F, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3F, 0x01], [0x7F, 0x08, 0x14, 0x22, 0x41], [0x7F, 0x40, 0x40, 0x40, 0x40],
+    [0x7F, 0x02, 0x04, 0x02, 0x7F], [0x7F, 0x04, 0x08, 0x10, 0x7F], [0x3E, 0x41, 0x41, 0x41, 0x3E],
+    [0x7F, 0x09, 0x09, 0x09, 0x06], [0x3E, 0x41, 0x51, 0x21, 0x5E], [0x7F, 0x09, 0x19, 0x29, 0x46],
+    [0x46, 0x49, 0x49, 0x49, 0x31], [0x01, 0x01, 0x7F, 0x01, 0x01], [0x3F, 0x40, 0x40, 0x40, 0x3F],
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], [0x7F, 0x20, 0x18, 0x20, 0x7F], [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x03, 0x04, 0x78, 0x04, 0x03], [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x00, 0x7F, 0x41, 0x41],
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x41, 0x41, 0x7F, 0x00, 0x00], [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40], [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7F, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], [0x38, 0x44, 0x44, 0x48, 0x7F],
+    [0x38, 0x54, 0x54, 0x54, 0x18], [0x08, 0x7E, 0x09, 0x01, 0x02], [0x08, 0x14, 0x54, 0x54, 0x3C],
+    [0x7F, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7D, 0x40, 0x00], [0x20, 0x40, 0x44, 0x3D, 0x00],
+    [0x00, 0x7F, 0x10, 0x28, 0x44], [0x00, 0x41, 0x7F, 0x40, 0x00], [0x7C, 0x04, 0x18, 0x04, 0x78],
+    [0x7C, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], [0x7C, 0x14, 0x14, 0x14, 0x08],
+    [0x08, 0x14, 0x14, 0x18, 0x7C], [0x7C, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3F, 0x44, 0x40, 0x20], [0x3C, 0x40, 0x40, 0x20, 0x7C], [0x1C, 0x20, 0x40, 0x20, 0x1C],
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], [0x44, 0x28, 0x10, 0x28, 0x44], [0x0C, 0x50, 0x50, 0x50, 0x3C],
+    [0x44, 0x64, 0x54, 0x4C, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], [0x00, 0x00, 0x7F, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00], [0x08, 0x04, 0x08, 0x10, 0x08],
+];
+
+// 3x5 小号点阵，每字 5 行，高位在左；小写按大写显示
+const FONT_3X5: [(char, [u8; 5]); 50] = [
+    (' ', [0b000, 0b000, 0b000, 0b000, 0b000]), ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]), ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]), ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]), ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]), ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]), ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]), ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]), ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]), ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]), ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]), ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]), ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]), ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]), ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]), ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]), ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]), ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]), ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]), ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    (',', [0b000, 0b000, 0b000, 0b010, 0b100]), ('!', [0b010, 0b010, 0b010, 0b000, 0b010]),
+    ('?', [0b110, 0b001, 0b010, 0b000, 0b010]), ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('+', [0b000, 0b010, 0b111, 0b010, 0b000]), (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]), ('\'', [0b010, 0b010, 0b000, 0b000, 0b000]),
+    ('(', [0b001, 0b010, 0b010, 0b010, 0b001]), (')', [0b100, 0b010, 0b010, 0b010, 0b100]),
+    ('=', [0b000, 0b111, 0b000, 0b111, 0b000]), ('_', [0b000, 0b000, 0b000, 0b000, 0b111]),
+];
+
+/// 点阵字形，返回 (宽, 高, 按行的像素)
+fn pixel_glyph(font: &str, c: char) -> Result<(usize, usize, Vec<bool>)> {
+    match font {
+        "5x7" => {
+            let code = c as u32;
+            if !(32..=126).contains(&code) {
+                anyhow::bail!("character '{}' is not in the built-in 5x7 font, use a TTF font", c);
+            }
+            let columns = FONT_5X7[(code - 32) as usize];
+            let pixels = (0..7).flat_map(|y| columns.map(|col| col >> y & 1 == 1)).collect();
+            Ok((5, 7, pixels))
+        }
+        "3x5" => {
+            let upper = c.to_ascii_uppercase();
+            let Some((_, rows)) = FONT_3X5.iter().find(|(g, _)| *g == upper) else {
+                anyhow::bail!("character '{}' is not in the built-in 3x5 font, use a TTF font", c);
+            };
+            let pixels = rows.iter().flat_map(|row| (0..3).rev().map(move |x| row >> x & 1 == 1)).collect();
+            Ok((3, 5, pixels))
+        }
+        other => anyhow::bail!("unknown built-in font: {}", other),
+    }
+}
+
+fn render_pixel_font(options: &TextOptions) -> Result<Canvas> {
+    let scale = options.scale.max(1) as usize;
+    let spacing = options.letter_spacing as usize;
+    let mut lines = Vec::new();
+    for line in options.text.lines() {
+        let glyphs = line.chars().map(|c| pixel_glyph(&options.font, c)).collect::<Result<Vec<_>>>()?;
+        lines.push(glyphs);
+    }
+    let glyph_height = pixel_glyph(&options.font, ' ')?.1;
+    let line_width = |glyphs: &Vec<(usize, usize, Vec<bool>)>| {
+        let w: usize = glyphs.iter().map(|g| g.0 + spacing).sum();
+        w.saturating_sub(spacing) * scale
+    };
+    let width = lines.iter().map(line_width).max().unwrap_or(0);
+    let line_height = (glyph_height + options.line_spacing as usize) * scale;
+    let height = (lines.len() * line_height).saturating_sub(options.line_spacing as usize * scale);
+
+    let mut canvas = Canvas::new(width, height);
+    for (row, glyphs) in lines.iter().enumerate() {
+        let mut left = 0;
+        for (w, h, pixels) in glyphs {
+            for py in 0..*h {
+                for px in 0..*w {
+                    if !pixels[py * w + px] {
+                        continue;
+                    }
+                    for sy in 0..scale {
+                        for sx in 0..scale {
+                            let (x, y) = ((left + px) * scale + sx, row * line_height + py * scale + sy);
+                            canvas.cells[y * width + x] = Cell::Full;
+                        }
+                    }
+                }
+            }
+            left += w + spacing;
+        }
+    }
+    Ok(canvas)
+}
+
+/// 点阵字形的内凹角放楼梯，把锯齿状的斜笔画连成斜坡
+fn smooth_corners(canvas: &mut Canvas) {
+    let mut cells = canvas.cells.clone();
+    for y in 0..canvas.height as i64 {
+        for x in 0..canvas.width as i64 {
+            if canvas.filled(x, y) {
+                continue;
+            }
+            let (left, right) = (canvas.filled(x - 1, y), canvas.filled(x + 1, y));
+            let (up, down) = (canvas.filled(x, y - 1), canvas.filled(x, y + 1));
+            if left == right || up == down {
+                continue;
+            }
+            cells[y as usize * canvas.width + x as usize] = Cell::Stair { right, top: up };
+        }
+    }
+    canvas.cells = cells;
+}
+
+/// TTF 按两倍分辨率栅格化，每格四个子像素决定整块、半砖或楼梯
+fn render_ttf(options: &TextOptions, font_data: Vec<u8>) -> Result<Canvas> {
+    let font = FontVec::try_from_vec(font_data).map_err(|_| anyhow::anyhow!("invalid font file"))?;
+    let scaled = font.as_scaled(PxScale::from(options.height.max(1) as f32 * 2.0));
+    let spacing = options.letter_spacing as f32 * 2.0;
+    let line_height = scaled.height() + options.line_spacing as f32 * 2.0;
+
+    let mut glyphs = Vec::new();
+    let mut width = 0.0f32;
+    let lines: Vec<&str> = options.text.lines().collect();
+    for (row, line) in lines.iter().enumerate() {
+        let baseline = row as f32 * line_height + scaled.ascent();
+        let mut caret = 0.0f32;
+        let mut previous = None;
+        for c in line.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(previous) = previous {
+                caret += scaled.kern(previous, id) + spacing;
+            }
+            glyphs.push(id.with_scale_and_position(scaled.scale(), ab_glyph::point(caret, baseline)));
+            caret += scaled.h_advance(id);
+            previous = Some(id);
+        }
+        width = width.max(caret);
+    }
+    let fine_width = (width.ceil() as usize + 1) / 2 * 2;
+    let fine_height = ((lines.len() as f32 * line_height - options.line_spacing as f32 * 2.0).ceil() as usize + 1) / 2 * 2;
+    let mut coverage = vec![0.0f32; fine_width * fine_height];
+    for glyph in glyphs {
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|x, y, c| {
+            let (fx, fy) = (bounds.min.x as i64 + x as i64, bounds.min.y as i64 + y as i64);
+            if fx >= 0 && fy >= 0 && (fx as usize) < fine_width && (fy as usize) < fine_height {
+                let at = fy as usize * fine_width + fx as usize;
+                coverage[at] = (coverage[at] + c).min(1.0);
+            }
+        });
+    }
+
+    let mut canvas = Canvas::new(fine_width / 2, fine_height / 2);
+    for y in 0..canvas.height {
+        for x in 0..canvas.width {
+            let quad = |dx: usize, dy: usize| coverage[(y * 2 + dy) * fine_width + x * 2 + dx] >= 0.5;
+            let (tl, tr, bl, br) = (quad(0, 0), quad(1, 0), quad(0, 1), quad(1, 1));
+            let count = [tl, tr, bl, br].iter().filter(|q| **q).count();
+            canvas.cells[y * canvas.width + x] = match count {
+                4 => Cell::Full,
+                // 缺的那一角在左侧则高侧在右，缺在下方则为上半楼梯
+                3 if options.smooth => Cell::Stair { right: !tl || !bl, top: !bl || !br },
+                2 if options.smooth && tl && tr => Cell::Slab { top: true },
+                2 if options.smooth && bl && br => Cell::Slab { top: false },
+                0 | 1 => Cell::Empty,
+                _ => Cell::Full,
+            };
+        }
+    }
+    Ok(canvas)
+}
+
+fn with_properties(name: &str, properties: &[(&str, &str)]) -> Arc<BlockData> {
+    let base = BlockData::from_name(name);
+    let properties: BTreeMap<Arc<str>, Arc<str>> =
+        properties.iter().map(|(k, v)| (Arc::from(*k), Arc::from(*v))).collect();
+    Arc::new(BlockData { id: base.id.clone(), properties })
+}
+
+fn direction(v: [i32; 3]) -> &'static str {
+    match v {
+        [1, 0, 0] => "east",
+        [-1, 0, 0] => "west",
+        [0, 0, 1] => "south",
+        _ => "north",
+    }
+}
+
+pub fn generate_text(options: &TextOptions, font_data: Option<Vec<u8>>) -> Result<SchematicData> {
+    if options.text.trim().is_empty() {
+        anyhow::bail!("text is empty");
+    }
+    let (right, up, out) = frame(&options.facing)?;
+    // 地面文字的上方向是水平的，楼梯半砖无法表现
+    let smooth = options.smooth && up[1] != 0;
+    let canvas = match font_data {
+        Some(data) => render_ttf(&TextOptions { smooth, ..options.clone() }, data)?,
+        None => {
+            let mut canvas = render_pixel_font(options)?;
+            if smooth {
+                smooth_corners(&mut canvas);
+            }
+            canvas
+        }
+    };
+
+    let pad = options.padding as usize + usize::from(options.outline.is_some());
+    let framed = options.background.is_some() || options.outline.is_some();
+    let pad = if framed { pad } else { 0 };
+    let (width, height) = (canvas.width + pad * 2, canvas.height + pad * 2);
+
+    let foreground = BlockData::from_name(&options.foreground);
+    let background = options.background.as_deref().map(BlockData::from_name);
+    let outline = options.outline.as_deref().map(BlockData::from_name);
+    let shaped = |cell: Cell| -> Arc<BlockData> {
+        match cell {
+            Cell::Stair { right: high_right, top } => match &options.stair_block {
+                Some(name) => {
+                    let facing = direction(if high_right { right } else { right.map(|v| -v) });
+                    stair(name, facing, if top { "top" } else { "bottom" })
+                }
+                None => foreground.clone(),
+            },
+            Cell::Slab { top } => match &options.slab_block {
+                Some(name) => with_properties(name, &[("type", if top { "top" } else { "bottom" }), ("waterlogged", "false")]),
+                None => foreground.clone(),
+            },
+            _ => foreground.clone(),
+        }
+    };
+
+    let depth = options.depth.max(1) as i32;
+    let front = if background.is_some() { 1 } else { 0 };
+    let mut placed: Vec<([i32; 3], Arc<BlockData>)> = Vec::new();
+    for iy in 0..height {
+        for ix in 0..width {
+            let (cx, cy) = (ix as i64 - pad as i64, iy as i64 - pad as i64);
+            let cell = canvas.get(cx, cy);
+            let state = if cell != Cell::Empty {
+                Some(shaped(cell))
+            } else {
+                let near = (-1..=1).any(|dy| (-1..=1).any(|dx| canvas.filled(cx + dx, cy + dy)));
+                match &outline {
+                    Some(outline) if near => Some(outline.clone()),
+                    _ => None,
+                }
+            };
+            let (u, v) = (ix as i32, (height - 1 - iy) as i32);
+            let mut put = |d: i32, state: &Arc<BlockData>| {
+                let pos = [0, 1, 2].map(|a| right[a] * u + up[a] * v + out[a] * d);
+                placed.push((pos, state.clone()));
+            };
+            if let Some(background) = &background {
+                put(0, background);
+            }
+            if let Some(state) = state {
+                for d in front..front + depth {
+                    put(d, &state);
+                }
+            }
+        }
+    }
+    if placed.is_empty() {
+        anyhow::bail!("text produced no blocks");
+    }
+
+    Ok(placed_schematic(placed))
+}
@@ -0,0 +1,175 @@
+use crate::generator::mesh_import::ImportedMesh;
+use crate::generator::palette::{block_palette, PaletteFace};
+use crate::map_art::palette::ToneMask;
+use crate::utils::block_state_pos_list::{BlockData, BlockPos, BlockStatePosList};
+use crate::utils::entities::EntitiesList;
+use crate::utils::minecraft_data::map_art_data::MapArtsData;
+use crate::utils::schematic_data::{SchematicData, Size};
+use crate::utils::tile_entities::TileEntitiesList;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoxelOptions {
+    // 最长边的方块数
+    pub size: u32,
+    // 实心填充，否则只保留外壳
+    pub solid: bool,
+    pub fill_block: String,
+    // STL 等建模软件常用 z 轴朝上
+    pub z_up: bool,
+    // 按模型颜色选方块，关闭或模型无颜色时使用 default_block
+    pub use_color: bool,
+    pub default_block: String,
+    pub blocks: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl Default for VoxelOptions {
+    fn default() -> Self {
+        Self {
+            size: 64,
+            solid: false,
+            fill_block: "stone".to_string(),
+            z_up: false,
+            use_color: true,
+            default_block: "white_concrete".to_string(),
+            blocks: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+// 最长边上限，网格按 size³ 分配内存
+pub const MAX_VOXEL_SIZE: u32 = 512;
+
+const SURFACE: u8 = 1;
+const OUTSIDE: u8 = 2;
+
+pub fn voxelize(mesh: &ImportedMesh, map_arts: &MapArtsData, options: &VoxelOptions) -> Result<SchematicData> {
+    if options.size > MAX_VOXEL_SIZE {
+        anyhow::bail!("voxel size {} exceeds the limit of {}", options.size, MAX_VOXEL_SIZE);
+    }
+    let orient = |p: [f32; 3]| if options.z_up { [p[0], p[2], -p[1]] } else { p };
+    let (mut min, mut max) = ([f32::MAX; 3], [f32::MIN; 3]);
+    for triangle in &mesh.triangles {
+        for p in triangle.positions.map(orient) {
+            for a in 0..3 {
+                min[a] = min[a].min(p[a]);
+                max[a] = max[a].max(p[a]);
+            }
+        }
+    }
+    let extent = (0..3).map(|a| max[a] - min[a]).fold(0.0f32, f32::max);
+    if !extent.is_finite() || extent <= 0.0 {
+        anyhow::bail!("model has no volume");
+    }
+    let scale = (options.size.max(1) - 1).max(1) as f32 / extent;
+    let dims = [0, 1, 2].map(|a| ((max[a] - min[a]) * scale).round() as usize + 1);
+    let index = |v: [usize; 3]| (v[1] * dims[2] + v[2]) * dims[0] + v[0];
+
+    let mut grid = vec![0u8; dims[0] * dims[1] * dims[2]];
+    let mut colors: HashMap<usize, ([f32; 3], u32)> = HashMap::new();
+
+    // 按体素间距的一半在三角形上均匀采样
+    for triangle in &mesh.triangles {
+        let corners = triangle.positions.map(|p| {
+            let p = orient(p);
+            [0, 1, 2].map(|a| (p[a] - min[a]) * scale)
+        });
+        let edge = |i: usize, j: usize| {
+            (0..3).map(|a| (corners[i][a] - corners[j][a]).powi(2)).sum::<f32>().sqrt()
+        };
+        let steps = ((edge(0, 1).max(edge(1, 2)).max(edge(2, 0)) * 2.0).ceil() as usize).max(1);
+        for i in 0..=steps {
+            for j in 0..=steps - i {
+                let (u, v) = (i as f32 / steps as f32, j as f32 / steps as f32);
+                let bary = [1.0 - u - v, u, v];
+                let voxel = [0, 1, 2].map(|a| {
+                    let p = corners[0][a] * bary[0] + corners[1][a] * bary[1] + corners[2][a] * bary[2];
+                    (p.round().max(0.0) as usize).min(dims[a] - 1)
+                });
+                let at = index(voxel);
+                grid[at] = SURFACE;
+                if !options.use_color {
+                    continue;
+                }
+                if let Some(color) = mesh.color_at(triangle, bary) {
+                    let entry = colors.entry(at).or_insert(([0.0; 3], 0));
+                    for c in 0..3 {
+                        entry.0[c] += color[c];
+                    }
+                    entry.1 += 1;
+                }
+            }
+        }
+    }
+
+    if options.solid {
+        // 从包围盒边界向内泛洪，碰不到的空格即为内部
+        let mut queue = VecDeque::new();
+        for z in 0..dims[2] {
+            for y in 0..dims[1] {
+                for x in 0..dims[0] {
+                    let border = x == 0 || y == 0 || z == 0 || x == dims[0] - 1 || y == dims[1] - 1 || z == dims[2] - 1;
+                    let at = index([x, y, z]);
+                    if border && grid[at] == 0 {
+                        grid[at] = OUTSIDE;
+                        queue.push_back([x, y, z]);
+                    }
+                }
+            }
+        }
+        while let Some(v) = queue.pop_front() {
+            for (a, d) in [(0, -1), (0, 1), (1, -1), (1, 1), (2, -1), (2, 1)] {
+                let n = v[a] as i64 + d;
+                if n < 0 || n >= dims[a] as i64 {
+                    continue;
+                }
+                let mut next = v;
+                next[a] = n as usize;
+                let at = index(next);
+                if grid[at] == 0 {
+                    grid[at] = OUTSIDE;
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    let palette = block_palette(map_arts, &options.blocks, &options.exclude, PaletteFace::Average)?;
+    let blocks: Vec<Arc<BlockData>> = palette.colors.iter().map(|c| BlockData::from_name(&c.block)).collect();
+    let default_block = BlockData::from_name(&options.default_block);
+    let fill = BlockData::from_name(&options.fill_block);
+    let mut cache: HashMap<[u8; 3], usize> = HashMap::new();
+    let mut list = BlockStatePosList::default();
+
+    for y in 0..dims[1] {
+        for z in 0..dims[2] {
+            for x in 0..dims[0] {
+                let at = index([x, y, z]);
+                let state = match grid[at] {
+                    SURFACE => match colors.get(&at) {
+                        Some((sum, count)) => {
+                            let key = sum.map(|c| (c / *count as f32).round().clamp(0.0, 255.0) as u8);
+                            let entry = *cache
+                                .entry(key)
+                                .or_insert_with(|| palette.nearest(key.map(|c| c as f32), ToneMask::FLAT).0);
+                            &blocks[entry]
+                        }
+                        None => &default_block,
+                    },
+                    0 if options.solid => &fill,
+                    _ => continue,
+                };
+                list.add(BlockPos { x: x as i32, y: y as i32, z: z as i32 }, state.clone());
+            }
+        }
+    }
+
+    let size = Size { width: dims[0] as i32, height: dims[1] as i32, length: dims[2] as i32 };
+    Ok(SchematicData::new(list, TileEntitiesList::default(), EntitiesList::default(), size))
+}
@@ -4,31 +4,52 @@ mod building_gadges;
 pub mod create;
 mod data_files;
 mod database;
+pub mod datapack;
+mod generator;
 pub mod litematica;
-mod map_art;
+pub mod map_art;
+mod render;
+mod mcfunction;
 pub mod modules;
 pub mod utils;
 mod word_edit;
-mod split_schematic;
+pub mod split_schematic;
 
 use crate::database::db_control;
+use crate::utils::minecraft_data::block_tags::BlockTags;
 use crate::utils::minecraft_data::je_blocks_data::BlocksData;
 use crate::utils::minecraft_data::map_art_data::MapArtsData;
 use data_files::{config, config::get_config, config::update_config, files::FileManager};
+use database::db_apis::links_api::get_schematic_links;
 use database::db_apis::logs_api::{add_logs, get_logs};
 use database::db_apis::schematic_data_api::{get_schematic_requirements, get_unique_block};
 use database::db_apis::schematics_api::{add_schematic, get_schematic, get_schematics, count_schematics};
+use database::db_apis::theme_api::{delete_theme_preset, get_theme_presets, save_theme_preset};
 use database::db_apis::user_api::{get_user_data, update_user_classification_tauri, get_user_classification};
 use modules::convert::{convert, convert_lm, get_je_blocks, get_map_arts, get_schematic_convert_data};
+use modules::datapack::{export_mcfunction_datapack, export_structure_datapack};
+use modules::generate::{
+    create_mural, create_note_block_song, create_shape, create_terrain, create_text, create_voxel_model, preview_mural,
+};
 use modules::history::get_history;
-use modules::map_art::create_map_art;
-use modules::replace::schematic_replacement;
+use modules::thumbnail::get_schematic_thumbnail;
+use modules::build_guide::{export_build_guide, get_layer_image};
+use modules::mesh::export_mesh;
+use render::block_colors::BlockColors;
+use modules::structure::{apply_structure_palette, export_structure_tiles, get_structure_palettes};
+use modules::map_art::{
+    create_map_art, create_map_art_from_image, export_map_items, export_map_items_from_image,
+    get_pack_map_arts, preview_map_art,
+};
+use modules::replace::{apply_theme_preset, schematic_extract, schematic_recolor, schematic_replacement};
 use modules::schematic::{
     copy_schematic, delete_schematic, encode_uploaded_schematic, get_schematic_str,
     update_schematic_name_description, get_schematic_data, update_schematic_classification_tauri,
-    save_snbt
+    save_snbt, import_mcfunction
+};
+use split_schematic::split_schematic::{
+    export_split_manifest, schematic_split, schematic_split_budget, schematic_split_save,
 };
-use split_schematic::split_schematic::schematic_split;
 use tauri::Manager;
 use utils::loading::close_splashscreen;
 use utils::minecraft_data::versions_data::VersionData;
@@ -57,8 +78,12 @@ pub fn run() {
             app.manage(version_data);
             let je_blocks = BlocksData::new(app.handle())?;
             app.manage(je_blocks);
+            let block_tags = BlockTags::new(app.handle())?;
+            app.manage(block_tags);
             let map_arts = MapArtsData::new(app.handle())?;
             app.manage(map_arts);
+            let block_colors = BlockColors::new(app.handle())?;
+            app.manage(block_colors);
             Ok(())
         })
         .plugin(tauri_plugin_opener::init())
@@ -81,6 +106,9 @@ pub fn run() {
             delete_schematic,
             add_logs,
             schematic_split,
+            schematic_split_budget,
+            schematic_split_save,
+            export_split_manifest,
             schematic_replacement,
             update_user_classification_tauri,
             update_schematic_classification_tauri,
@@ -96,7 +124,36 @@ pub fn run() {
             get_unique_block,
             get_schematic_str,
             get_schematic_data,
-            get_schematic_convert_data
+            get_schematic_convert_data,
+            import_mcfunction,
+            export_mcfunction_datapack,
+            export_structure_datapack,
+            get_structure_palettes,
+            apply_structure_palette,
+            export_structure_tiles,
+            get_schematic_thumbnail,
+            get_layer_image,
+            export_build_guide,
+            export_mesh,
+            preview_map_art,
+            create_map_art_from_image,
+            export_map_items_from_image,
+            export_map_items,
+            get_schematic_links,
+            get_pack_map_arts,
+            preview_mural,
+            create_mural,
+            create_terrain,
+            create_voxel_model,
+            create_shape,
+            create_text,
+            create_note_block_song,
+            get_theme_presets,
+            save_theme_preset,
+            delete_theme_preset,
+            apply_theme_preset,
+            schematic_recolor,
+            schematic_extract
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -29,6 +29,7 @@ pub struct ToLmSchematic {
     pub block_state_to_index: HashMap<Arc<BlockData>, usize>,
     pub tile_entities: TileEntitiesList,
     pub entities: EntitiesList,
+    pub preview_image: Option<Vec<i32>>,
 }
 
 impl ToLmSchematic {
@@ -169,8 +170,15 @@ impl ToLmSchematic {
             block_state_to_index,
             tile_entities,
             entities,
+            preview_image: None,
         })
     }
+
+    // 投影预览图，ARGB 像素，边长为 sqrt(len) 的正方形
+    pub fn set_preview_image(&mut self, argb: Vec<i32>) {
+        self.preview_image = Some(argb);
+    }
+
     pub fn get_block_id_list(&self) -> Vec<i32> {
         let total_blocks = (self.length * self.width * self.height) as usize;
 
@@ -329,6 +337,12 @@ impl ToLmSchematic {
         metadata.insert("TotalBlocks".to_string(), Value::Int(0));
         metadata.insert("TimeModified".to_string(), Value::Long(timestamp_sec));
         metadata.insert("TimeCreated".to_string(), Value::Long(timestamp_sec));
+        if let Some(preview) = &self.preview_image {
+            metadata.insert(
+                "PreviewImageData".to_string(),
+                Value::IntArray(fastnbt::IntArray::new(preview.clone())),
+            );
+        }
 
         Compound(metadata)
     }
@@ -16,11 +16,14 @@ use sysinfo::{Pid, ProcessesToUpdate, System};
 use utils::extend_write::to_writer_gzip;
 use utils::requirements::get_requirements;
 use crate::be_schematic::le_reader::write_nbt_le::save_nbt_le;
+use crate::mcfunction::mcfunction_schematic::McFunctionSchematic;
+use crate::mcfunction::to_mcfunction::ToMcFunction;
 
 pub mod building_gadges;
 pub mod create;
 pub mod be_schematic;
 pub mod litematica;
+pub mod mcfunction;
 pub mod utils;
 pub mod word_edit;
 fn main() {
@@ -170,7 +173,397 @@ fn be_schematic_write() -> Result<(), SchematicError> {
     println!("内存峰值: {} MB", peak_mem_kb / 1024);
     Ok(())
 }
+#[test]
+fn test_mcfunction_schematic() -> Result<(), SchematicError> {
+    let peak_watcher = start_memory_peak_watcher();
+    let start_time = Instant::now();
+
+    let schematic = McFunctionSchematic::new("./schematic/test.mcfunction")?;
+    let schem = schematic.get_blocks_pos()?;
+    println!("{:?}", schem.size);
+    assert_eq!(schem.size.width, 15);
+    assert_eq!(schem.tile_entities_list.elements.len(), 2);
+    let commands = ToMcFunction::new(&schem)?.commands(false);
+    let schem2 = McFunctionSchematic::new_from_str(&commands.join("\n")).get_blocks_pos()?;
+    println!("{} commands", commands.len());
+    assert_eq!(schem2.tile_entities_list.elements.len(), 2);
+    let requirements = get_requirements(&schem.blocks)?;
+    println!("{:?}", requirements);
+
+    let duration = start_time.elapsed();
+    let peak_mem_kb = peak_watcher.load(Ordering::Relaxed) / 1024;
+    println!("执行时间: {:.2} 秒", duration.as_secs_f64());
+    println!("内存峰值: {} MB", peak_mem_kb / 1024);
+    Ok(())
+}
+
+
+#[test]
+fn test_mcfunction_execute() -> Result<(), SchematicError> {
+    let commands = [
+        "setblock 0 0 0 minecraft:stone",
+        "execute positioned ~5 ~ ~ run setblock ~ ~ ~ minecraft:oak_planks",
+        "execute positioned 1 2 3 positioned ~1.5 ~ ~ align xyz run setblock ~ ~ ~ minecraft:glass",
+    ];
+    let schem = McFunctionSchematic::new_from_str(&commands.join("\n")).get_blocks_pos()?;
+    assert_eq!((schem.size.width, schem.size.height, schem.size.length), (6, 3, 4));
+    let find = |x, y, z| {
+        schem.blocks.elements.iter()
+            .find(|b| (b.pos.x, b.pos.y, b.pos.z) == (x, y, z))
+            .map(|b| b.block.id.name.to_string())
+    };
+    assert_eq!(find(5, 0, 0).as_deref(), Some("minecraft:oak_planks"));
+    assert_eq!(find(2, 2, 3).as_deref(), Some("minecraft:glass"));
+
+    // 不支持的 execute 子命令、超出原版上限的区域都应报错
+    for bad in [
+        "execute as @a run setblock ~ ~ ~ minecraft:stone",
+        "execute positioned ~1 ~ ~",
+        "fill -30000000 0 -30000000 30000000 255 30000000 minecraft:stone",
+        "clone 0 0 0 40 40 40 100 0 0",
+    ] {
+        assert!(McFunctionSchematic::new_from_str(bad).get_blocks_pos().is_err(), "{}", bad);
+    }
+    let full = McFunctionSchematic::new_from_str("fill 0 0 0 31 31 31 minecraft:stone").get_blocks_pos()?;
+    assert_eq!(full.blocks.elements.len(), 32768);
+    Ok(())
+}
+
+#[test]
+fn test_fill_limit_boxes() -> Result<(), SchematicError> {
+    use crate::mcfunction::to_mcfunction::FILL_LIMIT;
+    use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockStatePosList};
+    use crate::utils::entities::EntitiesList;
+    use crate::utils::schematic_data::{SchematicData, Size};
+    use crate::utils::tile_entities::TileEntitiesList;
+    use std::collections::BTreeMap;
+
+    let block = |name: &str| {
+        Arc::new(BlockData { id: BlockId { name: Arc::from(name) }, properties: BTreeMap::new() })
+    };
+    let schematic = |list: BlockStatePosList, width, height, length| {
+        let size = Size { width, height, length };
+        SchematicData::new(list, TileEntitiesList::default(), EntitiesList::default(), size)
+    };
+
+    // 40³ 的实心石头超过单条 fill 上限，必须拆成多个区域
+    let stone = block("minecraft:stone");
+    let mut list = BlockStatePosList::default();
+    for y in 0..40 {
+        for z in 0..40 {
+            for x in 0..40 {
+                list.add_by_pos(x, y, z, stone.clone());
+            }
+        }
+    }
+    let commands = ToMcFunction::new(&schematic(list, 40, 40, 40))?.commands(false);
+    let mut filled = 0;
+    for command in &commands {
+        let args: Vec<i64> = command
+            .split_whitespace()
+            .skip(1)
+            .take(6)
+            .map(|a| a.trim_start_matches('~').parse().unwrap_or(0))
+            .collect();
+        let volume = if command.starts_with("fill") {
+            (args[3] - args[0] + 1) * (args[4] - args[1] + 1) * (args[5] - args[2] + 1)
+        } else {
+            1
+        };
+        assert!(volume <= FILL_LIMIT, "{}", command);
+        filled += volume;
+    }
+    assert_eq!(filled, 40 * 40 * 40);
+    let schem = McFunctionSchematic::new_from_str(&commands.join("\n")).get_blocks_pos()?;
+    assert_eq!(schem.blocks.elements.len(), 40 * 40 * 40);
+
+    // 沙子柱逐层放置，普通方块柱可以一条 fill
+    let mut list = BlockStatePosList::default();
+    for y in 0..3 {
+        list.add_by_pos(0, y, 0, block("minecraft:sand"));
+        list.add_by_pos(1, y, 0, stone.clone());
+    }
+    let commands = ToMcFunction::new(&schematic(list, 2, 3, 1))?.commands(false);
+    let count = |prefix: &str, block: &str| {
+        commands.iter().filter(|c| c.starts_with(prefix) && c.contains(block)).count()
+    };
+    assert_eq!((count("setblock", "minecraft:sand"), count("fill", "minecraft:sand")), (3, 0));
+    assert_eq!((count("setblock", "minecraft:stone"), count("fill", "minecraft:stone")), (0, 1));
+    Ok(())
+}
+
+#[test]
+fn test_worldgen_radius() -> anyhow::Result<()> {
+    use rust_lib::datapack::worldgen::{worldgen_datapack_files, WorldgenOptions, WorldgenStructure};
+    use rust_lib::utils::block_state_pos_list::{BlockData, BlockId, BlockStatePosList};
+    use rust_lib::utils::entities::EntitiesList;
+    use rust_lib::utils::schematic_data::{SchematicData, Size};
+    use rust_lib::utils::tile_entities::TileEntitiesList;
+    use std::collections::BTreeMap;
+
+    let stone = Arc::new(BlockData {
+        id: BlockId { name: Arc::from("minecraft:stone") },
+        properties: BTreeMap::new(),
+    });
+    let radius = |width: i32| -> anyhow::Result<i64> {
+        let mut list = BlockStatePosList::default();
+        for x in 0..width {
+            list.add_by_pos(x, 0, 0, stone.clone());
+        }
+        let size = Size { width, height: 1, length: 1 };
+        let data = SchematicData::new(list, TileEntitiesList::default(), EntitiesList::default(), size);
+        let structure = WorldgenStructure { name: "wide".to_string(), data };
+        let files = worldgen_datapack_files(vec![structure], &WorldgenOptions::default())?;
+        let (_, bytes) = files
+            .iter()
+            .find(|(path, _)| path.ends_with("worldgen/structure/wide.json"))
+            .ok_or(anyhow::anyhow!("missing structure json"))?;
+        let json: serde_json::Value = serde_json::from_slice(bytes)?;
+        json["max_distance_from_center"].as_i64().ok_or(anyhow::anyhow!("missing radius"))
+    };
+    // 120 宽切成 48、48、24 三块，起始块中心在 x = 24，最后一块的远边在 x = 120
+    assert!(radius(120)? >= 120 - 24);
+    assert!(radius(200).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_map_colors_tile() {
+    use rust_lib::map_art::map_item::{MapColors, MAP_SIZE};
+
+    let (width, height) = (130, 3);
+    let colors: Vec<u8> = (0..width * height).map(|i| (i % 200 + 1) as u8).collect();
+    let map = MapColors { width, height, colors: colors.clone() };
+    assert_eq!(map.grid(), (2, 1));
+
+    let left = map.tile(0, 0);
+    assert_eq!(left.len(), MAP_SIZE * MAP_SIZE);
+    assert_eq!(&left[..MAP_SIZE], &colors[..MAP_SIZE]);
+    assert_eq!(&left[MAP_SIZE..2 * MAP_SIZE], &colors[width..width + MAP_SIZE]);
+    assert!(left[height * MAP_SIZE..].iter().all(|&c| c == 0));
+
+    // 右侧只剩两列，其余补透明
+    let right = map.tile(1, 0);
+    for y in 0..height {
+        let row = &right[y * MAP_SIZE..(y + 1) * MAP_SIZE];
+        assert_eq!(&row[..2], &colors[y * width + MAP_SIZE..y * width + width]);
+        assert!(row[2..].iter().all(|&c| c == 0));
+    }
+    assert!(right[height * MAP_SIZE..].iter().all(|&c| c == 0));
+}
+
+#[test]
+fn test_valley_heights() {
+    use rust_lib::map_art::builder::valley_heights;
+    use rust_lib::map_art::palette::MapTone;
+
+    let columns = [
+        vec![MapTone::High, MapTone::High, MapTone::Low, MapTone::Normal, MapTone::Low, MapTone::High],
+        vec![MapTone::Low, MapTone::Low, MapTone::Low, MapTone::High, MapTone::Normal, MapTone::Normal],
+        vec![MapTone::Normal; 6],
+    ];
+    let (width, height) = (columns.len(), 6);
+    let mut pixels = vec![None; width * height];
+    for (x, column) in columns.iter().enumerate() {
+        for (y, tone) in column.iter().enumerate() {
+            pixels[y * width + x] = Some((0, *tone));
+        }
+    }
+    let (heights, base) = valley_heights(&pixels, width, height);
+    for (x, column) in columns.iter().enumerate() {
+        // 每格与北侧方块的高度差决定明暗，必须与原图一致
+        let mut north = base[x];
+        let mut lowest = base[x];
+        for (y, tone) in column.iter().enumerate() {
+            let h = heights[y * width + x];
+            assert_eq!((h - north).signum(), tone.delta(), "x {} y {}", x, y);
+            north = h;
+            lowest = lowest.min(h);
+        }
+        assert_eq!(lowest, 0, "x {}", x);
+    }
+}
+
+#[test]
+fn test_rule_matcher() -> anyhow::Result<()> {
+    use rust_lib::modules::modules_data::replace_data::{PropertyValues, ReplacementRule, RuleMatcher};
+    use rust_lib::utils::block_state_pos_list::{BlockData, BlockId};
+    use rust_lib::utils::minecraft_data::block_tags::BlockTags;
+    use std::collections::BTreeMap;
+
+    let tags = BlockTags::parse(
+        r##"{
+            "minecraft:logs": { "values": ["minecraft:oak_log", "#minecraft:birch_logs"] },
+            "minecraft:birch_logs": { "values": ["minecraft:birch_log", "birch_wood"] }
+        }"##,
+    )?;
+    let none = BTreeMap::new();
+    let matcher = |mode: i64, pattern: &str, properties: &BTreeMap<String, PropertyValues>| {
+        let rule: ReplacementRule = serde_json::from_value(serde_json::json!({
+            "schematic_id": 0,
+            "mode": mode,
+            "original_id": pattern,
+            "quantity": 0,
+            "global": true,
+            "match_properties": properties,
+        }))?;
+        RuleMatcher::new(&rule, &tags)
+    };
+    let hits = |m: &RuleMatcher, names: &[&str]| -> Vec<bool> {
+        names.iter().map(|n| m.matches(&BlockData::from_name(n))).collect()
+    };
+
+    let tag = matcher(3, "#logs", &none)?;
+    assert_eq!(hits(&tag, &["oak_log", "birch_log", "birch_wood", "spruce_log"]), [true, true, true, false]);
+    assert!(matcher(3, "#no_such_tag", &none).is_err());
+
+    let glob = matcher(4, "*_log", &none)?;
+    let names = ["oak_log", "stripped_oak_log", "oak_log_slab", "oak_planks"];
+    assert_eq!(hits(&glob, &names), [true, true, false, false]);
+    let glob = matcher(4, "?ak_planks", &none)?;
+    assert_eq!(hits(&glob, &["oak_planks", "dark_oak_planks"]), [true, false]);
+
+    // 正则需匹配带命名空间的完整 id
+    let regex = matcher(5, "minecraft:(oak|birch)_log", &none)?;
+    let names = ["oak_log", "birch_log", "stripped_oak_log", "oak_log_slab"];
+    assert_eq!(hits(&regex, &names), [true, true, false, false]);
+    assert_eq!(hits(&matcher(5, "oak", &none)?, &["oak_log"]), [false]);
+    assert!(matcher(5, "(", &none).is_err());
+
+    let mut properties = BTreeMap::new();
+    properties.insert("axis".to_string(), PropertyValues::Many(vec!["x".to_string(), "z".to_string()]));
+    let log = |name: &str, axis: &str| BlockData {
+        id: BlockId { name: Arc::from(format!("minecraft:{}", name)) },
+        properties: BTreeMap::from([(Arc::from("axis"), Arc::from(axis))]),
+    };
+    let glob = matcher(4, "*_log", &properties)?;
+    assert!(glob.matches(&log("oak_log", "x")));
+    assert!(!glob.matches(&log("oak_log", "y")));
+    assert!(glob.matches(&log("birch_log", "z")));
+    assert!(!glob.matches(&BlockData::from_name("oak_log")));
+    Ok(())
+}
+
+#[test]
+fn test_extract() -> anyhow::Result<()> {
+    use fastnbt::Value;
+    use rust_lib::modules::modules_data::extract_data::{extract, BlockPredicate, ExtractMode, ExtractOptions};
+    use rust_lib::utils::block_state_pos_list::{BlockData, BlockPos, BlockStatePosList};
+    use rust_lib::utils::entities::EntitiesList;
+    use rust_lib::utils::minecraft_data::block_tags::BlockTags;
+    use rust_lib::utils::schematic_data::{SchematicData, Size};
+    use rust_lib::utils::tile_entities::{TileEntities, TileEntitiesList};
+    use std::collections::{BTreeMap, HashMap};
+
+    let data = || -> anyhow::Result<SchematicData> {
+        let mut blocks = BlockStatePosList::default();
+        blocks.add_by_pos(0, 0, 0, BlockData::from_name("stone"));
+        blocks.add_by_pos(1, 0, 0, BlockData::from_name("chest"));
+        blocks.add_by_pos(2, 0, 0, BlockData::from_name("barrel"));
+        let mut tile_entities = TileEntitiesList::default();
+        for x in [1, 2] {
+            let nbt = Value::Compound(HashMap::new());
+            tile_entities.elements.push(TileEntities { pos: BlockPos { x, y: 0, z: 0 }, nbt });
+        }
+        let entity = |id: &str| {
+            Value::Compound(HashMap::from([("id".to_string(), Value::String(id.to_string()))]))
+        };
+        let entities = EntitiesList::from_nbt(&[entity("minecraft:armor_stand"), entity("minecraft:pig")], 1)?;
+        Ok(SchematicData::new(blocks, tile_entities, entities, Size { width: 3, height: 1, length: 1 }))
+    };
+    let predicate = |mode, pattern: &str| BlockPredicate {
+        mode,
+        original_id: Some(pattern.to_string()),
+        original_details: None,
+        match_properties: BTreeMap::new(),
+    };
+    let options = |mode| ExtractOptions {
+        mode,
+        blocks: vec![predicate(0, "minecraft:chest")],
+        entities: vec![predicate(4, "armor_*")],
+    };
+    let tags = BlockTags::default();
+
+    // 方块实体跟随所在方块保留或删除
+    let mut kept = data()?;
+    let stats = extract(&mut kept, &options(ExtractMode::Keep), &tags)?;
+    assert_eq!((stats.blocks, stats.tile_entities, stats.entities), (1, 1, 1));
+    assert_eq!((stats.removed_blocks, stats.removed_entities), (2, 1));
+    assert_eq!(kept.tile_entities_list.elements[0].pos.x, 1);
+    assert_eq!((kept.size.width, kept.size.height, kept.size.length), (3, 1, 1));
+
+    let mut removed = data()?;
+    let stats = extract(&mut removed, &options(ExtractMode::Remove), &tags)?;
+    assert_eq!((stats.blocks, stats.tile_entities, stats.entities), (2, 1, 1));
+    assert_eq!((stats.removed_blocks, stats.removed_entities), (1, 1));
+    assert_eq!(removed.tile_entities_list.elements[0].pos.x, 2);
+
+    let empty = ExtractOptions { mode: ExtractMode::Keep, blocks: Vec::new(), entities: Vec::new() };
+    assert!(extract(&mut data()?, &empty, &tags).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_shulker_boxes() {
+    use crate::utils::block_state_pos_list::BlockId;
+    use crate::utils::requirements::{shulker_boxes, Requirements};
 
+    let id = |name: &str| BlockId { name: Arc::from(name) };
+    let boxes = |items: &[(&str, i32)]| {
+        let mut req = Requirements::new();
+        for (name, count) in items {
+            req.add_requirement(id(name), *count);
+        }
+        shulker_boxes(&req)
+    };
+    assert_eq!(boxes(&[]), 0);
+    assert_eq!(boxes(&[("minecraft:stone", 64 * 27)]), 1);
+    assert_eq!(boxes(&[("minecraft:stone", 64 * 27 + 1)]), 2);
+    // 告示牌 16 一组，床不能堆叠，每种材料单独占格
+    assert_eq!(boxes(&[("minecraft:oak_sign", 16 * 27)]), 1);
+    assert_eq!(boxes(&[("minecraft:oak_sign", 16 * 27 + 1)]), 2);
+    assert_eq!(boxes(&[("minecraft:white_bed", 28)]), 2);
+    assert_eq!(boxes(&[("minecraft:stone", 1), ("minecraft:oak_sign", 1), ("minecraft:white_bed", 1)]), 1);
+}
+
+#[test]
+fn test_split_by_budget() -> anyhow::Result<()> {
+    use rust_lib::split_schematic::split_schematic::{split_by_budget, BudgetKind, Offset, SplitBudget};
+    use rust_lib::utils::block_state_pos_list::{BlockData, BlockStatePosList};
+    use rust_lib::utils::requirements::Requirements;
+    use rust_lib::utils::schematic_data::Size;
+
+    // 按 y 分层：两层各 4 块、一层 16 块、两层各 3 块
+    let mut list = BlockStatePosList::default();
+    for (y, count, name) in [(0, 4, "stone"), (1, 4, "stone"), (2, 16, "dirt"), (3, 3, "glass"), (4, 3, "glass")] {
+        let block = BlockData::from_name(name);
+        for i in 0..count {
+            list.add_by_pos(i % 4, y, i / 4, block.clone());
+        }
+    }
+    let size = Size { width: 4, height: 5, length: 4 };
+    let split = |descending| {
+        let budget = SplitBudget { kind: BudgetKind::Blocks, limit: 10, axis: 1, descending };
+        split_by_budget(&list.elements, &size, &budget)
+    };
+
+    let layout = |parts: &[(BlockStatePosList, Size, Offset, Requirements)]| -> Vec<(i32, i32, usize)> {
+        parts.iter().map(|(blocks, size, offset, _)| (offset.y, size.height, blocks.elements.len())).collect()
+    };
+    // 单层 16 块超出预算时单独成块
+    assert_eq!(layout(&split(false)?), vec![(0, 2, 8), (2, 1, 16), (3, 2, 6)]);
+
+    let parts = split(true)?;
+    assert_eq!(layout(&parts), vec![(3, 2, 6), (2, 1, 16), (0, 2, 8)]);
+    for (blocks, _, _, req) in &parts {
+        let total: i32 = req.get_requirements().values().sum();
+        assert_eq!(total as usize, blocks.elements.len());
+    }
+
+    let zero = SplitBudget { kind: BudgetKind::Blocks, limit: 0, axis: 1, descending: false };
+    assert!(split_by_budget(&list.elements, &size, &zero).is_err());
+    Ok(())
+}
 
 fn start_memory_peak_watcher() -> Arc<AtomicU64> {
     let peak = Arc::new(AtomicU64::new(0));
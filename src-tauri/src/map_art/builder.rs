@@ -0,0 +1,187 @@
+use crate::map_art::generator::MapPixels;
+use crate::utils::block_state_pos_list::{BlockData, BlockPos, BlockStatePos, BlockStatePosList};
+use crate::utils::entities::EntitiesList;
+use crate::utils::schematic_data::{SchematicData, Size};
+use crate::utils::tile_entities::TileEntitiesList;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+pub const MAP_SIZE: i32 = 128;
+
+/// 按地图网格切出的一块，offset 为其在整幅地图画中的位置
+pub struct MapPart {
+    pub index: usize,
+    pub column: usize,
+    pub row: usize,
+    pub offset: BlockPos,
+    pub data: SchematicData,
+}
+
+/// 受重力影响或不是完整方块、下方必须有支撑的方块
+pub fn needs_support(name: &str) -> bool {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    matches!(
+        name,
+        "sand" | "red_sand" | "gravel" | "suspicious_sand" | "suspicious_gravel" | "snow" | "dragon_egg"
+    ) || name.ends_with("carpet")
+        || name.ends_with("_concrete_powder")
+        || name.ends_with("pressure_plate")
+        || name.ends_with("anvil")
+}
+
+/// 谷底式阶梯：只保留相邻高度的大小关系，每列高度取满足明暗的最小值
+pub fn valley_heights(pixels: &MapPixels, width: usize, height: usize) -> (Vec<i32>, Vec<i32>) {
+    let mut heights = vec![0i32; width * height];
+    let mut base = vec![0i32; width];
+    for x in 0..width {
+        // 相等的相邻方块合并为一组，组与组之间只有升降关系
+        let mut groups = vec![0usize; height];
+        let mut rises: Vec<bool> = Vec::new();
+        let mut group = 0;
+        for y in 0..height {
+            if let Some((_, tone)) = pixels[y * width + x] {
+                if tone.delta() != 0 {
+                    rises.push(tone.delta() > 0);
+                    group += 1;
+                }
+            }
+            groups[y] = group;
+        }
+        let count = group + 1;
+        let mut up = vec![0i32; count];
+        for g in 1..count {
+            up[g] = if rises[g - 1] { up[g - 1] + 1 } else { 0 };
+        }
+        let mut down = vec![0i32; count];
+        for g in (0..count - 1).rev() {
+            down[g] = if rises[g] { 0 } else { down[g + 1] + 1 };
+        }
+        base[x] = up[0].max(down[0]);
+        for y in 0..height {
+            let g = groups[y];
+            heights[y * width + x] = up[g].max(down[g]);
+        }
+    }
+    (heights, base)
+}
+
+/// 在需要支撑的方块下方补方块，放不下时整体上移一格，返回上移的格数
+pub fn add_support(list: &mut BlockStatePosList, size: &mut Size, block: &Arc<BlockData>) -> i32 {
+    let is_air = |b: &BlockData| b.id.name.as_ref() == "minecraft:air";
+    let occupied: HashSet<(i32, i32, i32)> = list
+        .elements
+        .iter()
+        .filter(|e| !is_air(&e.block))
+        .map(|e| (e.pos.x, e.pos.y, e.pos.z))
+        .collect();
+    let mut supports: Vec<(i32, i32, i32)> = list
+        .elements
+        .iter()
+        .filter(|e| needs_support(&e.block.id.name))
+        .map(|e| (e.pos.x, e.pos.y - 1, e.pos.z))
+        .filter(|p| !occupied.contains(p))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    if supports.is_empty() {
+        return 0;
+    }
+    supports.sort();
+
+    let placed: HashSet<(i32, i32, i32)> = supports.iter().copied().collect();
+    list.elements
+        .retain(|e| !(is_air(&e.block) && placed.contains(&(e.pos.x, e.pos.y, e.pos.z))));
+    for (x, y, z) in supports {
+        list.add(BlockPos { x, y, z }, block.clone());
+    }
+
+    let shift = list.elements.iter().map(|e| e.pos.y).min().unwrap_or(0).min(0).abs();
+    if shift > 0 {
+        for element in list.elements.iter_mut() {
+            element.pos.y += shift;
+        }
+    }
+    let top = list.elements.iter().map(|e| e.pos.y).max().unwrap_or(0);
+    size.height = size.height.max(top + 1);
+    shift
+}
+
+/// 按 128×128 地图网格切分；阶梯模式下每块都带上北侧的参照行
+pub fn split_map_parts(
+    data: &SchematicData,
+    axis: &str,
+    staircase: bool,
+    width: u32,
+    height: u32,
+) -> Vec<MapPart> {
+    let (width, height) = (width as i32, height as i32);
+    let row_offset = staircase as i32;
+    let columns = (width + MAP_SIZE - 1) / MAP_SIZE;
+    let rows = (height + MAP_SIZE - 1) / MAP_SIZE;
+    let mut buckets: Vec<Vec<BlockStatePos>> = (0..columns * rows).map(|_| Vec::new()).collect();
+
+    for element in &data.blocks.elements {
+        let pos = element.pos;
+        // 还原成图片上的像素坐标
+        let (ix, iy) = match axis {
+            "x" => (pos.z, height - 1 - pos.y),
+            "z" => (pos.x, height - 1 - pos.y),
+            _ => (pos.x, pos.z - row_offset),
+        };
+        if ix < 0 || ix >= width || iy >= height || iy < -row_offset {
+            continue;
+        }
+        let column = ix / MAP_SIZE;
+        if iy >= 0 {
+            buckets[((iy / MAP_SIZE) * columns + column) as usize].push(element.clone());
+        }
+        // 每块最后一行同时是下一块的参照行，底座行只属于第一行地图
+        let next = iy + 1;
+        if staircase && next % MAP_SIZE == 0 && next < height {
+            buckets[((next / MAP_SIZE) * columns + column) as usize].push(element.clone());
+        }
+    }
+
+    let mut parts = Vec::new();
+    for (index, elements) in buckets.into_iter().enumerate() {
+        if elements.is_empty() {
+            continue;
+        }
+        let (column, row) = (index as i32 % columns, index as i32 / columns);
+        let cols = (width - column * MAP_SIZE).min(MAP_SIZE);
+        let rows_in = (height - row * MAP_SIZE).min(MAP_SIZE);
+        let bottom = height - (height.min((row + 1) * MAP_SIZE));
+        let min_y = elements.iter().map(|e| e.pos.y).min().unwrap_or(0);
+        let max_y = elements.iter().map(|e| e.pos.y).max().unwrap_or(0);
+        let (offset, size) = match axis {
+            "x" => (
+                BlockPos { x: 0, y: bottom, z: column * MAP_SIZE },
+                Size { width: 1, height: rows_in, length: cols },
+            ),
+            "z" => (
+                BlockPos { x: column * MAP_SIZE, y: bottom, z: 0 },
+                Size { width: cols, height: rows_in, length: 1 },
+            ),
+            _ => (
+                BlockPos { x: column * MAP_SIZE, y: min_y, z: row * MAP_SIZE },
+                Size { width: cols, height: max_y - min_y + 1, length: rows_in + row_offset },
+            ),
+        };
+        let mut list = BlockStatePosList::default();
+        for element in elements {
+            let pos = element.pos;
+            list.add(
+                BlockPos { x: pos.x - offset.x, y: pos.y - offset.y, z: pos.z - offset.z },
+                element.block,
+            );
+        }
+        parts.push(MapPart {
+            index,
+            column: column as usize,
+            row: row as usize,
+            offset,
+            data: SchematicData::new(list, TileEntitiesList::default(), EntitiesList::default(), size),
+        });
+    }
+    parts
+}
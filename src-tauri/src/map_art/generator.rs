@@ -0,0 +1,293 @@
+use crate::map_art::builder::{add_support, split_map_parts, valley_heights, MapPart};
+use crate::map_art::palette::{MapPalette, MapTone, ToneMask};
+use crate::utils::block_state_pos_list::{BlockData, BlockPos, BlockStatePosList};
+use crate::utils::entities::EntitiesList;
+use crate::utils::schematic_data::{SchematicData, Size};
+use crate::utils::tile_entities::TileEntitiesList;
+use anyhow::Result;
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DitherMode {
+    None,
+    #[default]
+    FloydSteinberg,
+    Atkinson,
+    Ordered,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapArtOptions {
+    pub width: u32,
+    pub height: u32,
+    pub rotation: u32,
+    pub dither: DitherMode,
+    // 阶梯模式：用高度差得到三档明暗
+    pub staircase: bool,
+    pub max_height: i32,
+    pub replace_air: bool,
+    pub axis: String,
+    // 阶梯模式第一行北侧的参照方块
+    pub base_block: String,
+    // 按 128×128 地图切分为多个蓝图
+    pub split_maps: bool,
+    // 沙子、混凝土粉末、地毯等方块下方补支撑
+    pub support: bool,
+    pub support_block: String,
+    // 谷底式阶梯，明暗不变的前提下压低每列高度
+    pub valley: bool,
+}
+
+impl Default for MapArtOptions {
+    fn default() -> Self {
+        Self {
+            width: 128,
+            height: 128,
+            rotation: 0,
+            dither: DitherMode::FloydSteinberg,
+            staircase: false,
+            max_height: 256,
+            replace_air: false,
+            axis: "y".to_string(),
+            base_block: "cobblestone".to_string(),
+            split_maps: false,
+            support: false,
+            support_block: "cobblestone".to_string(),
+            valley: false,
+        }
+    }
+}
+
+/// 每个像素的量化结果，None 表示透明
+pub type MapPixels = Vec<Option<(usize, MapTone)>>;
+
+pub struct Quantized {
+    pub pixels: MapPixels,
+    // 阶梯模式下每个像素的 y，非阶梯模式全为 0
+    pub heights: Vec<i32>,
+    // 每列第一行北侧参照方块的 y
+    pub base: Vec<i32>,
+}
+
+pub struct MapArt {
+    pub data: SchematicData,
+    pub preview: RgbaImage,
+    // 开启切分时的各张地图，offset 相对于 data
+    pub parts: Vec<MapPart>,
+}
+
+const BAYER: [[f32; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+const ORDERED_SPREAD: f32 = 40.0;
+
+fn diffusion(mode: DitherMode) -> &'static [(i32, i32, f32)] {
+    match mode {
+        DitherMode::FloydSteinberg => &[
+            (1, 0, 7.0 / 16.0),
+            (-1, 1, 3.0 / 16.0),
+            (0, 1, 5.0 / 16.0),
+            (1, 1, 1.0 / 16.0),
+        ],
+        // Atkinson 只扩散 3/4 的误差，对比度更高
+        DitherMode::Atkinson => &[
+            (1, 0, 1.0 / 8.0),
+            (2, 0, 1.0 / 8.0),
+            (-1, 1, 1.0 / 8.0),
+            (0, 1, 1.0 / 8.0),
+            (1, 1, 1.0 / 8.0),
+            (0, 2, 1.0 / 8.0),
+        ],
+        DitherMode::None | DitherMode::Ordered => &[],
+    }
+}
+
+pub fn prepare_image(bytes: &[u8], options: &MapArtOptions) -> Result<RgbaImage> {
+    let image = image::load_from_memory(bytes)?;
+    let image = match options.rotation % 360 {
+        90 => image.rotate90(),
+        180 => image.rotate180(),
+        270 => image.rotate270(),
+        _ => image,
+    };
+    let (w, h) = (image.width().max(1), image.height().max(1));
+    let (tw, th) = match (options.width, options.height) {
+        (0, 0) => (w, h),
+        (0, th) => ((w as u64 * th as u64 / h as u64).max(1) as u32, th),
+        (tw, 0) => (tw, (h as u64 * tw as u64 / w as u64).max(1) as u32),
+        size => size,
+    };
+    if (tw, th) == (w, h) {
+        return Ok(image.to_rgba8());
+    }
+    Ok(image.resize_exact(tw, th, FilterType::Lanczos3).to_rgba8())
+}
+
+/// 逐行量化；阶梯模式下同时记录每列高度，超出高度上限时禁止继续升高或降低
+pub fn quantize(image: &RgbaImage, palette: &MapPalette, options: &MapArtOptions) -> Quantized {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mut buffer: Vec<[f32; 3]> = image
+        .pixels()
+        .map(|p| [p.0[0] as f32, p.0[1] as f32, p.0[2] as f32])
+        .collect();
+    let kernel = diffusion(options.dither);
+    let max_height = options.max_height.max(1);
+
+    let mut pixels: MapPixels = vec![None; width * height];
+    let mut heights = vec![0i32; width * height];
+    let mut current = vec![0i32; width];
+    let mut min = vec![0i32; width];
+    let mut max = vec![0i32; width];
+    let mut cache: HashMap<(u32, u8), (usize, MapTone)> = HashMap::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            heights[index] = current[x];
+            if image.get_pixel(x as u32, y as u32).0[3] < 128 {
+                continue;
+            }
+            let mut mask = if options.staircase { ToneMask::ALL } else { ToneMask::FLAT };
+            if options.staircase {
+                if current[x] + 1 > max[x] && current[x] + 1 - min[x] >= max_height {
+                    mask = mask.without(MapTone::High);
+                }
+                if current[x] - 1 < min[x] && max[x] - (current[x] - 1) >= max_height {
+                    mask = mask.without(MapTone::Low);
+                }
+            }
+            let mut color = buffer[index];
+            if options.dither == DitherMode::Ordered {
+                let offset = (BAYER[y % 4][x % 4] + 0.5) / 16.0 - 0.5;
+                for c in color.iter_mut() {
+                    *c += offset * ORDERED_SPREAD;
+                }
+            }
+            let key = color.map(|c| c.round().clamp(0.0, 255.0) as u32);
+            let (entry, tone) = *cache
+                .entry((key[0] << 16 | key[1] << 8 | key[2], mask.bits()))
+                .or_insert_with(|| palette.nearest(key.map(|c| c as f32), mask));
+            pixels[index] = Some((entry, tone));
+
+            if options.staircase {
+                current[x] += tone.delta();
+                min[x] = min[x].min(current[x]);
+                max[x] = max[x].max(current[x]);
+                heights[index] = current[x];
+            }
+
+            if !kernel.is_empty() {
+                let chosen = palette.colors[entry].tone(tone);
+                let error = [
+                    color[0] - chosen[0] as f32,
+                    color[1] - chosen[1] as f32,
+                    color[2] - chosen[2] as f32,
+                ];
+                for &(dx, dy, weight) in kernel {
+                    let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                    if nx < 0 || nx >= width as i32 || ny >= height as i32 {
+                        continue;
+                    }
+                    let target = &mut buffer[ny as usize * width + nx as usize];
+                    for c in 0..3 {
+                        target[c] = (target[c] + error[c] * weight).clamp(-64.0, 319.0);
+                    }
+                }
+            }
+        }
+    }
+
+    // 每列单独归一化，最低点为 0
+    if options.staircase {
+        for x in 0..width {
+            for y in 0..height {
+                heights[y * width + x] -= min[x];
+            }
+        }
+    }
+    let base = min.iter().map(|m| -m).collect();
+    Quantized { pixels, heights, base }
+}
+
+pub fn generate_map_art(bytes: &[u8], palette: &MapPalette, options: &MapArtOptions) -> Result<MapArt> {
+    let image = prepare_image(bytes, options)?;
+    let (width, height) = (image.width(), image.height());
+    let Quantized { pixels, mut heights, mut base } = quantize(&image, palette, options);
+    if options.staircase && options.valley {
+        (heights, base) = valley_heights(&pixels, width as usize, height as usize);
+    }
+
+    let axis = options.axis.to_lowercase();
+    if options.staircase && axis != "y" {
+        anyhow::bail!("staircase map art must be laid flat on the y axis");
+    }
+
+    let blocks: Vec<Arc<BlockData>> = palette.colors.iter().map(|c| BlockData::from_name(&c.block)).collect();
+    let air = BlockData::from_name("air");
+    let base_block = BlockData::from_name(&options.base_block);
+    let mut list = BlockStatePosList::default();
+    let mut preview = RgbaImage::new(width, height);
+    let row_offset = options.staircase as i32;
+    let mut top = 0;
+
+    for y in 0..height {
+        for x in 0..width {
+            let index = (y * width + x) as usize;
+            let (ix, iy) = (x as i32, y as i32);
+            let pos = match axis.as_str() {
+                "x" => BlockPos { x: 0, y: height as i32 - 1 - iy, z: ix },
+                "z" => BlockPos { x: ix, y: height as i32 - 1 - iy, z: 0 },
+                _ => BlockPos { x: ix, y: heights[index], z: iy + row_offset },
+            };
+            match pixels[index] {
+                Some((entry, tone)) => {
+                    let c = palette.colors[entry].tone(tone);
+                    preview.put_pixel(x, y, Rgba([c[0], c[1], c[2], 255]));
+                    top = top.max(pos.y);
+                    list.add(pos, blocks[entry].clone());
+                }
+                None if options.replace_air => list.add(pos, air.clone()),
+                None => {}
+            }
+        }
+    }
+    if options.staircase {
+        for x in 0..width {
+            top = top.max(base[x as usize]);
+            list.add(BlockPos { x: x as i32, y: base[x as usize], z: 0 }, base_block.clone());
+        }
+    }
+
+    let size = match axis.as_str() {
+        "x" => Size { width: 1, height: height as i32, length: width as i32 },
+        "z" => Size { width: width as i32, height: height as i32, length: 1 },
+        _ => Size { width: width as i32, height: top + 1, length: height as i32 + row_offset },
+    };
+    let mut data = SchematicData::new(list, TileEntitiesList::default(), EntitiesList::default(), size);
+
+    let mut parts = if options.split_maps {
+        split_map_parts(&data, &axis, options.staircase, width, height)
+    } else {
+        Vec::new()
+    };
+    if options.support {
+        let support = BlockData::from_name(&options.support_block);
+        for part in parts.iter_mut() {
+            part.offset.y -= add_support(&mut part.data.blocks, &mut part.data.size, &support);
+        }
+        let shift = add_support(&mut data.blocks, &mut data.size, &support);
+        for part in parts.iter_mut() {
+            part.offset.y += shift;
+        }
+    }
+    Ok(MapArt { data, preview, parts })
+}
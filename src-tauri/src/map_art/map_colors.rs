@@ -0,0 +1,218 @@
+use crate::map_art::palette::{MapPalette, MapTone, PaletteColor};
+
+/// 原版地图基础色，下标即地图颜色 id，0 为透明
+pub const MAP_BASE_COLORS: [[u8; 3]; 62] = [
+    [0, 0, 0],
+    [127, 178, 56],
+    [247, 233, 163],
+    [199, 199, 199],
+    [255, 0, 0],
+    [160, 160, 255],
+    [167, 167, 167],
+    [0, 124, 0],
+    [255, 255, 255],
+    [164, 168, 184],
+    [151, 109, 77],
+    [112, 112, 112],
+    [64, 64, 255],
+    [143, 119, 72],
+    [255, 252, 245],
+    [216, 127, 51],
+    [178, 76, 216],
+    [102, 153, 216],
+    [229, 229, 51],
+    [127, 204, 25],
+    [242, 127, 165],
+    [76, 76, 76],
+    [153, 153, 153],
+    [76, 127, 153],
+    [127, 63, 178],
+    [51, 76, 178],
+    [102, 76, 51],
+    [102, 127, 51],
+    [153, 51, 51],
+    [25, 25, 25],
+    [250, 238, 77],
+    [92, 219, 213],
+    [74, 128, 255],
+    [0, 217, 58],
+    [129, 86, 49],
+    [112, 2, 0],
+    [209, 177, 161],
+    [159, 82, 36],
+    [149, 87, 108],
+    [112, 108, 138],
+    [186, 133, 36],
+    [103, 117, 53],
+    [160, 77, 78],
+    [57, 41, 35],
+    [135, 107, 98],
+    [87, 92, 92],
+    [122, 73, 88],
+    [76, 62, 92],
+    [76, 50, 35],
+    [76, 82, 42],
+    [142, 60, 46],
+    [37, 22, 16],
+    [189, 48, 49],
+    [148, 63, 97],
+    [92, 25, 29],
+    [22, 126, 134],
+    [58, 142, 140],
+    [86, 44, 62],
+    [20, 180, 133],
+    [100, 100, 100],
+    [216, 175, 147],
+    [127, 167, 150],
+];
+
+const DYES: [(&str, u8, u8); 16] = [
+    // (染料名, 染色方块颜色, 陶瓦颜色)
+    ("white", 8, 36),
+    ("orange", 15, 37),
+    ("magenta", 16, 38),
+    ("light_blue", 17, 39),
+    ("yellow", 18, 40),
+    ("lime", 19, 41),
+    ("pink", 20, 42),
+    ("gray", 21, 43),
+    ("light_gray", 22, 44),
+    ("cyan", 23, 45),
+    ("purple", 24, 46),
+    ("blue", 25, 47),
+    ("brown", 26, 48),
+    ("green", 27, 49),
+    ("red", 28, 50),
+    ("black", 29, 51),
+];
+
+const NAMED: &[(&str, u8)] = &[
+    ("grass_block", 1),
+    ("slime_block", 1),
+    ("sand", 2),
+    ("sandstone", 2),
+    ("smooth_sandstone", 2),
+    ("birch_planks", 2),
+    ("birch_log", 2),
+    ("glowstone", 2),
+    ("end_stone", 2),
+    ("bone_block", 2),
+    ("cobweb", 3),
+    ("mushroom_stem", 3),
+    ("redstone_block", 4),
+    ("tnt", 4),
+    ("ice", 5),
+    ("packed_ice", 5),
+    ("blue_ice", 5),
+    ("iron_block", 6),
+    ("brewing_stand", 6),
+    ("heavy_weighted_pressure_plate", 6),
+    ("oak_leaves", 7),
+    ("spruce_leaves", 7),
+    ("birch_leaves", 7),
+    ("jungle_leaves", 7),
+    ("acacia_leaves", 7),
+    ("dark_oak_leaves", 7),
+    ("snow_block", 8),
+    ("snow", 8),
+    ("clay", 9),
+    ("dirt", 10),
+    ("coarse_dirt", 10),
+    ("granite", 10),
+    ("jungle_planks", 10),
+    ("stone", 11),
+    ("cobblestone", 11),
+    ("andesite", 11),
+    ("stone_bricks", 11),
+    ("gravel", 11),
+    ("water", 12),
+    ("oak_planks", 13),
+    ("oak_log", 13),
+    ("quartz_block", 14),
+    ("diorite", 14),
+    ("sea_lantern", 14),
+    ("acacia_planks", 15),
+    ("pumpkin", 15),
+    ("terracotta", 15),
+    ("red_sand", 15),
+    ("red_sandstone", 15),
+    ("purpur_block", 16),
+    ("dark_oak_planks", 26),
+    ("soul_sand", 26),
+    ("gold_block", 30),
+    ("diamond_block", 31),
+    ("lapis_block", 32),
+    ("emerald_block", 33),
+    ("spruce_planks", 34),
+    ("podzol", 34),
+    ("netherrack", 35),
+    ("nether_bricks", 35),
+    ("red_nether_bricks", 35),
+    ("brown_mushroom_block", 10),
+    ("crimson_nylium", 52),
+    ("warped_nylium", 55),
+    ("warped_wart_block", 58),
+    ("deepslate", 59),
+    ("raw_iron_block", 60),
+];
+
+pub fn shade(base: u8, tone: MapTone) -> [u8; 3] {
+    let factor = match tone {
+        MapTone::Low => 180,
+        MapTone::Normal => 220,
+        MapTone::High => 255,
+    };
+    MAP_BASE_COLORS[base as usize].map(|c| (c as u32 * factor / 255) as u8)
+}
+
+/// 写入 colors 数组的字节：基础色 * 4 + 明暗档位
+pub fn color_byte(base: u8, tone: MapTone) -> u8 {
+    if base == 0 {
+        return 0;
+    }
+    let shade = match tone {
+        MapTone::Low => 0,
+        MapTone::Normal => 1,
+        MapTone::High => 2,
+    };
+    base * 4 + shade
+}
+
+/// 原版全部地图色组成的调色板，block 字段存基础色 id
+pub fn vanilla_palette() -> MapPalette {
+    MapPalette {
+        colors: (1..MAP_BASE_COLORS.len() as u8)
+            .map(|id| PaletteColor {
+                block: id.to_string(),
+                tones: [shade(id, MapTone::Low), shade(id, MapTone::Normal), shade(id, MapTone::High)],
+            })
+            .collect(),
+    }
+}
+
+/// 按方块名推断地图基础色，未收录的返回 None
+pub fn block_map_color(name: &str) -> Option<u8> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    if let Some(&(_, color)) = NAMED.iter().find(|(n, _)| *n == name) {
+        return Some(color);
+    }
+    for (dye, dyed, terracotta) in DYES {
+        let Some(rest) = name.strip_prefix(dye).and_then(|r| r.strip_prefix('_')) else {
+            continue;
+        };
+        return Some(if rest == "terracotta" { terracotta } else { dyed });
+    }
+    None
+}
+
+/// 任意颜色找最接近的基础色(按 normal 档比较)
+pub fn nearest_base(color: [u8; 3]) -> u8 {
+    (1..MAP_BASE_COLORS.len() as u8)
+        .min_by_key(|id| {
+            let c = shade(*id, MapTone::Normal);
+            (0..3)
+                .map(|i| (c[i] as i32 - color[i] as i32).pow(2))
+                .sum::<i32>()
+        })
+        .unwrap_or(11)
+}
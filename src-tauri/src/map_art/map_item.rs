@@ -0,0 +1,270 @@
+use crate::map_art::generator::{prepare_image, quantize, MapArtOptions};
+use crate::map_art::map_colors::{block_map_color, color_byte, nearest_base, vanilla_palette};
+use crate::map_art::palette::MapTone;
+use crate::render::block_colors::{strip_namespace, BlockColors};
+use crate::utils::block_state_pos_list::BlockData;
+use crate::utils::extend_write::to_bytes_gzip;
+use crate::utils::minecraft_data::map_art_data::MapArtsData;
+use crate::utils::schematic_data::SchematicData;
+use anyhow::Result;
+use fastnbt::{ByteArray, Value};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub const MAP_SIZE: usize = 128;
+// 1.20.5 起物品改用组件，give 命令格式不同
+const COMPONENT_DATA_VERSION: i32 = 3837;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapItemOptions {
+    pub start_id: i32,
+    pub dimension: String,
+    pub locked: bool,
+    // 左上角地图的中心坐标，其余地图按 128 格递增
+    pub x_center: i32,
+    pub z_center: i32,
+    pub data_version: i32,
+    // 存档 data/idcounts.dat 里当前的 map 值，给出时才写 idcounts，且 start_id 必须大于它
+    pub current_map_id: Option<i32>,
+    // 蓝图是阶梯地图画时第一行是北侧参照，不属于画面
+    pub staircase: bool,
+}
+
+impl Default for MapItemOptions {
+    fn default() -> Self {
+        Self {
+            start_id: 0,
+            dimension: "minecraft:overworld".to_string(),
+            locked: true,
+            x_center: 0,
+            z_center: 0,
+            data_version: 3465,
+            current_map_id: None,
+            staircase: false,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MapData {
+    scale: i8,
+    dimension: String,
+    tracking_position: bool,
+    unlimited_tracking: bool,
+    locked: bool,
+    x_center: i32,
+    z_center: i32,
+    banners: Vec<Value>,
+    frames: Vec<Value>,
+    colors: ByteArray,
+}
+
+#[derive(Serialize)]
+struct MapFile {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    data: MapData,
+}
+
+#[derive(Serialize)]
+struct IdCounts {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    data: HashMap<String, i32>,
+}
+
+/// 整幅画的地图颜色字节，行优先，0 为透明
+pub struct MapColors {
+    pub width: usize,
+    pub height: usize,
+    pub colors: Vec<u8>,
+}
+
+pub struct MapItems {
+    pub files: Vec<(String, Vec<u8>)>,
+    pub commands: Vec<String>,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+/// 方块的地图基础色：优先按名字查原版颜色，其次用地图画数据的平均色，最后取材质顶面的近似色
+fn block_base(block: &BlockData, map_arts: &MapArtsData, block_colors: &BlockColors) -> Option<u8> {
+    if BlockColors::is_invisible(&block.id.name) {
+        return None;
+    }
+    let name = strip_namespace(&block.id.name);
+    block_map_color(name)
+        .or_else(|| {
+            let known = map_arts.categories.values().find_map(|c| c.get(name))?;
+            Some(nearest_base([*known.normal.first()?, *known.normal.get(1)?, *known.normal.get(2)?]))
+        })
+        .or_else(|| {
+            let top = block_colors.face_colors(block)?.top;
+            Some(nearest_base([top[0], top[1], top[2]]))
+        })
+}
+
+impl MapColors {
+    /// 用原版全部地图色量化图片；阶梯模式可用三档明暗且不限高度
+    pub fn from_image(bytes: &[u8], options: &MapArtOptions) -> Result<Self> {
+        let image = prepare_image(bytes, options)?;
+        let palette = vanilla_palette();
+        let bases: Vec<u8> = palette
+            .colors
+            .iter()
+            .map(|c| c.block.parse().unwrap_or(0))
+            .collect();
+        let options = MapArtOptions { max_height: i32::MAX, ..options.clone() };
+        let quantized = quantize(&image, &palette, &options);
+        let colors = quantized
+            .pixels
+            .iter()
+            .map(|p| p.map_or(0, |(entry, tone)| color_byte(bases[entry], tone)))
+            .collect();
+        Ok(Self {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            colors,
+        })
+    }
+
+    /// 从地图画蓝图俯视取色，明暗由每格顶部方块与北侧方块的高度差决定
+    pub fn from_schematic(
+        data: &SchematicData,
+        map_arts: &MapArtsData,
+        block_colors: &BlockColors,
+        staircase: bool,
+    ) -> Self {
+        let width = data.size.width.max(0) as usize;
+        let length = data.size.length.max(0) as usize;
+        let mut tops: Vec<Option<(i32, u8)>> = vec![None; width * length];
+        let mut cache: HashMap<Arc<str>, Option<u8>> = HashMap::new();
+
+        for element in &data.blocks.elements {
+            let (x, z) = (element.pos.x, element.pos.z);
+            if x < 0 || z < 0 || x as usize >= width || z as usize >= length {
+                continue;
+            }
+            let index = z as usize * width + x as usize;
+            if tops[index].is_some_and(|(y, _)| y >= element.pos.y) {
+                continue;
+            }
+            let base = *cache
+                .entry(element.block.id.name.clone())
+                .or_insert_with(|| block_base(&element.block, map_arts, block_colors));
+            if let Some(base) = base {
+                tops[index] = Some((element.pos.y, base));
+            }
+        }
+
+        // 阶梯地图画多出的第一行只是北侧参照，不属于画面
+        let skip = (staircase && length > 1) as usize;
+        let height = length - skip;
+        let mut colors = vec![0u8; width * height];
+        for z in skip..length {
+            for x in 0..width {
+                let Some((y, base)) = tops[z * width + x] else {
+                    continue;
+                };
+                let north = z.checked_sub(1).and_then(|n| tops[n * width + x]);
+                let tone = match north.map(|(ny, _)| y.cmp(&ny)) {
+                    Some(Ordering::Greater) => MapTone::High,
+                    Some(Ordering::Less) => MapTone::Low,
+                    _ => MapTone::Normal,
+                };
+                colors[(z - skip) * width + x] = color_byte(base, tone);
+            }
+        }
+        Self { width, height, colors }
+    }
+
+    /// 按 128×128 切分后的列数与行数
+    pub fn grid(&self) -> (usize, usize) {
+        (self.width.div_ceil(MAP_SIZE).max(1), self.height.div_ceil(MAP_SIZE).max(1))
+    }
+
+    /// 取出一张地图的颜色，画面不足的部分补透明
+    pub fn tile(&self, column: usize, row: usize) -> Vec<u8> {
+        let mut tile = vec![0u8; MAP_SIZE * MAP_SIZE];
+        for y in 0..MAP_SIZE {
+            let sy = row * MAP_SIZE + y;
+            if sy >= self.height {
+                break;
+            }
+            let sx = column * MAP_SIZE;
+            if sx >= self.width {
+                break;
+            }
+            let len = (self.width - sx).min(MAP_SIZE);
+            let start = sy * self.width + sx;
+            tile[y * MAP_SIZE..y * MAP_SIZE + len].copy_from_slice(&self.colors[start..start + len]);
+        }
+        tile
+    }
+}
+
+pub fn give_command(id: i32, data_version: i32) -> String {
+    if data_version >= COMPONENT_DATA_VERSION {
+        format!("give @p minecraft:filled_map[minecraft:map_id={}]", id)
+    } else {
+        format!("give @p minecraft:filled_map{{map:{}}}", id)
+    }
+}
+
+/// 生成 data/map_<id>.dat 与 give 命令，地图 id 按行优先递增
+/// idcounts 只能增大，否则游戏之后新建的地图会覆盖已有地图，所以只有知道存档当前值时才写
+pub fn map_item_files(colors: &MapColors, options: &MapItemOptions) -> Result<MapItems> {
+    if let Some(current) = options.current_map_id {
+        if options.start_id <= current {
+            anyhow::bail!(
+                "start_id {} must be greater than the world's current map id {}",
+                options.start_id,
+                current
+            );
+        }
+    }
+    let (columns, rows) = colors.grid();
+    let mut files = Vec::new();
+    let mut commands = Vec::new();
+    let mut function = String::new();
+    let mut id = options.start_id;
+
+    for row in 0..rows {
+        for column in 0..columns {
+            let map = MapFile {
+                data_version: options.data_version,
+                data: MapData {
+                    scale: 0,
+                    dimension: options.dimension.clone(),
+                    tracking_position: false,
+                    unlimited_tracking: false,
+                    locked: options.locked,
+                    x_center: options.x_center + (column * MAP_SIZE) as i32,
+                    z_center: options.z_center + (row * MAP_SIZE) as i32,
+                    banners: Vec::new(),
+                    frames: Vec::new(),
+                    colors: ByteArray::new(colors.tile(column, row).into_iter().map(|c| c as i8).collect()),
+                },
+            };
+            files.push((format!("data/map_{}.dat", id), to_bytes_gzip(&map)?));
+            let command = give_command(id, options.data_version);
+            function.push_str(&format!("# row {} column {}\n{}\n", row + 1, column + 1, command));
+            commands.push(command);
+            id += 1;
+        }
+    }
+
+    if let Some(current) = options.current_map_id {
+        let counts = IdCounts {
+            data_version: options.data_version,
+            data: HashMap::from([("map".to_string(), (id - 1).max(current))]),
+        };
+        files.push(("data/idcounts.dat".to_string(), to_bytes_gzip(&counts)?));
+    }
+    files.push(("give_maps.mcfunction".to_string(), function.into_bytes()));
+    Ok(MapItems { files, commands, columns, rows })
+}
@@ -1 +1,6 @@
-
+pub mod builder;
+pub mod generator;
+pub mod map_colors;
+pub mod map_item;
+pub mod palette;
+pub mod texture_colors;
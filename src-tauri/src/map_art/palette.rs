@@ -0,0 +1,125 @@
+use crate::utils::minecraft_data::map_art_data::MapArtsData;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 地图上同一种方块的三档明暗，取决于北侧方块的相对高度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MapTone {
+    Low,
+    Normal,
+    High,
+}
+
+impl MapTone {
+    pub const ALL: [MapTone; 3] = [MapTone::Low, MapTone::Normal, MapTone::High];
+
+    /// 相对北侧方块的高度变化
+    pub fn delta(self) -> i32 {
+        match self {
+            MapTone::Low => -1,
+            MapTone::Normal => 0,
+            MapTone::High => 1,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PaletteColor {
+    pub block: String,
+    pub tones: [[u8; 3]; 3],
+}
+
+impl PaletteColor {
+    pub fn tone(&self, tone: MapTone) -> [u8; 3] {
+        self.tones[tone as usize]
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MapPalette {
+    pub colors: Vec<PaletteColor>,
+}
+
+/// 允许使用的明暗档位，按位存储
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ToneMask(u8);
+
+impl ToneMask {
+    pub const FLAT: ToneMask = ToneMask(1 << MapTone::Normal as u8);
+    pub const ALL: ToneMask = ToneMask(0b111);
+
+    pub fn without(self, tone: MapTone) -> Self {
+        ToneMask(self.0 & !tone.bit())
+    }
+
+    pub fn contains(self, tone: MapTone) -> bool {
+        self.0 & tone.bit() != 0
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+}
+
+fn rgb(values: &[u8]) -> Option<[u8; 3]> {
+    Some([*values.first()?, *values.get(1)?, *values.get(2)?])
+}
+
+impl MapPalette {
+    /// 从地图画数据中取出选中的方块，地图颜色相同的只保留第一个
+    pub fn from_selection(map_arts: &MapArtsData, blocks: &[String]) -> Result<Self> {
+        let mut colors = Vec::new();
+        let mut seen = HashSet::new();
+        for block in blocks {
+            let name = block.strip_prefix("minecraft:").unwrap_or(block);
+            let Some(data) = map_arts.categories.values().find_map(|c| c.get(name)) else {
+                continue;
+            };
+            let (Some(low), Some(normal), Some(high)) =
+                (rgb(&data.low), rgb(&data.normal), rgb(&data.high))
+            else {
+                continue;
+            };
+            if !seen.insert(normal) {
+                continue;
+            }
+            colors.push(PaletteColor {
+                block: name.to_string(),
+                tones: [low, normal, high],
+            });
+        }
+        if colors.is_empty() {
+            anyhow::bail!("no usable map art blocks selected");
+        }
+        Ok(Self { colors })
+    }
+
+    pub fn nearest(&self, color: [f32; 3], mask: ToneMask) -> (usize, MapTone) {
+        let mut best = (0, MapTone::Normal);
+        let mut best_distance = f32::MAX;
+        for tone in MapTone::ALL {
+            if !mask.contains(tone) {
+                continue;
+            }
+            for (index, entry) in self.colors.iter().enumerate() {
+                let c = entry.tone(tone);
+                let (dr, dg, db) = (
+                    color[0] - c[0] as f32,
+                    color[1] - c[1] as f32,
+                    color[2] - c[2] as f32,
+                );
+                let distance = dr * dr + dg * dg + db * db;
+                if distance < best_distance {
+                    best_distance = distance;
+                    best = (index, tone);
+                }
+            }
+        }
+        best
+    }
+}
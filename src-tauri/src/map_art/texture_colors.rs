@@ -0,0 +1,132 @@
+use crate::render::block_colors::{apply_tint, strip_namespace, BlockColors};
+use crate::utils::block_state_pos_list::{BlockData, BlockId};
+use crate::utils::minecraft_data::map_art_data::{BlockColorData, MapArtsData};
+use anyhow::{Context, Result};
+use image::RgbaImage;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use zip::ZipArchive;
+
+// 计算方式变化时递增，旧缓存自动失效
+const CACHE_VERSION: u32 = 1;
+
+/// 原版 jar 或资源包 zip，按 assets/minecraft/textures 读取材质
+pub struct TextureSource {
+    archive: ZipArchive<File>,
+}
+
+impl TextureSource {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+        let archive = ZipArchive::new(file).context("Resource pack must be a zip or jar file")?;
+        Ok(Self { archive })
+    }
+
+    /// texture 形如 block/oak_log，资源包中没有时返回 None
+    pub fn texture(&mut self, texture: &str) -> Option<RgbaImage> {
+        let path = format!("assets/minecraft/textures/{}.png", strip_namespace(texture));
+        let mut entry = self.archive.by_name(&path).ok()?;
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes).ok()?;
+        let image = image::load_from_memory(&bytes).ok()?.to_rgba8();
+        // 动态材质只取第一帧
+        let size = image.width();
+        if image.height() > size {
+            return Some(image::imageops::crop_imm(&image, 0, 0, size, size).to_image());
+        }
+        Some(image)
+    }
+}
+
+fn rgb_hex(rgb: &[u8]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+/// 与 map_art_scripts/map_art.py 相同：四个采样点、全图平均色及三档明暗
+pub fn color_data(image: &RgbaImage, zh_cn: &str) -> BlockColorData {
+    let (width, height) = (image.width().max(1), image.height().max(1));
+    let sample = |x: u32, y: u32| {
+        let p = image.get_pixel(x.min(width - 1), y.min(height - 1)).0;
+        vec![p[0], p[1], p[2]]
+    };
+    let (mut r, mut g, mut b) = (0u64, 0u64, 0u64);
+    for p in image.pixels() {
+        r += p.0[0] as u64;
+        g += p.0[1] as u64;
+        b += p.0[2] as u64;
+    }
+    let count = (image.width() as u64 * image.height() as u64).max(1);
+    let average = [r / count, g / count, b / count];
+    let shade = |factor: u64| average.iter().map(|c| (c * factor / 255) as u8).collect::<Vec<u8>>();
+    let (low, normal, high) = (shade(180), shade(220), shade(255));
+
+    BlockColorData {
+        left_top: sample(width / 4, height / 4),
+        right_bottom: sample(width / 2, height / 2),
+        right_top: sample(3 * width / 4, height / 4),
+        left_bottom: sample(width / 4, 3 * height / 4),
+        average_hex: rgb_hex(&high),
+        average: high.clone(),
+        low_hex: rgb_hex(&low),
+        low,
+        normal_hex: rgb_hex(&normal),
+        normal,
+        high_hex: rgb_hex(&high),
+        high,
+        zh_cn: zh_cn.to_string(),
+    }
+}
+
+/// 缓存键：文件名、大小与修改时间，资源包更新后自动重新计算
+pub fn pack_cache_key(path: &Path) -> Result<String> {
+    let meta = std::fs::metadata(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let stem: String = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    Ok(format!("v{}_{}_{}_{}", CACHE_VERSION, stem, meta.len(), modified))
+}
+
+/// 以内置数据的方块列表为准，用资源包材质重新取色；资源包缺少的材质保留原数据
+pub fn map_arts_from_pack(base: &MapArtsData, pack: &Path, block_colors: &BlockColors) -> Result<MapArtsData> {
+    let mut source = TextureSource::open(pack)?;
+    let mut categories = HashMap::new();
+    for (category, blocks) in &base.categories {
+        let mut computed = HashMap::new();
+        for (name, original) in blocks {
+            let block = BlockData {
+                id: BlockId { name: Arc::from(format!("minecraft:{}", name)) },
+                properties: BTreeMap::new(),
+            };
+            let texture = block_colors
+                .texture_name(&block)
+                .unwrap_or_else(|| format!("block/{}", name));
+            let data = match source.texture(&texture) {
+                Some(mut image) => {
+                    let tint = BlockColors::texture_tint(&texture);
+                    for pixel in image.pixels_mut() {
+                        pixel.0 = apply_tint(pixel.0, tint);
+                    }
+                    color_data(&image, &original.zh_cn)
+                }
+                None => original.clone(),
+            };
+            computed.insert(name.clone(), data);
+        }
+        categories.insert(category.clone(), computed);
+    }
+    Ok(MapArtsData { categories })
+}
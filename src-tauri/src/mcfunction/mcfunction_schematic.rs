@@ -0,0 +1,553 @@
+use crate::mcfunction::to_mcfunction::FILL_LIMIT;
+use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos, BlockStatePosList};
+use crate::utils::entities::EntitiesList;
+use crate::utils::schematic_data::{SchematicData, SchematicError, Size};
+use crate::utils::tile_entities::{TileEntities, TileEntitiesList};
+use fastnbt::Value;
+use fastnbt::Value::Compound;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy)]
+enum Coord {
+    Absolute(f64),
+    Relative(f64),
+    Local(f64),
+}
+
+#[derive(Debug, Clone)]
+struct BlockInput {
+    block: Arc<BlockData>,
+    nbt: Option<Value>,
+}
+
+#[derive(Debug, Clone)]
+struct BlockFilter {
+    id: Arc<str>,
+    properties: BTreeMap<Arc<str>, Arc<str>>,
+}
+
+impl BlockFilter {
+    fn matches(&self, block: &BlockData) -> bool {
+        block.id.name == self.id
+            && self
+                .properties
+                .iter()
+                .all(|(k, v)| block.properties.get(k) == Some(v))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Volume {
+    blocks: HashMap<(i32, i32, i32), Arc<BlockData>>,
+    nbt: HashMap<(i32, i32, i32), Value>,
+}
+
+impl Volume {
+    fn get(&self, pos: (i32, i32, i32)) -> Option<&Arc<BlockData>> {
+        self.blocks.get(&pos)
+    }
+
+    fn is_air(&self, pos: (i32, i32, i32)) -> bool {
+        self.get(pos).map(|b| is_air(&b.id.name)).unwrap_or(true)
+    }
+
+    fn set(&mut self, pos: (i32, i32, i32), input: &BlockInput) {
+        self.nbt.remove(&pos);
+        if is_air(&input.block.id.name) {
+            self.blocks.remove(&pos);
+            return;
+        }
+        self.blocks.insert(pos, input.block.clone());
+        if let Some(nbt) = &input.nbt {
+            self.nbt.insert(pos, nbt.clone());
+        }
+    }
+
+    fn clear(&mut self, pos: (i32, i32, i32)) {
+        self.blocks.remove(&pos);
+        self.nbt.remove(&pos);
+    }
+}
+
+#[derive(Debug)]
+pub struct McFunctionSchematic {
+    pub commands: Vec<String>,
+}
+
+impl McFunctionSchematic {
+    pub fn new(file_path: &str) -> Result<Self, SchematicError> {
+        let text = fs::read_to_string(file_path)?;
+        Ok(Self::new_from_str(&text))
+    }
+
+    pub fn new_from_bytes(data: Vec<u8>) -> Result<Self, SchematicError> {
+        let text = String::from_utf8(data)?;
+        Ok(Self::new_from_str(&text))
+    }
+
+    pub fn new_from_str(text: &str) -> Self {
+        let commands = text
+            .lines()
+            .map(|line| line.trim().to_string())
+            .collect();
+        Self { commands }
+    }
+
+    pub fn get_blocks_pos(&self) -> Result<SchematicData, SchematicError> {
+        let mut volume = Volume::default();
+        for (index, raw) in self.commands.iter().enumerate() {
+            let line = index + 1;
+            let Some(command) = strip_command(raw) else {
+                continue;
+            };
+            let (origin, command) = parse_execute(command)
+                .map_err(|message| SchematicError::Command { line, message })?;
+            let args = split_args(command);
+            let result = match args.first().map(|s| s.as_str()) {
+                Some("setblock") => run_setblock(&mut volume, &args[1..], origin),
+                Some("fill") => run_fill(&mut volume, &args[1..], origin),
+                Some("clone") => run_clone(&mut volume, &args[1..], origin),
+                _ => continue,
+            };
+            result.map_err(|message| SchematicError::Command { line, message })?;
+        }
+        volume_to_schematic(volume)
+    }
+}
+
+fn strip_command(raw: &str) -> Option<&str> {
+    let line = raw.trim().trim_start_matches('/');
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    Some(line)
+}
+
+fn next_token(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    s.split_once(char::is_whitespace).unwrap_or((s, ""))
+}
+
+// execute 只支持 positioned 和 align，返回执行位置与 run 后面的命令
+fn parse_execute(line: &str) -> Result<([f64; 3], &str), String> {
+    let mut origin = [0.0; 3];
+    let Some(mut rest) = line.strip_prefix("execute ") else {
+        return Ok((origin, line));
+    };
+    loop {
+        let (sub, tail) = next_token(rest);
+        match sub {
+            "run" => return Ok((origin, tail.trim().trim_start_matches('/'))),
+            "positioned" => {
+                let (x, tail) = next_token(tail);
+                let (y, tail) = next_token(tail);
+                let (z, tail) = next_token(tail);
+                if x == "as" || x == "over" {
+                    return Err(format!("execute: unsupported subcommand 'positioned {}'", x));
+                }
+                for (i, token) in [x, y, z].into_iter().enumerate() {
+                    origin[i] = match parse_coord(token)? {
+                        Coord::Absolute(v) => v,
+                        Coord::Relative(v) => origin[i] + v,
+                        Coord::Local(_) => {
+                            return Err("execute positioned: local coordinates are not supported".to_string());
+                        }
+                    };
+                }
+                rest = tail;
+            }
+            "align" => {
+                let (axes, tail) = next_token(tail);
+                if axes.is_empty() {
+                    return Err("execute align: missing axes".to_string());
+                }
+                for axis in axes.chars() {
+                    let i = match axis {
+                        'x' => 0,
+                        'y' => 1,
+                        'z' => 2,
+                        _ => return Err(format!("execute align: invalid axes '{}'", axes)),
+                    };
+                    origin[i] = origin[i].floor();
+                }
+                rest = tail;
+            }
+            "" => return Err("execute: missing run".to_string()),
+            other => return Err(format!("execute: unsupported subcommand '{}'", other)),
+        }
+    }
+}
+
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for c in line.chars() {
+        if let Some(q) = quote {
+            current.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => {
+                quote = Some(c);
+                current.push(c);
+            }
+            '[' | '{' => {
+                depth += 1;
+                current.push(c);
+            }
+            ']' | '}' => {
+                depth -= 1;
+                current.push(c);
+            }
+            c if c.is_whitespace() && depth <= 0 => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    args
+}
+
+fn parse_coord(token: &str) -> Result<Coord, String> {
+    let parse = |s: &str| -> Result<f64, String> {
+        if s.is_empty() {
+            Ok(0.0)
+        } else {
+            s.parse::<f64>()
+                .map_err(|_| format!("invalid coordinate '{}'", token))
+        }
+    };
+    if let Some(rest) = token.strip_prefix('~') {
+        Ok(Coord::Relative(parse(rest)?))
+    } else if let Some(rest) = token.strip_prefix('^') {
+        Ok(Coord::Local(parse(rest)?))
+    } else {
+        Ok(Coord::Absolute(parse(token)?))
+    }
+}
+
+// 世界边界
+const WORLD_LIMIT: f64 = 30_000_000.0;
+
+// 没有执行者，相对坐标以执行位置为基准，局部坐标按默认朝向(南)展开
+fn parse_pos(args: &[String], origin: [f64; 3]) -> Result<(i32, i32, i32), String> {
+    if args.len() < 3 {
+        return Err("missing coordinates".to_string());
+    }
+    let coords = [
+        parse_coord(&args[0])?,
+        parse_coord(&args[1])?,
+        parse_coord(&args[2])?,
+    ];
+    let is_local = coords.iter().any(|c| matches!(c, Coord::Local(_)));
+    if is_local && !coords.iter().all(|c| matches!(c, Coord::Local(_))) {
+        return Err("cannot mix local and world coordinates".to_string());
+    }
+    let mut pos = [0; 3];
+    for (i, c) in coords.into_iter().enumerate() {
+        let v = match c {
+            Coord::Absolute(v) => v,
+            Coord::Relative(v) | Coord::Local(v) => origin[i] + v,
+        };
+        if !(-WORLD_LIMIT..=WORLD_LIMIT).contains(&v) {
+            return Err(format!("coordinate out of world: {}", v));
+        }
+        pos[i] = v.floor() as i32;
+    }
+    Ok((pos[0], pos[1], pos[2]))
+}
+
+fn parse_properties(raw: &str) -> Result<BTreeMap<Arc<str>, Arc<str>>, String> {
+    let mut properties = BTreeMap::new();
+    for pair in raw.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (k, v) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("invalid block state '{}'", pair))?;
+        properties.insert(Arc::from(k.trim()), Arc::from(v.trim()));
+    }
+    Ok(properties)
+}
+
+fn parse_block(token: &str) -> Result<BlockInput, String> {
+    let (head, nbt) = match token.find('{') {
+        Some(index) => (&token[..index], Some(&token[index..])),
+        None => (token, None),
+    };
+    let (name, properties) = match head.find('[') {
+        Some(index) => {
+            let end = head
+                .rfind(']')
+                .ok_or_else(|| format!("unclosed block state in '{}'", token))?;
+            (&head[..index], parse_properties(&head[index + 1..end])?)
+        }
+        None => (head, BTreeMap::new()),
+    };
+    if name.starts_with('#') {
+        return Err(format!("block tags are not supported: '{}'", name));
+    }
+    let name = if name.contains(':') {
+        name.to_string()
+    } else {
+        format!("minecraft:{}", name)
+    };
+    let nbt = match nbt {
+        Some(snbt) => {
+            let value: Value = fastsnbt::from_str(snbt)
+                .map_err(|e| format!("invalid block nbt '{}': {}", snbt, e))?;
+            let Compound(mut map) = value else {
+                return Err("block nbt must be a compound".to_string());
+            };
+            map.entry("id".to_string())
+                .or_insert_with(|| Value::String(name.clone()));
+            Some(Compound(map))
+        }
+        None => None,
+    };
+    Ok(BlockInput {
+        block: Arc::new(BlockData {
+            id: BlockId {
+                name: Arc::from(name),
+            },
+            properties,
+        }),
+        nbt,
+    })
+}
+
+fn parse_filter(token: &str) -> Result<BlockFilter, String> {
+    let input = parse_block(token)?;
+    Ok(BlockFilter {
+        id: input.block.id.name.clone(),
+        properties: input.block.properties.clone(),
+    })
+}
+
+fn ordered(a: (i32, i32, i32), b: (i32, i32, i32)) -> ((i32, i32, i32), (i32, i32, i32)) {
+    (
+        (a.0.min(b.0), a.1.min(b.1), a.2.min(b.2)),
+        (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2)),
+    )
+}
+
+// 与原版一致，单条命令最多影响 FILL_LIMIT 个方块
+fn check_volume(command: &str, min: (i32, i32, i32), max: (i32, i32, i32)) -> Result<(), String> {
+    let count = (max.0 - min.0 + 1) as i64 * (max.1 - min.1 + 1) as i64 * (max.2 - min.2 + 1) as i64;
+    if count > FILL_LIMIT {
+        return Err(format!(
+            "{}: too many blocks in the specified area ({} > {})",
+            command, count, FILL_LIMIT
+        ));
+    }
+    Ok(())
+}
+
+fn run_setblock(volume: &mut Volume, args: &[String], origin: [f64; 3]) -> Result<(), String> {
+    let pos = parse_pos(args, origin)?;
+    let block = args.get(3).ok_or("setblock: missing block")?;
+    let input = parse_block(block)?;
+    match args.get(4).map(|s| s.as_str()) {
+        None | Some("replace") | Some("destroy") => volume.set(pos, &input),
+        Some("keep") => {
+            if volume.is_air(pos) {
+                volume.set(pos, &input);
+            }
+        }
+        Some(mode) => return Err(format!("setblock: unknown mode '{}'", mode)),
+    }
+    Ok(())
+}
+
+fn run_fill(volume: &mut Volume, args: &[String], origin: [f64; 3]) -> Result<(), String> {
+    if args.len() < 7 {
+        return Err("fill: missing arguments".to_string());
+    }
+    let (min, max) = ordered(parse_pos(&args[0..3], origin)?, parse_pos(&args[3..6], origin)?);
+    check_volume("fill", min, max)?;
+    let input = parse_block(&args[6])?;
+    let mode = args.get(7).map(|s| s.as_str()).unwrap_or("replace");
+    let filter = match (mode, args.get(8)) {
+        ("replace", Some(token)) => Some(parse_filter(token)?),
+        _ => None,
+    };
+    let air = BlockInput {
+        block: Arc::new(BlockData {
+            id: BlockId {
+                name: Arc::from("minecraft:air"),
+            },
+            properties: BTreeMap::new(),
+        }),
+        nbt: None,
+    };
+
+    for y in min.1..=max.1 {
+        for z in min.2..=max.2 {
+            for x in min.0..=max.0 {
+                let pos = (x, y, z);
+                let shell = x == min.0
+                    || x == max.0
+                    || y == min.1
+                    || y == max.1
+                    || z == min.2
+                    || z == max.2;
+                match mode {
+                    "replace" | "destroy" => {
+                        if let Some(filter) = &filter {
+                            let current = volume.get(pos).map(|b| filter.matches(b));
+                            let air_match = filter.id.as_ref() == "minecraft:air";
+                            if !current.unwrap_or(air_match) {
+                                continue;
+                            }
+                        }
+                        volume.set(pos, &input);
+                    }
+                    "keep" => {
+                        if volume.is_air(pos) {
+                            volume.set(pos, &input);
+                        }
+                    }
+                    "hollow" => {
+                        if shell {
+                            volume.set(pos, &input);
+                        } else {
+                            volume.set(pos, &air);
+                        }
+                    }
+                    "outline" => {
+                        if shell {
+                            volume.set(pos, &input);
+                        }
+                    }
+                    _ => return Err(format!("fill: unknown mode '{}'", mode)),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_clone(volume: &mut Volume, args: &[String], origin: [f64; 3]) -> Result<(), String> {
+    if args.len() < 9 {
+        return Err("clone: missing arguments".to_string());
+    }
+    let (min, max) = ordered(parse_pos(&args[0..3], origin)?, parse_pos(&args[3..6], origin)?);
+    check_volume("clone", min, max)?;
+    let dest = parse_pos(&args[6..9], origin)?;
+    let mut rest = args[9..].iter().map(|s| s.as_str());
+    let mask = rest.next().unwrap_or("replace");
+    let filter = if mask == "filtered" {
+        let token = rest.next().ok_or("clone: missing filter")?;
+        Some(parse_filter(token)?)
+    } else {
+        None
+    };
+    let flag = rest.next().unwrap_or("normal");
+    if !matches!(mask, "replace" | "masked" | "filtered") {
+        return Err(format!("clone: unknown mask mode '{}'", mask));
+    }
+    if !matches!(flag, "normal" | "force" | "move") {
+        return Err(format!("clone: unknown clone mode '{}'", flag));
+    }
+
+    let mut copied = Vec::new();
+    for y in min.1..=max.1 {
+        for z in min.2..=max.2 {
+            for x in min.0..=max.0 {
+                let pos = (x, y, z);
+                let block = volume.get(pos).cloned();
+                let keep = match (mask, &block) {
+                    ("replace", _) => true,
+                    ("masked", Some(b)) => !is_air(&b.id.name),
+                    ("filtered", Some(b)) => filter.as_ref().map(|f| f.matches(b)).unwrap_or(false),
+                    _ => false,
+                };
+                if !keep {
+                    continue;
+                }
+                let target = (dest.0 + x - min.0, dest.1 + y - min.1, dest.2 + z - min.2);
+                copied.push((pos, target, block, volume.nbt.get(&pos).cloned()));
+            }
+        }
+    }
+
+    if flag == "move" {
+        for (source, _, _, _) in &copied {
+            volume.clear(*source);
+        }
+    }
+    for (_, target, block, nbt) in copied {
+        match block {
+            Some(block) => volume.set(target, &BlockInput { block, nbt }),
+            None => volume.clear(target),
+        }
+    }
+    Ok(())
+}
+
+fn is_air(name: &str) -> bool {
+    matches!(
+        name,
+        "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air"
+    )
+}
+
+fn volume_to_schematic(volume: Volume) -> Result<SchematicData, SchematicError> {
+    if volume.blocks.is_empty() {
+        return Err(SchematicError::InvalidFormat("No blocks placed by commands"));
+    }
+    let mut min = (i32::MAX, i32::MAX, i32::MAX);
+    let mut max = (i32::MIN, i32::MIN, i32::MIN);
+    for &(x, y, z) in volume.blocks.keys() {
+        min = (min.0.min(x), min.1.min(y), min.2.min(z));
+        max = (max.0.max(x), max.1.max(y), max.2.max(z));
+    }
+
+    let mut positions: Vec<_> = volume.blocks.keys().copied().collect();
+    positions.sort_by_key(|&(x, y, z)| (y, z, x));
+
+    let mut block_list = BlockStatePosList::default();
+    let mut tile_entities = TileEntitiesList::default();
+    tile_entities.original_type = 1;
+    for pos in positions {
+        let local = BlockPos {
+            x: pos.0 - min.0,
+            y: pos.1 - min.1,
+            z: pos.2 - min.2,
+        };
+        block_list.add(local, volume.blocks[&pos].clone());
+        if let Some(nbt) = volume.nbt.get(&pos) {
+            tile_entities.elements.push(TileEntities {
+                pos: local,
+                nbt: nbt.clone(),
+            });
+        }
+    }
+
+    Ok(SchematicData::new(
+        block_list,
+        tile_entities,
+        EntitiesList::default(),
+        Size {
+            width: max.0 - min.0 + 1,
+            height: max.1 - min.1 + 1,
+            length: max.2 - min.2 + 1,
+        },
+    ))
+}
@@ -0,0 +1,2 @@
+pub mod mcfunction_schematic;
+pub mod to_mcfunction;
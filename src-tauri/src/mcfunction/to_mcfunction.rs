@@ -0,0 +1,451 @@
+use crate::utils::block_state_pos_list::{BlockData, BlockPos};
+use crate::utils::schematic_data::{SchematicData, SchematicError};
+use crate::word_edit::to_we_schematic::ToWeSchematic;
+use fastnbt::Value;
+use fastnbt::Value::Compound;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const EMPTY: u32 = u32::MAX;
+pub const FILL_LIMIT: i64 = 32768;
+pub const COMMAND_LENGTH_LIMIT: usize = 32000;
+pub const CHAIN_LIMIT: usize = 60000;
+
+#[derive(Debug, Clone)]
+pub struct McFunctionOptions {
+    pub namespace: String,
+    pub include_air: bool,
+    pub pack_format: i32,
+    pub max_commands: usize,
+}
+
+impl Default for McFunctionOptions {
+    fn default() -> Self {
+        Self {
+            namespace: "mcs".to_string(),
+            include_air: false,
+            pack_format: 48,
+            max_commands: CHAIN_LIMIT,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FillBox {
+    phase: u8,
+    min: (i32, i32, i32),
+    max: (i32, i32, i32),
+    state: u32,
+}
+
+#[derive(Debug)]
+pub struct ToMcFunction {
+    width: i32,
+    height: i32,
+    length: i32,
+    grid: Vec<u32>,
+    pub unique_block_states: Vec<Arc<BlockData>>,
+    tile_entities: HashMap<(i32, i32, i32), Value>,
+    entities: Vec<(f64, f64, f64, String, Value)>,
+}
+
+impl ToMcFunction {
+    pub fn new(schematic: &SchematicData) -> Result<Self, SchematicError> {
+        let blocks = &schematic.blocks.elements;
+        if blocks.is_empty() {
+            return Err(SchematicError::InvalidFormat("Block list cannot be empty"));
+        }
+        let (min, max) = blocks.iter().fold(
+            (
+                BlockPos { x: i32::MAX, y: i32::MAX, z: i32::MAX },
+                BlockPos { x: i32::MIN, y: i32::MIN, z: i32::MIN },
+            ),
+            |(mut min, mut max), bp| {
+                min.x = min.x.min(bp.pos.x);
+                min.y = min.y.min(bp.pos.y);
+                min.z = min.z.min(bp.pos.z);
+                max.x = max.x.max(bp.pos.x);
+                max.y = max.y.max(bp.pos.y);
+                max.z = max.z.max(bp.pos.z);
+                (min, max)
+            },
+        );
+        let width = max.x - min.x + 1;
+        let height = max.y - min.y + 1;
+        let length = max.z - min.z + 1;
+
+        let mut unique_block_states = Vec::new();
+        let mut index_map: HashMap<Arc<BlockData>, u32> = HashMap::new();
+        let mut grid = vec![EMPTY; (width * height * length) as usize];
+        for block in blocks {
+            let index = *index_map.entry(block.block.clone()).or_insert_with(|| {
+                unique_block_states.push(block.block.clone());
+                (unique_block_states.len() - 1) as u32
+            });
+            let (x, y, z) = (block.pos.x - min.x, block.pos.y - min.y, block.pos.z - min.z);
+            grid[((y * length + z) * width + x) as usize] = index;
+        }
+
+        let tile_entities = schematic
+            .tile_entities_list
+            .elements
+            .iter()
+            .map(|te| {
+                (
+                    (te.pos.x - min.x, te.pos.y - min.y, te.pos.z - min.z),
+                    block_entity_data(&te.nbt),
+                )
+            })
+            .collect();
+
+        let entities = schematic
+            .entities_list
+            .elements
+            .iter()
+            .filter_map(|e| entity_data(&e.nbt))
+            .map(|(x, y, z, id, nbt)| {
+                (x - min.x as f64, y - min.y as f64, z - min.z as f64, id, nbt)
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            length,
+            grid,
+            unique_block_states,
+            tile_entities,
+            entities,
+        })
+    }
+
+    fn index(&self, x: i32, y: i32, z: i32) -> usize {
+        ((y * self.length + z) * self.width + x) as usize
+    }
+
+    fn greedy_boxes(&self, include_air: bool) -> Vec<FillBox> {
+        let mut visited = vec![false; self.grid.len()];
+        let mut boxes = Vec::new();
+        for y in 0..self.height {
+            for z in 0..self.length {
+                for x in 0..self.width {
+                    let idx = self.index(x, y, z);
+                    let state = self.grid[idx];
+                    if visited[idx] || state == EMPTY || self.tile_entities.contains_key(&(x, y, z)) {
+                        continue;
+                    }
+                    let block = &self.unique_block_states[state as usize];
+                    let phase = placement_phase(&block.id.name);
+                    if phase == 0 && !include_air {
+                        continue;
+                    }
+                    let same = |cx: i32, cy: i32, cz: i32, visited: &Vec<bool>| {
+                        let i = self.index(cx, cy, cz);
+                        self.grid[i] == state
+                            && !visited[i]
+                            && !self.tile_entities.contains_key(&(cx, cy, cz))
+                    };
+
+                    let mut x2 = x;
+                    while x2 + 1 < self.width
+                        && ((x2 + 2 - x) as i64) <= FILL_LIMIT
+                        && same(x2 + 1, y, z, &visited)
+                    {
+                        x2 += 1;
+                    }
+                    let dx = (x2 - x + 1) as i64;
+
+                    let mut z2 = z;
+                    'grow_z: while z2 + 1 < self.length && dx * ((z2 + 2 - z) as i64) <= FILL_LIMIT {
+                        for cx in x..=x2 {
+                            if !same(cx, y, z2 + 1, &visited) {
+                                break 'grow_z;
+                            }
+                        }
+                        z2 += 1;
+                    }
+                    let dz = (z2 - z + 1) as i64;
+
+                    // 受重力和依附方块只在同一层合并，保证自下而上放置
+                    let mut y2 = y;
+                    if phase < 2 {
+                        'grow_y: while y2 + 1 < self.height
+                            && dx * dz * ((y2 + 2 - y) as i64) <= FILL_LIMIT
+                        {
+                            for cz in z..=z2 {
+                                for cx in x..=x2 {
+                                    if !same(cx, y2 + 1, cz, &visited) {
+                                        break 'grow_y;
+                                    }
+                                }
+                            }
+                            y2 += 1;
+                        }
+                    }
+
+                    for cy in y..=y2 {
+                        for cz in z..=z2 {
+                            for cx in x..=x2 {
+                                let i = self.index(cx, cy, cz);
+                                visited[i] = true;
+                            }
+                        }
+                    }
+                    boxes.push(FillBox {
+                        phase,
+                        min: (x, y, z),
+                        max: (x2, y2, z2),
+                        state,
+                    });
+                }
+            }
+        }
+        boxes
+    }
+
+    pub fn commands(&self, include_air: bool) -> Vec<String> {
+        let mut ordered: Vec<(u8, i32, Vec<String>)> = self
+            .greedy_boxes(include_air)
+            .into_iter()
+            .map(|b| {
+                let block = ToWeSchematic::block_to_string(&self.unique_block_states[b.state as usize]);
+                let command = if b.min == b.max {
+                    format!("setblock ~{} ~{} ~{} {}", b.min.0, b.min.1, b.min.2, block)
+                } else {
+                    format!(
+                        "fill ~{} ~{} ~{} ~{} ~{} ~{} {}",
+                        b.min.0, b.min.1, b.min.2, b.max.0, b.max.1, b.max.2, block
+                    )
+                };
+                (b.phase, b.min.1, vec![command])
+            })
+            .collect();
+
+        for (&(x, y, z), nbt) in &self.tile_entities {
+            let state = self.grid[self.index(x, y, z)];
+            if state == EMPTY {
+                continue;
+            }
+            let block_data = &self.unique_block_states[state as usize];
+            let block = ToWeSchematic::block_to_string(block_data);
+            let phase = placement_phase(&block_data.id.name);
+            ordered.push((phase, y, block_entity_commands(x, y, z, &block, nbt)));
+        }
+        ordered.sort_by_key(|(phase, y, _)| (*phase, *y));
+
+        let mut commands: Vec<String> = ordered.into_iter().flat_map(|(_, _, c)| c).collect();
+        for (x, y, z, id, nbt) in &self.entities {
+            let snbt = fastsnbt::to_string(nbt).unwrap_or_default();
+            let command = format!("summon {} ~{:.3} ~{:.3} ~{:.3} {}", id, x, y, z, snbt);
+            if command.len() <= COMMAND_LENGTH_LIMIT {
+                commands.push(command);
+            } else {
+                commands.push(format!("summon {} ~{:.3} ~{:.3} ~{:.3}", id, x, y, z));
+            }
+        }
+        commands
+    }
+
+    pub fn datapack_files(&self, options: &McFunctionOptions) -> Vec<(String, Vec<u8>)> {
+        let ns = options.namespace.as_str();
+        let function_dir = if options.pack_format >= 45 { "function" } else { "functions" };
+        let commands = self.commands(options.include_air);
+        let chunk_size = options.max_commands.clamp(1, CHAIN_LIMIT);
+        let chunks: Vec<&[String]> = commands.chunks(chunk_size).collect();
+        let marker = format!("@e[type=minecraft:marker,tag={}_origin,limit=1]", ns);
+
+        let mut files = Vec::new();
+        let mcmeta = serde_json::json!({
+            "pack": {
+                "pack_format": options.pack_format,
+                "description": format!("MCSTools export ({}x{}x{})", self.width, self.height, self.length)
+            }
+        });
+        files.push((
+            "pack.mcmeta".to_string(),
+            serde_json::to_vec_pretty(&mcmeta).unwrap_or_default(),
+        ));
+
+        let build = format!(
+            "# 在需要放置的位置执行 /function {ns}:build\n\
+             kill @e[type=minecraft:marker,tag={ns}_origin]\n\
+             summon minecraft:marker ~ ~ ~ {{Tags:[\"{ns}_origin\"]}}\n\
+             function {ns}:step_0\n"
+        );
+        files.push((format!("data/{}/{}/build.mcfunction", ns, function_dir), build.into_bytes()));
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            let mut body = chunk.join("\n");
+            body.push('\n');
+            files.push((
+                format!("data/{}/{}/part_{}.mcfunction", ns, function_dir, i),
+                body.into_bytes(),
+            ));
+
+            let mut step = format!("execute at {} run function {}:part_{}\n", marker, ns, i);
+            if i + 1 < chunks.len() {
+                step.push_str(&format!("schedule function {}:step_{} 1t\n", ns, i + 1));
+            } else {
+                step.push_str(&format!("kill @e[type=minecraft:marker,tag={}_origin]\n", ns));
+            }
+            files.push((
+                format!("data/{}/{}/step_{}.mcfunction", ns, function_dir, i),
+                step.into_bytes(),
+            ));
+        }
+        files
+    }
+}
+
+// 0 空气(清场) 1 普通方块 2 受重力方块 3 依附方块 4 流体
+pub fn placement_phase(name: &str) -> u8 {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    if matches!(name, "air" | "cave_air" | "void_air") {
+        return 0;
+    }
+    if matches!(name, "water" | "lava" | "bubble_column") {
+        return 4;
+    }
+    if matches!(
+        name,
+        "sand" | "red_sand" | "gravel" | "anvil" | "chipped_anvil" | "damaged_anvil"
+            | "dragon_egg" | "scaffolding" | "pointed_dripstone" | "suspicious_sand"
+            | "suspicious_gravel"
+    ) || name.ends_with("_concrete_powder")
+    {
+        return 2;
+    }
+    const ATTACHED_SUFFIXES: [&str; 22] = [
+        "torch", "rail", "_button", "_pressure_plate", "_carpet", "_sign", "_banner",
+        "_sapling", "_door", "_bed", "_tulip", "_mushroom", "_coral", "_coral_fan",
+        "_candle", "candle", "lantern", "_head", "_skull", "_trapdoor", "_fungus", "_roots",
+    ];
+    if ATTACHED_SUFFIXES.iter().any(|s| name.ends_with(s))
+        || matches!(
+            name,
+            "redstone_wire" | "repeater" | "comparator" | "lever" | "ladder" | "vine"
+                | "tripwire" | "tripwire_hook" | "snow" | "bell" | "lily_pad" | "flower_pot"
+                | "short_grass" | "grass" | "tall_grass" | "fern" | "large_fern" | "dandelion"
+                | "poppy" | "blue_orchid" | "allium" | "azure_bluet" | "oxeye_daisy"
+                | "cornflower" | "lily_of_the_valley" | "wither_rose" | "sunflower" | "lilac"
+                | "rose_bush" | "peony" | "dead_bush" | "sugar_cane" | "cactus" | "kelp"
+                | "kelp_plant" | "seagrass" | "tall_seagrass" | "wheat" | "carrots"
+                | "potatoes" | "beetroots" | "nether_wart" | "cocoa" | "sea_pickle"
+                | "end_rod" | "chain" | "glow_lichen" | "sculk_vein" | "item_frame"
+                | "nether_portal" | "end_portal" | "fire" | "soul_fire" | "moss_carpet"
+        )
+        || name.starts_with("potted_")
+    {
+        return 3;
+    }
+    1
+}
+
+fn block_entity_data(nbt: &Value) -> Value {
+    let Compound(map) = nbt else {
+        return Compound(HashMap::new());
+    };
+    let source = match (map.get("Data"), map.get("Id")) {
+        (Some(Compound(data)), Some(_)) => data,
+        _ => map,
+    };
+    let cleaned = source
+        .iter()
+        .filter(|(k, _)| !matches!(k.as_str(), "x" | "y" | "z" | "id" | "Id" | "Pos" | "keepPacked"))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    Compound(cleaned)
+}
+
+fn entity_data(nbt: &Value) -> Option<(f64, f64, f64, String, Value)> {
+    let Compound(map) = nbt else {
+        return None;
+    };
+    // 原版结构(create)实体格式: {pos:[d,d,d], blockPos:[...], nbt:{...}}
+    let (pos, data) = match (map.get("pos"), map.get("nbt")) {
+        (Some(Value::List(pos)), Some(Compound(data))) => (pos.clone(), data.clone()),
+        _ => {
+            let data = match map.get("Data") {
+                Some(Compound(data)) => {
+                    let mut data = data.clone();
+                    if let Some(id) = map.get("Id") {
+                        data.insert("id".to_string(), id.clone());
+                    }
+                    data
+                }
+                _ => map.clone(),
+            };
+            let pos = match map.get("Pos") {
+                Some(Value::List(pos)) => pos.clone(),
+                _ => return None,
+            };
+            (pos, data)
+        }
+    };
+    let coords: Vec<f64> = pos
+        .iter()
+        .filter_map(|v| match v {
+            Value::Double(d) => Some(*d),
+            Value::Float(f) => Some(*f as f64),
+            Value::Int(i) => Some(*i as f64),
+            _ => None,
+        })
+        .collect();
+    if coords.len() != 3 {
+        return None;
+    }
+    let id = match data.get("id").or_else(|| data.get("Id")) {
+        Some(Value::String(id)) => id.clone(),
+        _ => return None,
+    };
+    let cleaned = data
+        .into_iter()
+        .filter(|(k, _)| !matches!(k.as_str(), "id" | "Id" | "Pos" | "UUID" | "UUIDMost" | "UUIDLeast"))
+        .collect();
+    Some((coords[0], coords[1], coords[2], id, Compound(cleaned)))
+}
+
+fn block_entity_commands(x: i32, y: i32, z: i32, block: &str, nbt: &Value) -> Vec<String> {
+    let snbt = fastsnbt::to_string(nbt).unwrap_or_default();
+    let full = format!("setblock ~{} ~{} ~{} {}{}", x, y, z, block, snbt);
+    if full.len() <= COMMAND_LENGTH_LIMIT {
+        return vec![full];
+    }
+
+    // 超出单条命令长度时拆成多条 data 命令写入
+    let target = format!("block ~{} ~{} ~{}", x, y, z);
+    let mut commands = vec![format!("setblock ~{} ~{} ~{} {}", x, y, z, block)];
+    let Compound(map) = nbt else {
+        return commands;
+    };
+    let mut pending: HashMap<String, Value> = HashMap::new();
+    for (key, value) in map {
+        let single = Compound(HashMap::from([(key.clone(), value.clone())]));
+        let single_snbt = fastsnbt::to_string(&single).unwrap_or_default();
+        if single_snbt.len() + target.len() + 12 > COMMAND_LENGTH_LIMIT {
+            if let Value::List(items) = value {
+                commands.push(format!("data modify {} {} set value []", target, key));
+                for item in items {
+                    let item_snbt = fastsnbt::to_string(item).unwrap_or_default();
+                    commands.push(format!("data modify {} {} append value {}", target, key, item_snbt));
+                }
+            }
+            continue;
+        }
+        let mut merged = pending.clone();
+        merged.insert(key.clone(), value.clone());
+        let merged_snbt = fastsnbt::to_string(&Compound(merged.clone())).unwrap_or_default();
+        if merged_snbt.len() + target.len() + 12 > COMMAND_LENGTH_LIMIT {
+            let pending_snbt = fastsnbt::to_string(&Compound(std::mem::take(&mut pending))).unwrap_or_default();
+            commands.push(format!("data merge {} {}", target, pending_snbt));
+            pending.insert(key.clone(), value.clone());
+        } else {
+            pending = merged;
+        }
+    }
+    if !pending.is_empty() {
+        let pending_snbt = fastsnbt::to_string(&Compound(pending)).unwrap_or_default();
+        commands.push(format!("data merge {} {}", target, pending_snbt));
+    }
+    commands
+}
@@ -0,0 +1,150 @@
+use crate::data_files::files::FileManager;
+use crate::database::db_apis::schematics_api::find_schematic;
+use crate::database::db_control::DatabaseState;
+use crate::render::block_colors::BlockColors;
+use crate::render::layers::{LayerOptions, LayerRenderer};
+use crate::render::pdf::{PdfPage, PdfWriter};
+use crate::utils::extend_write::{resolve_target, to_writer_zip};
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::requirements::get_requirements;
+use anyhow::Result;
+use base64::Engine;
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use serde_json::json;
+use std::fs::File;
+use std::io::{BufWriter, Cursor};
+use tauri::State;
+
+fn encode_png(image: RgbaImage) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgba8(image).write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+/// 单层材料数，按数量降序
+fn layer_materials(renderer: &LayerRenderer, index: usize) -> Result<Vec<(String, i32)>> {
+    let requirements = get_requirements(renderer.layer_blocks(index))?;
+    let mut list: Vec<(String, i32)> = requirements
+        .get_requirements()
+        .iter()
+        .map(|(id, &count)| (id.name.to_string(), count))
+        .collect();
+    list.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(list)
+}
+
+#[tauri::command]
+pub async fn get_layer_image(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    block_colors: State<'_, BlockColors>,
+    id: i64,
+    layer: usize,
+    cell_size: u32,
+    ruler: bool,
+) -> Result<String, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        let data = file_manager.get_schematic_data(
+            id,
+            schematic.version,
+            schematic.sub_type,
+            schematic.schematic_type,
+        )?;
+        let renderer = LayerRenderer::new(&data, &block_colors, LayerOptions { cell_size, ruler });
+        if layer >= renderer.layer_count() {
+            anyhow::bail!("layer {} out of range (0..{})", layer, renderer.layer_count());
+        }
+        let png = encode_png(renderer.render(layer))?;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(png);
+        Ok(format!("data:image/png;base64,{}", encoded))
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+#[tauri::command]
+pub async fn export_build_guide(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    block_colors: State<'_, BlockColors>,
+    je_blocks: State<'_, BlocksData>,
+    id: i64,
+    target: String,
+    format: String,
+    cell_size: u32,
+    ruler: bool,
+) -> Result<usize, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        let data = file_manager.get_schematic_data(
+            id,
+            schematic.version,
+            schematic.sub_type,
+            schematic.schematic_type,
+        )?;
+        let renderer = LayerRenderer::new(&data, &block_colors, LayerOptions { cell_size, ruler });
+        let layers = renderer.layer_count();
+
+        let extension = match format.as_str() {
+            "pdf" | "zip" => format.as_str(),
+            other => anyhow::bail!("unsupported build guide format: {}", other),
+        };
+        let dest_path = resolve_target(&target, &format!("{}_guide.{}", schematic.name, extension))?;
+
+        if extension == "pdf" {
+            let file = BufWriter::new(File::create(&dest_path)?);
+            let mut writer = PdfWriter::new(file)?;
+            for index in 0..layers {
+                let materials = layer_materials(&renderer, index)?;
+                writer.add_page(&PdfPage {
+                    title: format!(
+                        "{} - layer {}/{} (y={})",
+                        schematic.name,
+                        index + 1,
+                        layers,
+                        renderer.layer_y(index)
+                    ),
+                    image: renderer.render(index),
+                    lines: materials
+                        .iter()
+                        .map(|(name, count)| {
+                            let short = name.replace("minecraft:", "");
+                            let label = je_blocks.get_zh_cn(&short).map(str::to_string).unwrap_or(short);
+                            format!("{} x{}", label, count)
+                        })
+                        .collect(),
+                })?;
+            }
+            writer.finish()?;
+        } else {
+            let mut files = Vec::with_capacity(layers + 1);
+            let mut manifest = Vec::with_capacity(layers);
+            for index in 0..layers {
+                let y = renderer.layer_y(index);
+                let file = format!("layer_{:03}_y{}.png", index, y);
+                let materials = layer_materials(&renderer, index)?;
+                manifest.push(json!({
+                    "file": file,
+                    "layer": index,
+                    "y": y,
+                    "materials": materials.iter().map(|(name, count)| json!({
+                        "id": name,
+                        "zh_cn": je_blocks
+                            .get_zh_cn(&name.replace("minecraft:", ""))
+                            .unwrap_or(name),
+                        "num": count,
+                    })).collect::<Vec<_>>(),
+                }));
+                files.push((file, encode_png(renderer.render(index))?));
+            }
+            files.push(("layers.json".to_string(), serde_json::to_vec_pretty(&manifest)?));
+            to_writer_zip(&files, &dest_path.to_string_lossy())?;
+        }
+        Ok(layers)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
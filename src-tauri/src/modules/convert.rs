@@ -12,6 +12,8 @@ use std::collections::HashMap;
 use fastnbt::Value::Compound;
 use tauri::State;
 use crate::be_schematic::to_be_schematic::ToBESchematic;
+use crate::render::block_colors::BlockColors;
+use crate::render::isometric::render_lm_preview;
 
 #[tauri::command]
 pub async fn get_schematic_convert_data(
@@ -60,6 +62,7 @@ pub async fn get_map_arts(
 pub async fn convert(
     db: State<'_, DatabaseState>,
     file_manager: State<'_, FileManager>,
+    block_colors: State<'_, BlockColors>,
     id: i64,
     schematic_type: i64,
     lm_version: i64,
@@ -80,7 +83,9 @@ pub async fn convert(
                 file_manager.save_nbt_value(id, data, version, -1, schematic_type as i32, true)?;
             }
             2 => {
-                let data = ToLmSchematic::new(&data)?.lm_schematic(lm_version as i32);
+                let mut lm = ToLmSchematic::new(&data)?;
+                lm.set_preview_image(render_lm_preview(&data, &block_colors));
+                let data = lm.lm_schematic(lm_version as i32);
                 file_manager.save_nbt_value(id, data, version, -1, schematic_type as i32, true)?;
             }
             3 => {
@@ -0,0 +1,111 @@
+use crate::data_files::files::FileManager;
+use crate::database::db_apis::schematics_api::find_schematic;
+use crate::database::db_control::DatabaseState;
+use crate::datapack::worldgen::{sanitize_name, worldgen_datapack_files, WorldgenOptions, WorldgenStructure};
+use crate::mcfunction::to_mcfunction::{McFunctionOptions, ToMcFunction};
+use crate::utils::extend_write::write_zip;
+use tauri::State;
+
+#[tauri::command]
+pub async fn export_mcfunction_datapack(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    id: i64,
+    namespace: String,
+    target: String,
+    include_air: bool,
+    pack_format: i64,
+    max_commands: i64,
+) -> Result<bool, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        let data = file_manager.get_schematic_data(
+            id,
+            schematic.version,
+            schematic.sub_type,
+            schematic.schematic_type,
+        )?;
+
+        let namespace = sanitize_name(&namespace);
+        let mut options = McFunctionOptions {
+            include_air,
+            ..Default::default()
+        };
+        if !namespace.is_empty() {
+            options.namespace = namespace;
+        }
+        if pack_format > 0 {
+            options.pack_format = pack_format as i32;
+        }
+        if max_commands > 0 {
+            options.max_commands = max_commands as usize;
+        }
+
+        let files = ToMcFunction::new(&data)?.datapack_files(&options);
+        write_zip(&files, &target, &format!("{}.zip", options.namespace))?;
+        Ok(true)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+#[tauri::command]
+pub async fn export_structure_datapack(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    ids: Vec<i64>,
+    namespace: String,
+    target: String,
+    spacing: i64,
+    separation: i64,
+    biomes: Vec<String>,
+    include_air: bool,
+    pack_format: i64,
+) -> Result<bool, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let mut structures = Vec::with_capacity(ids.len());
+        for id in ids {
+            let schematic = find_schematic(&mut conn, id)?;
+            let data = file_manager.get_schematic_data(
+                id,
+                schematic.version,
+                schematic.sub_type,
+                schematic.schematic_type,
+            )?;
+            let name = sanitize_name(&schematic.name);
+            let name = if name.is_empty() {
+                format!("schematic_{}", id)
+            } else {
+                format!("{}_{}", name, id)
+            };
+            structures.push(WorldgenStructure { name, data });
+        }
+
+        let mut options = WorldgenOptions {
+            include_air,
+            biomes: biomes.into_iter().filter(|b| !b.trim().is_empty()).collect(),
+            ..Default::default()
+        };
+        let namespace = sanitize_name(&namespace);
+        if !namespace.is_empty() {
+            options.namespace = namespace;
+        }
+        if pack_format > 0 {
+            options.pack_format = pack_format as i32;
+        }
+        if spacing > 0 {
+            options.spacing = spacing as i32;
+        }
+        if separation >= 0 {
+            options.separation = separation as i32;
+        }
+
+        let files = worldgen_datapack_files(structures, &options)?;
+        write_zip(&files, &target, &format!("{}.zip", options.namespace))?;
+        Ok(true)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
@@ -0,0 +1,311 @@
+use crate::data_files::files::FileManager;
+use crate::database::db_apis::links_api::{new_schematic_link, TERRAIN_LINK};
+use crate::database::db_control::DatabaseState;
+use crate::generator::mesh_import::load_model;
+use crate::generator::mural::{generate_mural, MuralOptions};
+use crate::generator::note_blocks::{generate_note_blocks, parse_nbs, validate_circuit, NoteBlockOptions, SongInfo};
+use crate::generator::shapes::{generate_shape, ShapeOptions};
+use crate::generator::terrain::{chunk_terrain, generate_terrain, TerrainOptions};
+use crate::generator::text::{generate_text, TextOptions};
+use crate::generator::voxel::{voxelize, VoxelOptions};
+use crate::modules::map_art::{pack_map_arts, preview_data_url};
+use crate::modules::schematic::save_schematic_entry;
+use crate::render::block_colors::BlockColors;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::minecraft_data::map_art_data::MapArtsData;
+use crate::utils::schematic_data::{SchematicData, Size};
+use serde::Serialize;
+use std::path::Path;
+use tauri::State;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerateResult {
+    pub id: i64,
+    pub size: Size,
+    pub blocks: usize,
+    // 分块保存时的子蓝图 id
+    pub parts: Vec<i64>,
+}
+
+/// 生成结果统一写入仓库，之后可走 convert 导出任意格式
+fn save_generated(
+    db: &DatabaseState,
+    file_manager: &FileManager,
+    je_blocks: &BlocksData,
+    block_colors: &BlockColors,
+    data: &mut SchematicData,
+    name: String,
+    schematic_type: i64,
+    sub_version: i64,
+) -> anyhow::Result<GenerateResult> {
+    data.tile_entities_list.original_type = schematic_type as i32;
+    let mut conn = db.0.get()?;
+    let id = save_schematic_entry(
+        &mut conn,
+        file_manager,
+        je_blocks,
+        block_colors,
+        data,
+        name,
+        schematic_type as i32,
+        sub_version as i32,
+    )?;
+    Ok(GenerateResult {
+        id,
+        size: data.size,
+        blocks: data.blocks.elements.len(),
+        parts: Vec::new(),
+    })
+}
+
+#[tauri::command]
+pub async fn preview_mural(
+    file_manager: State<'_, FileManager>,
+    block_colors: State<'_, BlockColors>,
+    map_arts: State<'_, MapArtsData>,
+    image: Vec<u8>,
+    options: MuralOptions,
+    pack: Option<String>,
+) -> Result<String, String> {
+    async move {
+        let map_arts = pack_map_arts(&map_arts, &file_manager, &block_colors, pack.as_deref())?;
+        let mural = generate_mural(&image, &map_arts, &options)?;
+        preview_data_url(mural.preview)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// 图片生成竖直墙画，按方块侧面颜色匹配，不涉及地图明暗
+#[tauri::command]
+pub async fn create_mural(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    je_blocks: State<'_, BlocksData>,
+    block_colors: State<'_, BlockColors>,
+    map_arts: State<'_, MapArtsData>,
+    image: Vec<u8>,
+    file_name: String,
+    options: MuralOptions,
+    schematic_type: i64,
+    sub_version: i64,
+    pack: Option<String>,
+) -> Result<GenerateResult, String> {
+    async move {
+        let map_arts = pack_map_arts(&map_arts, &file_manager, &block_colors, pack.as_deref())?;
+        let mut mural = generate_mural(&image, &map_arts, &options)?;
+        save_generated(
+            &db,
+            &file_manager,
+            &je_blocks,
+            &block_colors,
+            &mut mural.data,
+            format!("mural_{}", file_name),
+            schematic_type,
+            sub_version,
+        )
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// 高度图生成地形，可附带地表遮罩；chunk_size 大于 0 时额外保存关联的分块
+#[tauri::command]
+pub async fn create_terrain(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    je_blocks: State<'_, BlocksData>,
+    block_colors: State<'_, BlockColors>,
+    heightmap: Vec<u8>,
+    file_name: String,
+    options: TerrainOptions,
+    schematic_type: i64,
+    sub_version: i64,
+) -> Result<GenerateResult, String> {
+    async move {
+        let mut data = generate_terrain(&heightmap, &options)?;
+        let mut result = save_generated(
+            &db,
+            &file_manager,
+            &je_blocks,
+            &block_colors,
+            &mut data,
+            format!("terrain_{}", file_name),
+            schematic_type,
+            sub_version,
+        )?;
+        if options.chunk_size == 0 {
+            return Ok(result);
+        }
+        let chunks = chunk_terrain(&data, options.chunk_size);
+        if chunks.len() <= 1 {
+            return Ok(result);
+        }
+        for mut chunk in chunks {
+            let part = save_generated(
+                &db,
+                &file_manager,
+                &je_blocks,
+                &block_colors,
+                &mut chunk.data,
+                format!("terrain_{}_{}", file_name, chunk.index + 1),
+                schematic_type,
+                sub_version,
+            )?;
+            let offset = [chunk.offset.x, chunk.offset.y, chunk.offset.z];
+            let mut conn = db.0.get()?;
+            new_schematic_link(&mut conn, result.id, part.id, chunk.index as i32, offset, TERRAIN_LINK)?;
+            result.parts.push(part.id);
+        }
+        Ok(result)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// 体素化 OBJ/STL/glTF/GLB 模型，按模型颜色匹配方块
+#[tauri::command]
+pub async fn create_voxel_model(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    je_blocks: State<'_, BlocksData>,
+    block_colors: State<'_, BlockColors>,
+    map_arts: State<'_, MapArtsData>,
+    path: String,
+    options: VoxelOptions,
+    schematic_type: i64,
+    sub_version: i64,
+    pack: Option<String>,
+) -> Result<GenerateResult, String> {
+    async move {
+        let path = Path::new(&path);
+        let mesh = load_model(path)?;
+        let map_arts = pack_map_arts(&map_arts, &file_manager, &block_colors, pack.as_deref())?;
+        let mut data = voxelize(&mesh, &map_arts, &options)?;
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        save_generated(
+            &db,
+            &file_manager,
+            &je_blocks,
+            &block_colors,
+            &mut data,
+            format!("voxel_{}", name),
+            schematic_type,
+            sub_version,
+        )
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// 参数化几何体：球、圆柱、圆环、穹顶、拱、螺旋
+#[tauri::command]
+pub async fn create_shape(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    je_blocks: State<'_, BlocksData>,
+    block_colors: State<'_, BlockColors>,
+    file_name: String,
+    options: ShapeOptions,
+    schematic_type: i64,
+    sub_version: i64,
+) -> Result<GenerateResult, String> {
+    async move {
+        let mut data = generate_shape(&options)?;
+        save_generated(
+            &db,
+            &file_manager,
+            &je_blocks,
+            &block_colors,
+            &mut data,
+            format!("shape_{}", file_name),
+            schematic_type,
+            sub_version,
+        )
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// 文字生成，内置点阵字体或指定 TTF/OTF 字体文件
+#[tauri::command]
+pub async fn create_text(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    je_blocks: State<'_, BlocksData>,
+    block_colors: State<'_, BlockColors>,
+    file_name: String,
+    options: TextOptions,
+    schematic_type: i64,
+    sub_version: i64,
+) -> Result<GenerateResult, String> {
+    async move {
+        let font_data = match &options.font_path {
+            Some(path) => Some(std::fs::read(path)?),
+            None => None,
+        };
+        let mut data = generate_text(&options, font_data)?;
+        save_generated(
+            &db,
+            &file_manager,
+            &je_blocks,
+            &block_colors,
+            &mut data,
+            format!("text_{}", file_name),
+            schematic_type,
+            sub_version,
+        )
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteBlockResult {
+    #[serde(flatten)]
+    pub result: GenerateResult,
+    pub song: SongInfo,
+}
+
+/// 读取 Note Block Studio 的 .nbs 生成可演奏的音符盒线路，按钮在启动线起点
+#[tauri::command]
+pub async fn create_note_block_song(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    je_blocks: State<'_, BlocksData>,
+    block_colors: State<'_, BlockColors>,
+    path: String,
+    options: NoteBlockOptions,
+    schematic_type: i64,
+    sub_version: i64,
+) -> Result<NoteBlockResult, String> {
+    async move {
+        let path = Path::new(&path);
+        let song = parse_nbs(&std::fs::read(path)?)?;
+        let mut generated = generate_note_blocks(&song, &options)?;
+        validate_circuit(&generated.data, &je_blocks)?;
+        let name = match song.name.trim() {
+            "" => path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            name => name.to_string(),
+        };
+        let result = save_generated(
+            &db,
+            &file_manager,
+            &je_blocks,
+            &block_colors,
+            &mut generated.data,
+            format!("song_{}", name),
+            schematic_type,
+            sub_version,
+        )?;
+        Ok(NoteBlockResult { result, song: generated.info })
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
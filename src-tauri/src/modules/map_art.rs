@@ -2,6 +2,7 @@ use crate::building_gadges::to_bg_schematic::ToBgSchematic;
 use crate::create::to_create_schematic::ToCreateSchematic;
 use crate::data_files::files::FileManager;
 use crate::database::db_apis::history_api::new_history;
+use crate::database::db_apis::links_api::{new_schematic_link, MAP_ART_LINK};
 use crate::database::db_apis::schematic_data_api::new_schematic_data;
 use crate::database::db_apis::schematics_api::new_schematic;
 use crate::database::db_apis::user_api::add_user_schematic;
@@ -20,6 +21,82 @@ use crate::utils::entities::{EntitiesList};
 use std::collections::VecDeque;
 use tauri::State;
 use crate::be_schematic::to_be_schematic::ToBESchematic;
+use crate::map_art::generator::{generate_map_art, MapArtOptions};
+use crate::map_art::map_item::{map_item_files, MapColors, MapItemOptions};
+use crate::map_art::palette::MapPalette;
+use crate::map_art::texture_colors::{map_arts_from_pack, pack_cache_key};
+use crate::utils::minecraft_data::map_art_data::CategoryBlocks;
+use std::borrow::Cow;
+use std::path::Path;
+use crate::database::db_apis::schematics_api::find_schematic;
+use crate::utils::extend_write::write_zip;
+use crate::modules::schematic::save_schematic_entry;
+use crate::render::block_colors::BlockColors;
+use crate::utils::minecraft_data::map_art_data::MapArtsData;
+use base64::Engine;
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use serde::Serialize;
+use std::io::Cursor;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MapArtResult {
+    pub id: i64,
+    pub preview: String,
+    pub size: Size,
+    pub blocks: usize,
+    // 按地图切分后保存的子蓝图 id
+    pub parts: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MapItemsResult {
+    pub start_id: i32,
+    pub count: usize,
+    pub columns: usize,
+    pub rows: usize,
+    pub commands: Vec<String>,
+}
+
+/// 按资源包重新取色的地图画数据，结果按资源包缓存；未指定资源包时使用内置数据
+pub fn pack_map_arts<'a>(
+    map_arts: &'a MapArtsData,
+    file_manager: &FileManager,
+    block_colors: &BlockColors,
+    pack: Option<&str>,
+) -> anyhow::Result<Cow<'a, MapArtsData>> {
+    let Some(pack) = pack.filter(|p| !p.is_empty()) else {
+        return Ok(Cow::Borrowed(map_arts));
+    };
+    let path = Path::new(pack);
+    let key = pack_cache_key(path)?;
+    if let Some(json) = file_manager.read_pack_colors(&key)? {
+        if let Ok(data) = serde_json::from_str(&json) {
+            return Ok(Cow::Owned(data));
+        }
+    }
+    let data = map_arts_from_pack(map_arts, path, block_colors)?;
+    file_manager.save_pack_colors(&key, &serde_json::to_string(&data)?)?;
+    Ok(Cow::Owned(data))
+}
+
+fn write_map_items(colors: &MapColors, options: &MapItemOptions, target: &str, name: &str) -> anyhow::Result<MapItemsResult> {
+    let items = map_item_files(colors, options)?;
+    write_zip(&items.files, target, &format!("{}_maps.zip", name))?;
+    Ok(MapItemsResult {
+        start_id: options.start_id,
+        count: items.commands.len(),
+        columns: items.columns,
+        rows: items.rows,
+        commands: items.commands,
+    })
+}
+
+pub fn preview_data_url(image: RgbaImage) -> anyhow::Result<String> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgba8(image).write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    Ok(format!("data:image/png;base64,{}", encoded))
+}
 
 #[tauri::command]
 pub async fn create_map_art(
@@ -297,3 +374,145 @@ pub async fn create_map_art(
     .await
     .map_err(|e: anyhow::Error| e.to_string())
 }
+
+/// 只生成预览图，不写入仓库
+#[tauri::command]
+pub async fn preview_map_art(
+    file_manager: State<'_, FileManager>,
+    block_colors: State<'_, BlockColors>,
+    map_arts: State<'_, MapArtsData>,
+    image: Vec<u8>,
+    blocks: Vec<String>,
+    options: MapArtOptions,
+    pack: Option<String>,
+) -> Result<String, String> {
+    async move {
+        let map_arts = pack_map_arts(&map_arts, &file_manager, &block_colors, pack.as_deref())?;
+        let palette = MapPalette::from_selection(&map_arts, &blocks)?;
+        let art = generate_map_art(&image, &palette, &options)?;
+        preview_data_url(art.preview)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// 在后端完成缩放、配色、抖动与阶梯高度计算，直接保存为蓝图
+#[tauri::command]
+pub async fn create_map_art_from_image(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    je_blocks: State<'_, BlocksData>,
+    block_colors: State<'_, BlockColors>,
+    map_arts: State<'_, MapArtsData>,
+    image: Vec<u8>,
+    file_name: String,
+    blocks: Vec<String>,
+    options: MapArtOptions,
+    schematic_type: i64,
+    sub_version: i64,
+    pack: Option<String>,
+) -> Result<MapArtResult, String> {
+    async move {
+        let map_arts = pack_map_arts(&map_arts, &file_manager, &block_colors, pack.as_deref())?;
+        let palette = MapPalette::from_selection(&map_arts, &blocks)?;
+        let mut art = generate_map_art(&image, &palette, &options)?;
+        art.data.tile_entities_list.original_type = schematic_type as i32;
+        let mut conn = db.0.get()?;
+        let id = save_schematic_entry(
+            &mut conn,
+            &file_manager,
+            &je_blocks,
+            &block_colors,
+            &art.data,
+            format!("map_art_{}", file_name),
+            schematic_type as i32,
+            sub_version as i32,
+        )?;
+        let mut parts = Vec::with_capacity(art.parts.len());
+        for part in art.parts.iter_mut() {
+            part.data.tile_entities_list.original_type = schematic_type as i32;
+            let child_id = save_schematic_entry(
+                &mut conn,
+                &file_manager,
+                &je_blocks,
+                &block_colors,
+                &part.data,
+                format!("map_art_{}_r{}_c{}", file_name, part.row + 1, part.column + 1),
+                schematic_type as i32,
+                sub_version as i32,
+            )?;
+            let offset = [part.offset.x, part.offset.y, part.offset.z];
+            new_schematic_link(&mut conn, id, child_id, part.index as i32, offset, MAP_ART_LINK)?;
+            parts.push(child_id);
+        }
+        Ok(MapArtResult {
+            id,
+            size: art.data.size,
+            blocks: art.data.blocks.elements.len(),
+            preview: preview_data_url(art.preview)?,
+            parts,
+        })
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// 图片直接转为 map_<id>.dat，不经过蓝图
+#[tauri::command]
+pub async fn export_map_items_from_image(
+    image: Vec<u8>,
+    file_name: String,
+    options: MapArtOptions,
+    map_options: MapItemOptions,
+    target: String,
+) -> Result<MapItemsResult, String> {
+    async move {
+        let colors = MapColors::from_image(&image, &options)?;
+        write_map_items(&colors, &map_options, &target, &file_name)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// 从已生成的地图画蓝图导出 map_<id>.dat
+#[tauri::command]
+pub async fn export_map_items(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    block_colors: State<'_, BlockColors>,
+    map_arts: State<'_, MapArtsData>,
+    id: i64,
+    map_options: MapItemOptions,
+    target: String,
+) -> Result<MapItemsResult, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        let data = file_manager.get_schematic_data(
+            id,
+            schematic.version,
+            schematic.sub_type,
+            schematic.schematic_type,
+        )?;
+        let colors = MapColors::from_schematic(&data, &map_arts, &block_colors, map_options.staircase);
+        write_map_items(&colors, &map_options, &target, &schematic.name)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// 与 get_map_arts 结构相同，颜色取自指定的原版 jar 或资源包
+#[tauri::command]
+pub async fn get_pack_map_arts(
+    file_manager: State<'_, FileManager>,
+    block_colors: State<'_, BlockColors>,
+    map_arts: State<'_, MapArtsData>,
+    pack: String,
+) -> Result<CategoryBlocks, String> {
+    async move {
+        let data = pack_map_arts(&map_arts, &file_manager, &block_colors, Some(&pack))?;
+        Ok(data.into_owned().categories)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
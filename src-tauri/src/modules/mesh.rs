@@ -0,0 +1,47 @@
+use crate::data_files::files::FileManager;
+use crate::database::db_apis::schematics_api::find_schematic;
+use crate::database::db_control::DatabaseState;
+use crate::render::block_colors::BlockColors;
+use crate::render::mesh::build_mesh;
+use crate::render::mesh_export::{write_glb, write_obj};
+use crate::utils::extend_write::{resolve_target, to_writer_zip};
+use tauri::State;
+
+/// 导出三维网格，format 为 obj(打包 obj/mtl/材质为 zip) 或 glb，返回三角形数
+#[tauri::command]
+pub async fn export_mesh(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    block_colors: State<'_, BlockColors>,
+    id: i64,
+    target: String,
+    format: String,
+) -> Result<usize, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        let data = file_manager.get_schematic_data(
+            id,
+            schematic.version,
+            schematic.sub_type,
+            schematic.schematic_type,
+        )?;
+        let extension = match format.as_str() {
+            "obj" => "zip",
+            "glb" => "glb",
+            other => anyhow::bail!("unsupported mesh format: {}", other),
+        };
+        let dest_path = resolve_target(&target, &format!("{}.{}", schematic.name, extension))?;
+
+        let mesh = build_mesh(&data, &block_colors);
+        if format == "obj" {
+            let files = write_obj(&mesh, "model")?;
+            to_writer_zip(&files, &dest_path.to_string_lossy())?;
+        } else {
+            std::fs::write(&dest_path, write_glb(&mesh)?)?;
+        }
+        Ok(mesh.triangle_count())
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
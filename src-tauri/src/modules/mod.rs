@@ -1,6 +1,12 @@
+pub mod build_guide;
 pub mod convert;
+pub mod datapack;
+pub mod generate;
 pub mod history;
 pub mod map_art;
+pub mod mesh;
 pub mod modules_data;
 pub mod replace;
 pub mod schematic;
+pub mod structure;
+pub mod thumbnail;
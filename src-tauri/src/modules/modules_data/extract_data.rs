@@ -0,0 +1,133 @@
+use crate::modules::modules_data::replace_data::{PropertyValues, RuleMatcher};
+use crate::utils::block_state_pos_list::{BlockData, BlockId};
+use crate::utils::minecraft_data::block_tags::BlockTags;
+use crate::utils::schematic_data::SchematicData;
+use anyhow::Result;
+use fastnbt::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+/// 与替换规则相同的匹配语言，mode 含义见 ReplacementRule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockPredicate {
+    pub mode: i64,
+    pub original_id: Option<String>,
+    #[serde(default)]
+    pub original_details: Option<BlockData>,
+    #[serde(default)]
+    pub match_properties: BTreeMap<String, PropertyValues>,
+}
+
+impl BlockPredicate {
+    fn compile(&self, tags: &BlockTags) -> Result<RuleMatcher> {
+        RuleMatcher::compile(
+            self.mode,
+            self.original_id.as_ref(),
+            self.original_details.as_ref(),
+            &self.match_properties,
+            tags,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractMode {
+    // 只保留命中的方块与实体
+    Keep,
+    // 去掉命中的方块与实体
+    Remove,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractOptions {
+    pub mode: ExtractMode,
+    // 任一条命中即算命中
+    #[serde(default)]
+    pub blocks: Vec<BlockPredicate>,
+    // 按实体 id 匹配，只支持 id、通配符、正则
+    #[serde(default)]
+    pub entities: Vec<BlockPredicate>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExtractStats {
+    pub blocks: usize,
+    pub tile_entities: usize,
+    pub entities: usize,
+    pub removed_blocks: usize,
+    pub removed_entities: usize,
+}
+
+/// 实体 id，兼容原版结构、投影、创世神等格式
+pub fn entity_id(nbt: &Value) -> Option<String> {
+    let Value::Compound(map) = nbt else {
+        return None;
+    };
+    if let Some(Value::Compound(inner)) = map.get("nbt") {
+        if let Some(Value::String(id)) = inner.get("id") {
+            return Some(id.clone());
+        }
+    }
+    ["id", "Id", "identifier"].iter().find_map(|key| match map.get(*key) {
+        Some(Value::String(id)) => Some(id.clone()),
+        _ => None,
+    })
+}
+
+/// 方块实体跟随所在方块保留或删除，尺寸不变便于与原蓝图对齐
+pub fn extract(data: &mut SchematicData, options: &ExtractOptions, tags: &BlockTags) -> Result<ExtractStats> {
+    if options.blocks.is_empty() && options.entities.is_empty() {
+        anyhow::bail!("extract needs at least one block or entity predicate");
+    }
+    let blocks = options
+        .blocks
+        .iter()
+        .map(|p| p.compile(tags))
+        .collect::<Result<Vec<_>>>()?;
+    let entities = options
+        .entities
+        .iter()
+        .map(|p| p.compile(tags))
+        .collect::<Result<Vec<_>>>()?;
+    let keep = options.mode == ExtractMode::Keep;
+
+    let before = data.blocks.elements.len();
+    data.blocks
+        .elements
+        .retain(|b| blocks.iter().any(|m| m.matches(&b.block)) == keep);
+    let positions: HashSet<(i32, i32, i32)> = data
+        .blocks
+        .elements
+        .iter()
+        .map(|b| (b.pos.x, b.pos.y, b.pos.z))
+        .collect();
+    data.tile_entities_list
+        .elements
+        .retain(|te| positions.contains(&(te.pos.x, te.pos.y, te.pos.z)));
+
+    let entity_count = data.entities_list.elements.len();
+    data.entities_list.elements.retain(|e| {
+        let matched = entity_id(&e.nbt).is_some_and(|id| {
+            let id = if id.contains(':') { id } else { format!("minecraft:{}", id) };
+            let probe = BlockData {
+                id: BlockId { name: Arc::from(id) },
+                properties: BTreeMap::new(),
+            };
+            entities.iter().any(|m| m.matches(&probe))
+        });
+        matched == keep
+    });
+
+    if data.blocks.elements.is_empty() && data.entities_list.elements.is_empty() {
+        anyhow::bail!("nothing left after extraction");
+    }
+    Ok(ExtractStats {
+        blocks: data.blocks.elements.len(),
+        tile_entities: data.tile_entities_list.elements.len(),
+        entities: data.entities_list.elements.len(),
+        removed_blocks: before - data.blocks.elements.len(),
+        removed_entities: entity_count - data.entities_list.elements.len(),
+    })
+}
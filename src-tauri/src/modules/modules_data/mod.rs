@@ -1,3 +1,6 @@
 pub mod convert_data;
+pub mod extract_data;
 pub mod history_data;
+pub mod recolor_data;
 pub mod replace_data;
+pub mod theme_data;
@@ -0,0 +1,134 @@
+use crate::generator::palette::{block_color, block_palette, delta_e, rgb_to_lab, PaletteFace};
+use crate::modules::modules_data::theme_data::{base_block, block_shape, shape_equivalent};
+use crate::utils::block_state_pos_list::BlockId;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::minecraft_data::map_art_data::MapArtsData;
+use crate::utils::schematic_data::SchematicData;
+use anyhow::Result;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecolorMapping {
+    pub original: String,
+    pub replacement: String,
+    pub count: usize,
+    // CIELAB 色差
+    pub error: f32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecolorReport {
+    pub replaced: usize,
+    // 有颜色但调色板里没有同形状的方块，保持原样
+    pub skipped: Vec<String>,
+    pub mean_error: f32,
+    pub max_error: f32,
+    pub mappings: Vec<RecolorMapping>,
+}
+
+fn with_namespace(name: &str) -> String {
+    if name.contains(':') { name.to_string() } else { format!("minecraft:{}", name) }
+}
+
+/// 把整方块换成调色板里色差最小的方块，楼梯、台阶等形状换成目标方块的同族形状
+pub fn recolor(
+    data: &mut SchematicData,
+    map_arts: &MapArtsData,
+    je_blocks: &BlocksData,
+    allowed: &[String],
+) -> Result<RecolorReport> {
+    if allowed.is_empty() {
+        anyhow::bail!("recolor palette is empty");
+    }
+    let palette = block_palette(map_arts, allowed, &[], PaletteFace::Average)?;
+    // 调色板里的地毯等形状不参与整方块匹配，但本身算允许使用
+    let cubes: Vec<(String, [f32; 3])> = palette
+        .colors
+        .iter()
+        .filter(|c| block_shape(&c.block, je_blocks).is_none())
+        .map(|c| (c.block.clone(), rgb_to_lab(c.tones[1])))
+        .collect();
+    let allowed: BTreeSet<String> = allowed
+        .iter()
+        .map(|b| b.strip_prefix("minecraft:").unwrap_or(b).to_string())
+        .collect();
+
+    let names: BTreeSet<Arc<str>> = data
+        .blocks
+        .elements
+        .iter()
+        .map(|b| b.block.id.name.clone())
+        .collect();
+    let mut report = RecolorReport::default();
+    let mut targets: HashMap<Arc<str>, (String, f32)> = HashMap::new();
+    for name in names {
+        let short = name.strip_prefix("minecraft:").unwrap_or(&name);
+        if allowed.contains(short) {
+            continue;
+        }
+        let shape = block_shape(short, je_blocks);
+        let color = block_color(map_arts, short, PaletteFace::Average).or_else(|| {
+            let (_, stem) = shape.as_ref()?;
+            block_color(map_arts, &base_block(stem, je_blocks)?, PaletteFace::Average)
+        });
+        let Some(color) = color else {
+            continue;
+        };
+        let lab = rgb_to_lab(color);
+        let best = cubes
+            .iter()
+            .filter_map(|(cube, cube_lab)| {
+                let target = match &shape {
+                    Some((form, _)) => shape_equivalent(cube, form, je_blocks)?,
+                    None => cube.clone(),
+                };
+                Some((target, delta_e(lab, *cube_lab)))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((target, error)) if target != short => {
+                targets.insert(name.clone(), (with_namespace(&target), error));
+            }
+            Some(_) => {}
+            None => report.skipped.push(name.to_string()),
+        }
+    }
+
+    let replaced: Vec<Option<Arc<str>>> = data
+        .blocks
+        .elements
+        .par_iter_mut()
+        .map(|block| {
+            let (target, _) = targets.get(&block.block.id.name)?;
+            let original = block.block.id.name.clone();
+            Arc::make_mut(&mut block.block).id = BlockId { name: Arc::from(target.as_str()) };
+            Some(original)
+        })
+        .collect();
+    let mut counts: HashMap<Arc<str>, usize> = HashMap::new();
+    for original in replaced.into_iter().flatten() {
+        *counts.entry(original).or_default() += 1;
+    }
+
+    let mut total_error = 0.0;
+    for (original, (replacement, error)) in targets {
+        let count = counts.get(&original).copied().unwrap_or(0);
+        report.replaced += count;
+        total_error += error * count as f32;
+        report.max_error = report.max_error.max(error);
+        report.mappings.push(RecolorMapping {
+            original: original.to_string(),
+            replacement,
+            count,
+            error,
+        });
+    }
+    if report.replaced > 0 {
+        report.mean_error = total_error / report.replaced as f32;
+    }
+    report.mappings.sort_by(|a, b| b.count.cmp(&a.count).then(a.original.cmp(&b.original)));
+    Ok(report)
+}
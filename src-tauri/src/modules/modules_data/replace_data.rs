@@ -1,25 +1,350 @@
-use crate::utils::block_state_pos_list::BlockData;
+use crate::utils::block_state_pos_list::{BlockData, BlockPos};
+use crate::utils::minecraft_data::block_tags::BlockTags;
+use anyhow::Result;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RuleRegion {
+    pub min: [i32; 3],
+    pub max: [i32; 3],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightedReplacement {
+    pub id: String,
+    pub weight: u32,
+}
+
+/// 属性条件，单值、多值之一，或 `any`/`*` 表示只要有该属性
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PropertyValues {
+    One(String),
+    Many(Vec<String>),
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReplacementRule {
     pub schematic_id: i64,
+    // 0 按 id 匹配，1 按完整状态匹配，2 按 id（可空）加部分属性匹配
+    // 3 方块标签、4 通配符、5 正则（需匹配带命名空间的完整 id），写在 original_id 里，同样可加属性条件
     pub mode: i64,
     pub original_id: Option<String>,
     pub replacement_id: Option<String>,
     pub original_details: Option<BlockData>,
     pub replacement_details: Option<BlockData>,
+    // global 为 false 时只替换 quantity 个
     pub quantity: i64,
     pub global: bool,
+    // 包围盒，两端包含
+    #[serde(default)]
+    pub region: Option<RuleRegion>,
+    #[serde(default)]
+    pub y_range: Option<[i32; 2]>,
+    // 0-100，命中的方块按概率替换
+    #[serde(default)]
+    pub percentage: Option<f64>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub match_properties: BTreeMap<String, PropertyValues>,
+    #[serde(default)]
+    pub set_properties: BTreeMap<String, String>,
+    #[serde(default)]
+    pub remove_properties: Vec<String>,
+    // 非空时按权重随机取一个 id 替换，保留原属性
+    #[serde(default)]
+    pub mix: Vec<WeightedReplacement>,
 }
+
+#[derive(Debug, Clone)]
+pub enum PropertyPredicate {
+    Any,
+    OneOf(Vec<String>),
+}
+
+impl PropertyPredicate {
+    fn new(values: &PropertyValues) -> Self {
+        match values {
+            PropertyValues::One(v) if v == "any" || v == "*" => PropertyPredicate::Any,
+            PropertyValues::One(v) => PropertyPredicate::OneOf(vec![v.clone()]),
+            PropertyValues::Many(vs) => PropertyPredicate::OneOf(vs.clone()),
+        }
+    }
+
+    fn matches(&self, value: Option<&Arc<str>>) -> bool {
+        match (self, value) {
+            (_, None) => false,
+            (PropertyPredicate::Any, Some(_)) => true,
+            (PropertyPredicate::OneOf(vs), Some(v)) => vs.iter().any(|x| x.as_str() == v.as_ref()),
+        }
+    }
+}
+
+pub type PropertyPredicates = Vec<(String, PropertyPredicate)>;
+
 #[derive(Debug, Clone)]
 pub enum RuleMatcher {
     IdMatch {
         original: String,
-        replacement: String,
     },
     FullMatch {
         original: BlockData,
-        replacement: BlockData,
     },
+    PropertyMatch {
+        original: Option<String>,
+        properties: PropertyPredicates,
+    },
+    TagMatch {
+        ids: HashSet<String>,
+        properties: PropertyPredicates,
+    },
+    GlobMatch {
+        pattern: Regex,
+        properties: PropertyPredicates,
+    },
+    RegexMatch {
+        pattern: Regex,
+        properties: PropertyPredicates,
+    },
+}
+
+/// `*` 匹配任意字符、`?` 匹配单个字符，不带命名空间时默认 minecraft
+fn glob_to_regex(glob: &str) -> Result<Regex> {
+    let glob = if glob.contains(':') { glob.to_string() } else { format!("minecraft:{}", glob) };
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Ok(Regex::new(&pattern)?)
+}
+
+impl RuleMatcher {
+    pub fn new(rule: &ReplacementRule, tags: &BlockTags) -> Result<Self> {
+        Self::compile(
+            rule.mode,
+            rule.original_id.as_ref(),
+            rule.original_details.as_ref(),
+            &rule.match_properties,
+            tags,
+        )
+    }
+
+    pub fn compile(
+        mode: i64,
+        original_id: Option<&String>,
+        original_details: Option<&BlockData>,
+        match_properties: &BTreeMap<String, PropertyValues>,
+        tags: &BlockTags,
+    ) -> Result<Self> {
+        let properties = || {
+            match_properties
+                .iter()
+                .map(|(k, v)| (k.clone(), PropertyPredicate::new(v)))
+                .collect::<PropertyPredicates>()
+        };
+        let pattern = || original_id.cloned().ok_or(anyhow::anyhow!("unknow original"));
+        Ok(match mode {
+            0 => RuleMatcher::IdMatch { original: pattern()? },
+            1 => RuleMatcher::FullMatch {
+                original: original_details
+                    .cloned()
+                    .ok_or(anyhow::anyhow!("unknow original"))?,
+            },
+            2 => RuleMatcher::PropertyMatch {
+                original: original_id.cloned(),
+                properties: properties(),
+            },
+            3 => {
+                let tag = pattern()?;
+                let ids = tags
+                    .get(&tag)
+                    .ok_or(anyhow::anyhow!("unknown block tag: {}", tag))?
+                    .clone();
+                RuleMatcher::TagMatch { ids, properties: properties() }
+            }
+            4 => RuleMatcher::GlobMatch {
+                pattern: glob_to_regex(&pattern()?)?,
+                properties: properties(),
+            },
+            5 => RuleMatcher::RegexMatch {
+                pattern: Regex::new(&format!("^(?:{})$", pattern()?))?,
+                properties: properties(),
+            },
+            _ => return Err(anyhow::anyhow!("unknow type: {}", mode)),
+        })
+    }
+
+    pub fn matches(&self, block: &BlockData) -> bool {
+        let name = block.id.name.as_ref();
+        let check = |properties: &PropertyPredicates| {
+            properties
+                .iter()
+                .all(|(k, p)| p.matches(block.properties.get(k.as_str())))
+        };
+        match self {
+            RuleMatcher::IdMatch { original } => name == original,
+            RuleMatcher::FullMatch { original } => {
+                block.id == original.id
+                    && block.properties.len() == original.properties.len()
+                    && block.properties.iter().all(|(k, v)| {
+                        original
+                            .properties
+                            .get(k.as_ref())
+                            .map(|ov| ov.as_ref() == v.as_ref())
+                            .unwrap_or(false)
+                    })
+            }
+            RuleMatcher::PropertyMatch { original, properties } => {
+                original.as_ref().is_none_or(|id| name == id) && check(properties)
+            }
+            RuleMatcher::TagMatch { ids, properties } => ids.contains(name) && check(properties),
+            RuleMatcher::GlobMatch { pattern, properties }
+            | RuleMatcher::RegexMatch { pattern, properties } => {
+                pattern.is_match(name) && check(properties)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RuleReplacement {
+    Keep,
+    Id(String),
+    Full(BlockData),
+    Mix {
+        ids: Vec<String>,
+        weights: WeightedIndex<u32>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct CompiledRule {
+    pub matcher: RuleMatcher,
+    pub replacement: RuleReplacement,
+    pub set_properties: Vec<(Arc<str>, Arc<str>)>,
+    pub remove_properties: Vec<String>,
+    pub region: Option<RuleRegion>,
+    pub y_range: Option<[i32; 2]>,
+    pub limit: Option<usize>,
+    pub percentage: Option<f64>,
+    pub seed: u64,
+}
+
+impl CompiledRule {
+    pub fn new(rule: &ReplacementRule, tags: &BlockTags) -> Result<Self> {
+        let matcher = RuleMatcher::new(rule, tags)?;
+        let edits = !rule.set_properties.is_empty() || !rule.remove_properties.is_empty();
+        let replacement = if !rule.mix.is_empty() {
+            let weights = WeightedIndex::new(rule.mix.iter().map(|m| m.weight))
+                .map_err(|e| anyhow::anyhow!("invalid mix weights: {}", e))?;
+            let ids = rule
+                .mix
+                .iter()
+                .map(|m| BlockData::from_name(&m.id).id.name.to_string())
+                .collect();
+            RuleReplacement::Mix { ids, weights }
+        } else if let (1, Some(details)) = (rule.mode, &rule.replacement_details) {
+            RuleReplacement::Full(details.clone())
+        } else if let Some(id) = rule.replacement_id.as_ref().filter(|_| rule.mode != 1) {
+            RuleReplacement::Id(id.clone())
+        } else if edits {
+            RuleReplacement::Keep
+        } else {
+            return Err(anyhow::anyhow!("unknow replacement"));
+        };
+        if let Some(p) = rule.percentage {
+            if !(0.0..=100.0).contains(&p) {
+                anyhow::bail!("percentage out of range: {}", p);
+            }
+        }
+        Ok(Self {
+            matcher,
+            replacement,
+            set_properties: rule
+                .set_properties
+                .iter()
+                .map(|(k, v)| (Arc::from(k.as_str()), Arc::from(v.as_str())))
+                .collect(),
+            remove_properties: rule.remove_properties.clone(),
+            region: rule.region,
+            y_range: rule.y_range,
+            limit: (!rule.global).then(|| rule.quantity.max(0) as usize),
+            percentage: rule.percentage,
+            seed: rule.seed.unwrap_or(0),
+        })
+    }
+
+    pub fn in_scope(&self, pos: &BlockPos) -> bool {
+        let in_region = self.region.is_none_or(|r| {
+            let p = [pos.x, pos.y, pos.z];
+            (0..3).all(|i| r.min[i].min(r.max[i]) <= p[i] && p[i] <= r.min[i].max(r.max[i]))
+        });
+        let in_y = self
+            .y_range
+            .is_none_or(|[lo, hi]| lo.min(hi) <= pos.y && pos.y <= lo.max(hi));
+        in_region && in_y
+    }
+
+    pub fn replace(&self, old: &BlockData, rng: &mut StdRng) -> BlockData {
+        let mut new_block = match &self.replacement {
+            RuleReplacement::Keep => old.clone(),
+            RuleReplacement::Id(id) => BlockData {
+                id: BlockData::from_name(id).id.clone(),
+                properties: old.properties.clone(),
+            },
+            RuleReplacement::Full(details) => details.clone(),
+            RuleReplacement::Mix { ids, weights } => BlockData {
+                id: BlockData::from_name(&ids[weights.sample(rng)]).id.clone(),
+                properties: old.properties.clone(),
+            },
+        };
+        for key in &self.remove_properties {
+            new_block.properties.remove(key.as_str());
+        }
+        for (k, v) in &self.set_properties {
+            new_block.properties.insert(k.clone(), v.clone());
+        }
+        new_block
+    }
+}
+
+// 每条规则预览时最多返回的受影响坐标数
+pub const SAMPLE_POSITIONS: usize = 32;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuleStats {
+    pub matched: usize,
+    pub replaced: usize,
+    pub samples: Vec<BlockPos>,
+}
+
+/// 替换前后材料数量的变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementDelta {
+    pub id: String,
+    pub zh_cn: String,
+    pub before: i32,
+    pub after: i32,
+    pub delta: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacementResult {
+    // 预览时为 None
+    pub id: Option<i64>,
+    pub version: i32,
+    pub dry_run: bool,
+    pub rules: Vec<RuleStats>,
+    pub delta: Vec<RequirementDelta>,
 }
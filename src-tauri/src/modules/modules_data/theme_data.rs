@@ -0,0 +1,127 @@
+use crate::database::db_data::ThemePair;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use std::collections::HashMap;
+
+// 同一材质的方块家族，{} 为材质名，例如 oak、stone_brick、white
+const FAMILY_FORMS: [&str; 39] = [
+    "{}",
+    "{}s",
+    "{}_planks",
+    "{}_log",
+    "{}_wood",
+    "stripped_{}_log",
+    "stripped_{}_wood",
+    "{}_stem",
+    "{}_hyphae",
+    "stripped_{}_stem",
+    "stripped_{}_hyphae",
+    "{}_leaves",
+    "{}_stairs",
+    "{}_slab",
+    "{}_fence",
+    "{}_fence_gate",
+    "{}_door",
+    "{}_trapdoor",
+    "{}_button",
+    "{}_pressure_plate",
+    "{}_sign",
+    "{}_wall_sign",
+    "{}_hanging_sign",
+    "{}_wall_hanging_sign",
+    "{}_wall",
+    "{}_wool",
+    "{}_carpet",
+    "{}_concrete",
+    "{}_concrete_powder",
+    "{}_terracotta",
+    "{}_glazed_terracotta",
+    "{}_stained_glass",
+    "{}_stained_glass_pane",
+    "{}_bed",
+    "{}_banner",
+    "{}_wall_banner",
+    "{}_candle",
+    "{}_candle_cake",
+    "{}_shulker_box",
+];
+
+/// 展开材质对，只保留两边都存在的方块，原 id -> 新 id
+/// 所有材质对一次性映射，互换（白 -> 黑、黑 -> 白）不会被后一条覆盖回去
+pub fn theme_mappings(pairs: &[ThemePair], je_blocks: &BlocksData) -> HashMap<String, String> {
+    let mut mappings = HashMap::new();
+    for pair in pairs {
+        let from = pair.from.trim().trim_start_matches("minecraft:");
+        let to = pair.to.trim().trim_start_matches("minecraft:");
+        if from.is_empty() || to.is_empty() || from == to {
+            continue;
+        }
+        for form in FAMILY_FORMS {
+            let original = form.replace("{}", from);
+            let replacement = form.replace("{}", to);
+            if je_blocks.contains(&original) && je_blocks.contains(&replacement) {
+                mappings
+                    .entry(format!("minecraft:{}", original))
+                    .or_insert_with(|| format!("minecraft:{}", replacement));
+            }
+        }
+    }
+    mappings
+}
+
+// 材质的整方块形态，用来从楼梯、台阶等形状找回基础方块
+pub const BASE_FORMS: [&str; 5] = ["{}", "{}s", "{}_planks", "{}_block", "{}_wool"];
+
+// 非整方块的形状，长的在前，避免 oak_wall_sign 被拆成 oak_wall + _sign
+pub const SHAPE_FORMS: [&str; 17] = [
+    "{}_wall_hanging_sign",
+    "{}_pressure_plate",
+    "{}_hanging_sign",
+    "{}_wall_banner",
+    "{}_fence_gate",
+    "{}_wall_sign",
+    "{}_trapdoor",
+    "{}_button",
+    "{}_stairs",
+    "{}_banner",
+    "{}_carpet",
+    "{}_candle",
+    "{}_fence",
+    "{}_slab",
+    "{}_wall",
+    "{}_door",
+    "{}_sign",
+];
+
+/// 按形态拆出材质名，例如 ("{}_stairs", "oak_stairs") -> "oak"
+pub fn form_stem(form: &str, name: &str) -> Option<String> {
+    let (prefix, suffix) = form.split_once("{}")?;
+    let stem = name.strip_prefix(prefix)?.strip_suffix(suffix)?;
+    (!stem.is_empty()).then(|| stem.to_string())
+}
+
+/// 材质的基础整方块，按 BASE_FORMS 顺序取第一个存在的
+pub fn base_block(stem: &str, je_blocks: &BlocksData) -> Option<String> {
+    BASE_FORMS
+        .iter()
+        .map(|form| form.replace("{}", stem))
+        .find(|name| je_blocks.contains(name))
+}
+
+/// 识别方块的形状，要求能找回基础方块，返回 (形状, 材质名)
+pub fn block_shape(name: &str, je_blocks: &BlocksData) -> Option<(&'static str, String)> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    SHAPE_FORMS.iter().find_map(|form| {
+        let stem = form_stem(form, name)?;
+        base_block(&stem, je_blocks).map(|_| (*form, stem))
+    })
+}
+
+/// 整方块在某个形状下的同族方块，例如 (deepslate_bricks, "{}_stairs") -> deepslate_brick_stairs
+pub fn shape_equivalent(base: &str, form: &str, je_blocks: &BlocksData) -> Option<String> {
+    let base = base.strip_prefix("minecraft:").unwrap_or(base);
+    BASE_FORMS
+        .iter()
+        .filter_map(|base_form| form_stem(base_form, base))
+        .map(|stem| form.replace("{}", &stem))
+        .find(|name| je_blocks.contains(name))
+}
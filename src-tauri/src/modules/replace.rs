@@ -1,238 +1,354 @@
-use crate::building_gadges::to_bg_schematic::ToBgSchematic;
-use crate::create::to_create_schematic::ToCreateSchematic;
 use crate::data_files::files::FileManager;
-use crate::database::db_apis::history_api::new_history;
-use crate::database::db_apis::schematic_data_api::new_schematic_data;
-use crate::database::db_apis::schematics_api::{find_schematic, new_schematic};
+use crate::database::db_apis::history_api::{new_history, update_history};
+use crate::database::db_apis::schematic_data_api::{new_schematic_data, update_schematic_data};
+use crate::database::db_apis::schematics_api::{
+    find_schematic, get_schematic_version, new_schematic, update_schematic,
+};
+use crate::database::db_apis::theme_api::find_theme_preset;
 use crate::database::db_apis::user_api::add_user_schematic;
 use crate::database::db_control::DatabaseState;
-use crate::litematica::to_lm_schematic::ToLmSchematic;
+use crate::database::db_data::Schematic;
 use crate::modules::modules_data::convert_data::get_unique_block_str;
-use crate::modules::modules_data::replace_data::{ReplacementRule, RuleMatcher};
-use crate::utils::block_state_pos_list::{BlockData, BlockId};
+use crate::modules::modules_data::extract_data::{extract, ExtractOptions, ExtractStats};
+use crate::modules::modules_data::recolor_data::{recolor, RecolorReport};
+use crate::modules::modules_data::replace_data::{
+    CompiledRule, ReplacementResult, ReplacementRule, RequirementDelta, RuleStats, SAMPLE_POSITIONS,
+};
+use crate::modules::modules_data::theme_data::theme_mappings;
+use crate::modules::thumbnail::try_generate_thumbnail;
+use crate::render::block_colors::BlockColors;
+use crate::utils::block_state_pos_list::BlockId;
+use crate::utils::minecraft_data::block_tags::BlockTags;
 use crate::utils::minecraft_data::je_blocks_data::BlocksData;
-use crate::utils::requirements::{get_requirements, RequirementStr};
-use crate::word_edit::to_we_schematic::ToWeSchematic;
+use crate::utils::minecraft_data::map_art_data::MapArtsData;
+use crate::utils::requirements::{get_requirements, RequirementStr, Requirements};
+use crate::utils::schematic_data::SchematicData;
+use anyhow::Result;
+use r2d2::PooledConnection;
+use r2d2_sqlite::SqliteConnectionManager;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::State;
-use crate::be_schematic::to_be_schematic::ToBESchematic;
 
+/// 按顺序执行规则，后面的规则能看到前面规则替换后的结果
+pub fn apply_rules(
+    data: &mut SchematicData,
+    rules: &[ReplacementRule],
+    tags: &BlockTags,
+) -> Result<Vec<RuleStats>> {
+    let compiled = rules
+        .iter()
+        .map(|rule| CompiledRule::new(rule, tags))
+        .collect::<Result<Vec<_>>>()?;
+    let mut stats = Vec::with_capacity(compiled.len());
+    for rule in &compiled {
+        let elements = &data.blocks.elements;
+        let mut candidates: Vec<usize> = (0..elements.len())
+            .into_par_iter()
+            .filter(|&i| rule.in_scope(&elements[i].pos) && rule.matcher.matches(&elements[i].block))
+            .collect();
+        let matched = candidates.len();
+        let mut rng = StdRng::seed_from_u64(rule.seed);
+        if let Some(p) = rule.percentage {
+            candidates.retain(|_| rng.gen_bool(p / 100.0));
+        }
+        if let Some(limit) = rule.limit {
+            if candidates.len() > limit {
+                let mut picked: Vec<usize> = rand::seq::index::sample(&mut rng, candidates.len(), limit)
+                    .into_iter()
+                    .map(|i| candidates[i])
+                    .collect();
+                picked.sort_unstable();
+                candidates = picked;
+            }
+        }
+        let mut samples = Vec::new();
+        for &i in &candidates {
+            let element = &mut data.blocks.elements[i];
+            let new_block = rule.replace(&element.block, &mut rng);
+            *Arc::make_mut(&mut element.block) = new_block;
+            if samples.len() < SAMPLE_POSITIONS {
+                samples.push(element.pos);
+            }
+        }
+        stats.push(RuleStats { matched, replaced: candidates.len(), samples });
+    }
+    Ok(stats)
+}
+
+/// 替换结果另存为新蓝图，沿用原蓝图的格式与元数据
+fn save_replaced(
+    conn: &mut PooledConnection<SqliteConnectionManager>,
+    file_manager: &FileManager,
+    je_blocks: &BlocksData,
+    schematic: &Schematic,
+    data: &SchematicData,
+) -> Result<i64> {
+    let requirement = get_requirements(&data.blocks)?;
+    let requirements_str =
+        RequirementStr::from_requirements(&requirement, je_blocks).export_to_string()?;
+    let unique_blocks = get_unique_block_str(&data.blocks)?;
+    let new_id = new_schematic(conn, schematic.clone())?;
+    new_schematic_data(conn, new_id, requirements_str.clone(), unique_blocks.clone())?;
+    add_user_schematic(conn, 1)?;
+    let schematic_str = serde_json::to_string(schematic)?;
+    new_history(conn, new_id, schematic_str, requirements_str, unique_blocks)?;
+    file_manager.save_schematic_value(
+        new_id,
+        data,
+        0,
+        schematic.sub_type,
+        schematic.schematic_type,
+    )?;
+    Ok(new_id)
+}
+
+/// 覆盖原蓝图，版本号加一，旧版本仍保留在历史里
+fn overwrite_replaced(
+    conn: &mut PooledConnection<SqliteConnectionManager>,
+    file_manager: &FileManager,
+    je_blocks: &BlocksData,
+    block_colors: &BlockColors,
+    schematic: &mut Schematic,
+    data: &SchematicData,
+) -> Result<i32> {
+    let requirement = get_requirements(&data.blocks)?;
+    let requirements_str =
+        RequirementStr::from_requirements(&requirement, je_blocks).export_to_string()?;
+    let unique_blocks = get_unique_block_str(&data.blocks)?;
+    let version = get_schematic_version(conn, schematic.id)?;
+    schematic.version = version + 1;
+    schematic.sizes = data.size.to_string();
+    update_schematic(conn, schematic.clone())?;
+    update_schematic_data(conn, schematic.id, requirements_str.clone(), unique_blocks.clone())?;
+    let schematic_str = serde_json::to_string(schematic)?;
+    update_history(conn, schematic.id, schematic_str, requirements_str, unique_blocks)?;
+    file_manager.save_schematic_value(
+        schematic.id,
+        data,
+        version + 1,
+        schematic.sub_type,
+        schematic.schematic_type,
+    )?;
+    try_generate_thumbnail(file_manager, block_colors, schematic.id, version + 1, data);
+    Ok(version + 1)
+}
+
+fn requirement_delta(
+    before: &Requirements,
+    after: &Requirements,
+    je_blocks: &BlocksData,
+) -> Vec<RequirementDelta> {
+    let (before, after) = (before.get_requirements(), after.get_requirements());
+    let mut ids: Vec<&BlockId> = before.keys().chain(after.keys()).collect();
+    ids.sort_by(|a, b| a.name.cmp(&b.name));
+    ids.dedup();
+    ids.into_iter()
+        .filter_map(|id| {
+            let b = before.get(id).copied().unwrap_or(0);
+            let a = after.get(id).copied().unwrap_or(0);
+            if a == b {
+                return None;
+            }
+            let name = id.name.strip_prefix("minecraft:").unwrap_or(&id.name);
+            Some(RequirementDelta {
+                id: id.name.to_string(),
+                zh_cn: je_blocks.get_zh_cn(name).unwrap_or(name).to_string(),
+                before: b,
+                after: a,
+                delta: a - b,
+            })
+        })
+        .collect()
+}
+
+/// 按材质映射一次性换 id，属性不变，返回替换的方块数
+pub fn apply_theme(data: &mut SchematicData, mappings: &HashMap<String, String>) -> usize {
+    data.blocks
+        .elements
+        .par_iter_mut()
+        .map(|block| match mappings.get(block.block.id.name.as_ref()) {
+            Some(replacement) => {
+                Arc::make_mut(&mut block.block).id = BlockId {
+                    name: Arc::from(replacement.as_str()),
+                };
+                1
+            }
+            None => 0,
+        })
+        .sum()
+}
+
+/// dry_run 只返回统计不保存；overwrite 覆盖原蓝图生成新版本，否则另存一份
 #[tauri::command]
 pub async fn schematic_replacement(
     rules: Vec<ReplacementRule>,
+    dry_run: Option<bool>,
+    overwrite: Option<bool>,
     db: State<'_, DatabaseState>,
     je_blocks: State<'_, BlocksData>,
+    block_tags: State<'_, BlockTags>,
+    block_colors: State<'_, BlockColors>,
     file_manager: State<'_, FileManager>,
-) -> Result<bool, String> {
+) -> Result<ReplacementResult, String> {
     async move {
-        let schematic_id = rules[0].schematic_id.clone();
+        let schematic_id = rules
+            .first()
+            .ok_or(anyhow::anyhow!("no replacement rules"))?
+            .schematic_id;
+        let dry_run = dry_run.unwrap_or(false);
         let mut conn = db.0.get()?;
         let mut schematic = find_schematic(&mut conn, schematic_id)?;
-        let version = schematic.version;
-        let sub_version = schematic.sub_type;
-        let v_type = schematic.schematic_type;
-        let mut data =
-            file_manager.get_schematic_data(schematic_id, version, sub_version, v_type)?;
-        let mut rule_cache = Vec::with_capacity(rules.len());
-        for rule in &rules {
-            let matcher = match rule.mode {
-                0 => {
-                    let original = rule
-                        .original_id
-                        .as_ref()
-                        .ok_or(anyhow::anyhow!("unknow original"))?;
-                    let replacement = rule
-                        .replacement_id
-                        .as_ref()
-                        .ok_or(anyhow::anyhow!("unknow replacement"))?;
-                    RuleMatcher::IdMatch {
-                        original: original.clone(),
-                        replacement: replacement.clone(),
-                    }
-                }
-                1 => {
-                    let original = rule
-                        .original_details
-                        .as_ref()
-                        .ok_or(anyhow::anyhow!("unknow original"))?;
-                    let replacement = rule
-                        .replacement_details
-                        .as_ref()
-                        .ok_or(anyhow::anyhow!("unknow replacement"))?;
-                    RuleMatcher::FullMatch {
-                        original: original.clone(),
-                        replacement: replacement.clone(),
-                    }
-                }
-                _ => return Err(anyhow::anyhow!("unknow type: {}", rule.mode)),
-            };
-            rule_cache.push((matcher, rule.global));
+        let mut data = file_manager.get_schematic_data(
+            schematic_id,
+            schematic.version,
+            schematic.sub_type,
+            schematic.schematic_type,
+        )?;
+        let before = get_requirements(&data.blocks)?;
+        let stats = apply_rules(&mut data, &rules, &block_tags)?;
+        let after = get_requirements(&data.blocks)?;
+        let delta = requirement_delta(&before, &after, &je_blocks);
+        let (id, version) = if dry_run {
+            (None, schematic.version)
+        } else if overwrite.unwrap_or(false) {
+            let version = overwrite_replaced(
+                &mut conn,
+                &file_manager,
+                &je_blocks,
+                &block_colors,
+                &mut schematic,
+                &data,
+            )?;
+            (Some(schematic_id), version)
+        } else {
+            schematic.name = format!("replace_schematic_{}", schematic_id);
+            let id = save_replaced(&mut conn, &file_manager, &je_blocks, &schematic, &data)?;
+            // 新蓝图从版本 0 开始，与 save_replaced 写入的文件一致
+            (Some(id), 0)
+        };
+        Ok(ReplacementResult { id, version, dry_run, rules: stats, delta })
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// 把主题预设套用到一个或多个蓝图，每个蓝图另存一份，返回新蓝图 id
+#[tauri::command]
+pub async fn apply_theme_preset(
+    db: State<'_, DatabaseState>,
+    je_blocks: State<'_, BlocksData>,
+    file_manager: State<'_, FileManager>,
+    preset_id: i64,
+    schematic_ids: Vec<i64>,
+) -> Result<Vec<i64>, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let preset = find_theme_preset(&mut conn, preset_id)?;
+        let mappings = theme_mappings(&preset.pairs, &je_blocks);
+        if mappings.is_empty() {
+            anyhow::bail!("theme preset {} maps no blocks", preset.name);
         }
+        let mut new_ids = Vec::with_capacity(schematic_ids.len());
+        for schematic_id in schematic_ids {
+            let mut schematic = find_schematic(&mut conn, schematic_id)?;
+            let mut data = file_manager.get_schematic_data(
+                schematic_id,
+                schematic.version,
+                schematic.sub_type,
+                schematic.schematic_type,
+            )?;
+            apply_theme(&mut data, &mappings);
+            schematic.name = format!("{}_{}", schematic.name, preset.name);
+            new_ids.push(save_replaced(&mut conn, &file_manager, &je_blocks, &schematic, &data)?);
+        }
+        Ok(new_ids)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
 
-        data.blocks.elements.par_iter_mut().for_each(|block| {
-            for (matcher, global) in &rule_cache {
-                let matches = match matcher {
-                    RuleMatcher::IdMatch { original, .. } => {
-                        block.block.id.name.as_ref() == *original
-                    }
-                    RuleMatcher::FullMatch { original, .. } => {
-                        block.block.id == original.id
-                            && block.block.properties.len() == original.properties.len()
-                            && block.block.properties.iter().all(|(k, v)| {
-                                original
-                                    .properties
-                                    .get(k.as_ref())
-                                    .map(|ov| ov.as_ref() == v.as_ref())
-                                    .unwrap_or(false)
-                            })
-                    }
-                };
-                if matches {
-                    let new_block = match matcher {
-                        RuleMatcher::IdMatch { replacement, .. } => BlockData {
-                            id: BlockId {
-                                name: Arc::from(replacement.as_str()),
-                            },
-                            properties: block.block.properties.clone(),
-                        },
-                        RuleMatcher::FullMatch { replacement, .. } => replacement.clone(),
-                    };
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecolorResult {
+    pub id: i64,
+    #[serde(flatten)]
+    pub report: RecolorReport,
+}
 
-                    let old = Arc::make_mut(&mut block.block);
-                    *old = new_block;
-                }
-            }
-        });
-        match v_type {
-            1 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToCreateSchematic::new(&data)?.create_schematic(true);
-                schematic.name = format!("replace_schematic_{}", schematic_id);
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_nbt_value(new_id, data, 0, sub_version, v_type, true)?;
-            }
-            2 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToLmSchematic::new(&data)?.lm_schematic(6);
-                schematic.name = format!("replace_schematic_{}", schematic_id);
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_nbt_value(new_id, data, 0, sub_version, v_type, true)?;
-            }
-            3 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToWeSchematic::new(&data)?.we_schematic(sub_version)?;
-                schematic.name = format!("replace_schematic_{}", schematic_id);
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_nbt_value(new_id, data, 0, sub_version, v_type, true)?;
-            }
-            4 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToBgSchematic::new(&data)?.bg_schematic(sub_version)?;
-                schematic.name = format!("replace_schematic_{}", schematic_id);
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_json_value(new_id, data, 0, sub_version, v_type)?;
-            }
-            5 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToBESchematic::new(&data)?.to_be_value();
-                schematic.name = format!("replace_schematic_{}", schematic_id);
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_nbt_le_value(new_id, data, 0, sub_version, v_type)?;
+/// 限定调色板重新配色，palette 可以写方块 id 或 #标签
+#[tauri::command]
+pub async fn schematic_recolor(
+    db: State<'_, DatabaseState>,
+    je_blocks: State<'_, BlocksData>,
+    block_tags: State<'_, BlockTags>,
+    map_arts: State<'_, MapArtsData>,
+    file_manager: State<'_, FileManager>,
+    schematic_id: i64,
+    palette: Vec<String>,
+) -> Result<RecolorResult, String> {
+    async move {
+        let mut allowed = Vec::new();
+        for entry in &palette {
+            if entry.starts_with('#') {
+                let ids = block_tags
+                    .get(entry)
+                    .ok_or(anyhow::anyhow!("unknown block tag: {}", entry))?;
+                allowed.extend(ids.iter().cloned());
+            } else {
+                allowed.push(entry.clone());
             }
-            _ => return Err(anyhow::anyhow!("unknow type: {}", v_type)),
         }
-        Ok(true)
+        let mut conn = db.0.get()?;
+        let mut schematic = find_schematic(&mut conn, schematic_id)?;
+        let mut data = file_manager.get_schematic_data(
+            schematic_id,
+            schematic.version,
+            schematic.sub_type,
+            schematic.schematic_type,
+        )?;
+        let report = recolor(&mut data, &map_arts, &je_blocks, &allowed)?;
+        schematic.name = format!("recolor_schematic_{}", schematic_id);
+        let id = save_replaced(&mut conn, &file_manager, &je_blocks, &schematic, &data)?;
+        Ok(RecolorResult { id, report })
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractResult {
+    pub id: i64,
+    #[serde(flatten)]
+    pub stats: ExtractStats,
+}
+
+/// 按条件提取或剔除方块与实体，结果另存为新蓝图
+#[tauri::command]
+pub async fn schematic_extract(
+    db: State<'_, DatabaseState>,
+    je_blocks: State<'_, BlocksData>,
+    block_tags: State<'_, BlockTags>,
+    file_manager: State<'_, FileManager>,
+    schematic_id: i64,
+    options: ExtractOptions,
+) -> Result<ExtractResult, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let mut schematic = find_schematic(&mut conn, schematic_id)?;
+        let mut data = file_manager.get_schematic_data(
+            schematic_id,
+            schematic.version,
+            schematic.sub_type,
+            schematic.schematic_type,
+        )?;
+        let stats = extract(&mut data, &options, &block_tags)?;
+        schematic.name = format!("extract_schematic_{}", schematic_id);
+        let id = save_replaced(&mut conn, &file_manager, &je_blocks, &schematic, &data)?;
+        Ok(ExtractResult { id, stats })
     }
     .await
     .map_err(|e: anyhow::Error| e.to_string())
@@ -20,6 +20,11 @@ use fastnbt::Value;
 use std::path::Path;
 use tauri::State;
 use crate::be_schematic::be_schematic::BESchematic;
+use crate::mcfunction::mcfunction_schematic::McFunctionSchematic;
+use crate::modules::thumbnail::try_generate_thumbnail;
+use crate::render::block_colors::BlockColors;
+use r2d2::PooledConnection;
+use r2d2_sqlite::SqliteConnectionManager;
 
 #[tauri::command]
 pub async fn encode_uploaded_schematic(
@@ -27,6 +32,7 @@ pub async fn encode_uploaded_schematic(
     file_manager: State<'_, FileManager>,
     versions_data: State<'_, VersionData>,
     je_blocks: State<'_, BlocksData>,
+    block_colors: State<'_, BlockColors>,
     file_name: String,
     data: Vec<u8>,
     update: bool,
@@ -128,7 +134,8 @@ pub async fn encode_uploaded_schematic(
                         -1,
                         1,
                         file_ext_str,
-                    )?
+                    )?;
+                    try_generate_thumbnail(&file_manager, &block_colors, schematic_id, version + 1, &schematic_data);
                 } else {
                     let schematic_id = new_schematic(&mut conn, schematic.clone())?;
                     new_schematic_data(
@@ -153,7 +160,8 @@ pub async fn encode_uploaded_schematic(
                         -1,
                         1,
                         file_ext_str,
-                    )?
+                    )?;
+                    try_generate_thumbnail(&file_manager, &block_colors, schematic_id, 0, &schematic_data);
                 }
             }
             "json" => {
@@ -215,7 +223,8 @@ pub async fn encode_uploaded_schematic(
                         schematic_type,
                         4,
                         file_ext_str,
-                    )?
+                    )?;
+                    try_generate_thumbnail(&file_manager, &block_colors, schematic_id, version + 1, &schematic_data);
                 } else {
                     let schematic_id = new_schematic(&mut conn, schematic.clone())?;
                     new_schematic_data(
@@ -240,7 +249,8 @@ pub async fn encode_uploaded_schematic(
                         schematic_type,
                         4,
                         file_ext_str,
-                    )?
+                    )?;
+                    try_generate_thumbnail(&file_manager, &block_colors, schematic_id, 0, &schematic_data);
                 }
             }
             "schem" => {
@@ -306,7 +316,8 @@ pub async fn encode_uploaded_schematic(
                         type_version,
                         3,
                         file_ext_str,
-                    )?
+                    )?;
+                    try_generate_thumbnail(&file_manager, &block_colors, schematic_id, version + 1, &schematic_data);
                 } else {
                     let schematic_id = new_schematic(&mut conn, schematic.clone())?;
                     new_schematic_data(
@@ -331,7 +342,8 @@ pub async fn encode_uploaded_schematic(
                         type_version,
                         3,
                         file_ext_str,
-                    )?
+                    )?;
+                    try_generate_thumbnail(&file_manager, &block_colors, schematic_id, 0, &schematic_data);
                 }
             }
             "litematic" => {
@@ -403,7 +415,8 @@ pub async fn encode_uploaded_schematic(
                         -1,
                         2,
                         file_ext_str,
-                    )?
+                    )?;
+                    try_generate_thumbnail(&file_manager, &block_colors, schematic_id, version + 1, &schematic_data);
                 } else {
                     let schematic_id = new_schematic(&mut conn, schematic.clone())?;
                     new_schematic_data(
@@ -428,7 +441,8 @@ pub async fn encode_uploaded_schematic(
                         -1,
                         2,
                         file_ext_str,
-                    )?
+                    )?;
+                    try_generate_thumbnail(&file_manager, &block_colors, schematic_id, 0, &schematic_data);
                 }
             }
             "mcstructure" => {
@@ -488,7 +502,8 @@ pub async fn encode_uploaded_schematic(
                         -1,
                         5,
                         file_ext_str,
-                    )?
+                    )?;
+                    try_generate_thumbnail(&file_manager, &block_colors, schematic_id, version + 1, &schematic_data);
                 } else {
                     let schematic_id = new_schematic(&mut conn, schematic.clone())?;
                     new_schematic_data(
@@ -513,7 +528,8 @@ pub async fn encode_uploaded_schematic(
                         -1,
                         5,
                         file_ext_str,
-                    )?
+                    )?;
+                    try_generate_thumbnail(&file_manager, &block_colors, schematic_id, 0, &schematic_data);
                 }
             }
             _ => {
@@ -564,7 +580,7 @@ pub async fn encode_uploaded_schematic(
                         -1,
                         -1,
                         file_ext_str,
-                    )?
+                    )?;
                 } else {
                     let schematic_id = new_schematic(&mut conn, schematic.clone())?;
                     new_schematic_data(
@@ -589,7 +605,7 @@ pub async fn encode_uploaded_schematic(
                         -1,
                         -1,
                         file_ext_str,
-                    )?
+                    )?;
                 }
             }
         };
@@ -738,4 +754,99 @@ pub async fn save_snbt(
     }
         .await
         .map_err(|e: anyhow::Error| e.to_string())
-}
\ No newline at end of file
+}
+pub fn save_schematic_entry(
+    conn: &mut PooledConnection<SqliteConnectionManager>,
+    file_manager: &FileManager,
+    je_blocks: &BlocksData,
+    block_colors: &BlockColors,
+    data: &SchematicData,
+    name: String,
+    schematic_type: i32,
+    sub_version: i32,
+) -> Result<i64> {
+    let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let sub_type = match schematic_type {
+        3 | 4 => sub_version,
+        _ => -1,
+    };
+    let requirement = get_requirements(&data.blocks)?;
+    let requirements_str = RequirementStr::from_requirements(&requirement, je_blocks)
+        .export_to_string()?;
+    let unique_blocks = get_unique_block_str(&data.blocks)?;
+    let schematic = Schematic {
+        id: 0,
+        name,
+        description: "".parse()?,
+        schematic_type,
+        sub_type,
+        is_deleted: false,
+        sizes: data.size.to_string(),
+        user: "your".parse()?,
+        is_upload: false,
+        version: 0,
+        version_list: "0".parse()?,
+        created_at: "".parse()?,
+        schematic_tags: "".to_string(),
+        classification: "".to_string(),
+        updated_at: now,
+        game_version: "".parse()?,
+        lm_version: if schematic_type == 2 { 6 } else { 0 },
+    };
+    let new_id = new_schematic(conn, schematic.clone())?;
+    new_schematic_data(
+        conn,
+        new_id,
+        requirements_str.clone(),
+        unique_blocks.clone(),
+    )?;
+    add_user_schematic(conn, 1)?;
+    let schematic_str = serde_json::to_string(&schematic)?;
+    new_history(
+        conn,
+        new_id,
+        schematic_str,
+        requirements_str,
+        unique_blocks,
+    )?;
+    file_manager.save_schematic_value(new_id, data, 0, sub_type, schematic_type)?;
+    try_generate_thumbnail(file_manager, block_colors, new_id, 0, data);
+    Ok(new_id)
+}
+
+#[tauri::command]
+pub async fn import_mcfunction(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    je_blocks: State<'_, BlocksData>,
+    block_colors: State<'_, BlockColors>,
+    file_name: String,
+    content: String,
+    schematic_type: i64,
+    sub_version: i64,
+) -> Result<i64, String> {
+    async move {
+        let schematic = McFunctionSchematic::new_from_str(&content);
+        let mut data = schematic.get_blocks_pos()?;
+        data.tile_entities_list.original_type = schematic_type as i32;
+        let mut conn = db.0.get()?;
+        let name = Path::new(&file_name)
+            .file_stem()
+            .and_then(|n| n.to_str())
+            .unwrap_or("mcfunction")
+            .to_string();
+        let new_id = save_schematic_entry(
+            &mut conn,
+            &file_manager,
+            &je_blocks,
+            &block_colors,
+            &data,
+            name,
+            schematic_type as i32,
+            sub_version as i32,
+        )?;
+        Ok(new_id)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
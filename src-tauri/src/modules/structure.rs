@@ -0,0 +1,117 @@
+use crate::create::create_schematic::CreateSchematic;
+use crate::create::to_create_schematic::ToCreateSchematic;
+use crate::data_files::files::FileManager;
+use crate::database::db_apis::schematics_api::find_schematic;
+use crate::database::db_control::DatabaseState;
+use crate::modules::schematic::save_schematic_entry;
+use crate::render::block_colors::BlockColors;
+use crate::utils::extend_write::{to_bytes_gzip, write_zip};
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::word_edit::to_we_schematic::ToWeSchematic;
+use anyhow::bail;
+use serde_json::json;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_structure_palettes(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    id: i64,
+) -> Result<Vec<Vec<String>>, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        if schematic.schematic_type != 1 {
+            return Ok(vec![]);
+        }
+        let nbt = file_manager.get_schematic_value(id, schematic.version, schematic.sub_type, 1)?;
+        let structure = CreateSchematic { nbt };
+        let mut variants = Vec::new();
+        for index in 0..structure.palette_count() {
+            let palette = structure.parse_palette(index)?;
+            variants.push(palette.iter().map(|b| ToWeSchematic::block_to_string(b)).collect());
+        }
+        Ok(variants)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+#[tauri::command]
+pub async fn apply_structure_palette(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    je_blocks: State<'_, BlocksData>,
+    block_colors: State<'_, BlockColors>,
+    id: i64,
+    palette_index: i64,
+) -> Result<i64, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        if schematic.schematic_type != 1 {
+            bail!("schematic {} is not a structure file", id);
+        }
+        let nbt = file_manager.get_schematic_value(id, schematic.version, schematic.sub_type, 1)?;
+        let structure = CreateSchematic { nbt };
+        let data = structure.get_blocks_pos_variant(palette_index as usize)?;
+        let name = format!("{}_palette_{}", schematic.name, palette_index);
+        let new_id = save_schematic_entry(
+            &mut conn,
+            &file_manager,
+            &je_blocks,
+            &block_colors,
+            &data,
+            name,
+            1,
+            -1,
+        )?;
+        Ok(new_id)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+#[tauri::command]
+pub async fn export_structure_tiles(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    id: i64,
+    target: String,
+    vi_air: bool,
+) -> Result<usize, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        let data = file_manager.get_schematic_data(
+            id,
+            schematic.version,
+            schematic.sub_type,
+            schematic.schematic_type,
+        )?;
+        let tiles = ToCreateSchematic::create_tiles(&data, vi_air)?;
+
+        let mut files = Vec::with_capacity(tiles.len() + 1);
+        let mut pieces = Vec::with_capacity(tiles.len());
+        for tile in &tiles {
+            let file = format!("tile_{}_{}_{}.nbt", tile.index.0, tile.index.1, tile.index.2);
+            pieces.push(json!({
+                "file": file,
+                "index": [tile.index.0, tile.index.1, tile.index.2],
+                "offset": [tile.offset.x, tile.offset.y, tile.offset.z],
+                "size": [tile.size.width, tile.size.height, tile.size.length],
+            }));
+            files.push((file, to_bytes_gzip(&tile.nbt)?));
+        }
+        let manifest = json!({
+            "name": schematic.name,
+            "pieces": pieces,
+        });
+        files.push(("manifest.json".to_string(), serde_json::to_vec_pretty(&manifest)?));
+
+        write_zip(&files, &target, &format!("schematic_{}_tiles.zip", id))?;
+        Ok(tiles.len())
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
@@ -0,0 +1,66 @@
+use crate::data_files::files::FileManager;
+use crate::database::db_apis::schematics_api::find_schematic;
+use crate::database::db_control::DatabaseState;
+use crate::render::block_colors::BlockColors;
+use crate::render::isometric::{render_png, THUMBNAIL_SIZE};
+use crate::utils::schematic_data::SchematicData;
+use anyhow::Result;
+use base64::Engine;
+use tauri::State;
+
+pub fn generate_thumbnail(
+    file_manager: &FileManager,
+    block_colors: &BlockColors,
+    id: i64,
+    version: i32,
+    data: &SchematicData,
+) -> Result<Vec<u8>> {
+    let png = render_png(data, block_colors, THUMBNAIL_SIZE)?;
+    file_manager.save_thumbnail(id, version, &png)?;
+    Ok(png)
+}
+
+/// 保存蓝图时顺带生成，失败只记录，缺的缩略图会在首次请求时补上
+pub fn try_generate_thumbnail(
+    file_manager: &FileManager,
+    block_colors: &BlockColors,
+    id: i64,
+    version: i32,
+    data: &SchematicData,
+) {
+    if let Err(e) = generate_thumbnail(file_manager, block_colors, id, version, data) {
+        eprintln!("thumbnail for schematic {} v{} failed: {}", id, version, e);
+    }
+}
+
+#[tauri::command]
+pub async fn get_schematic_thumbnail(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    block_colors: State<'_, BlockColors>,
+    id: i64,
+    version: i32,
+) -> Result<String, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        let version = if version < 0 { schematic.version } else { version };
+        // 旧数据或其他途径生成的版本没有缩略图，首次请求时补生成
+        let png = match file_manager.read_thumbnail(id, version)? {
+            Some(png) => png,
+            None => {
+                let data = file_manager.get_schematic_data(
+                    id,
+                    version,
+                    schematic.sub_type,
+                    schematic.schematic_type,
+                )?;
+                generate_thumbnail(&file_manager, &block_colors, id, version, &data)?
+            }
+        };
+        let encoded = base64::engine::general_purpose::STANDARD.encode(png);
+        Ok(format!("data:image/png;base64,{}", encoded))
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
@@ -0,0 +1,456 @@
+use crate::utils::block_state_pos_list::BlockData;
+use crate::utils::minecraft_data::map_art_data::MapArtsData;
+use anyhow::Result;
+use image::RgbaImage;
+use serde_json::Value as JsonValue;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+pub type Rgba = [u8; 4];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaceColors {
+    pub top: Rgba,
+    pub side: Rgba,
+}
+
+const GRASS_TINT: [u8; 3] = [0x91, 0xBD, 0x59];
+const FOLIAGE_TINT: [u8; 3] = [0x77, 0xAB, 0x2F];
+const SPRUCE_TINT: [u8; 3] = [0x61, 0x99, 0x61];
+const BIRCH_TINT: [u8; 3] = [0x80, 0xA7, 0x55];
+const WATER_TINT: [u8; 3] = [0x3F, 0x76, 0xE4];
+const REDSTONE_TINT: [u8; 3] = [0xC8, 0x1A, 0x0A];
+
+const TOP_KEYS: [&str; 8] = ["up", "top", "end", "all", "texture", "cross", "plant", "particle"];
+const SIDE_KEYS: [&str; 10] = [
+    "north", "south", "east", "side", "all", "texture", "cross", "plant", "front", "particle",
+];
+
+/// 方块颜色来源：内置材质图集的平均色，缺失时退回地图画数据的平均色
+#[derive(Debug, Default)]
+pub struct BlockColors {
+    atlas: RgbaImage,
+    regions: HashMap<String, [u32; 4]>,
+    textures: HashMap<String, Rgba>,
+    definitions: HashMap<String, JsonValue>,
+    models: HashMap<String, JsonValue>,
+    opaque: HashSet<String>,
+    fallback: HashMap<String, Rgba>,
+}
+
+impl BlockColors {
+    pub fn new(app: &AppHandle) -> Result<Self> {
+        let root = app
+            .path()
+            .resolve("data/resource/minecraft/assets", BaseDirectory::Resource)?;
+        let mut colors = Self::from_assets(&root)?;
+        if let Ok(map_arts) = MapArtsData::new(app) {
+            colors.add_fallback(&map_arts);
+        }
+        Ok(colors)
+    }
+
+    pub fn from_assets(root: &Path) -> Result<Self> {
+        let atlas = image::load_from_memory(&fs::read(root.join("atlas/atlas.png"))?)?.to_rgba8();
+        let regions: HashMap<String, [u32; 4]> =
+            serde_json::from_str(&fs::read_to_string(root.join("atlas/data.min.json"))?)?;
+
+        let textures = regions
+            .iter()
+            .map(|(name, &[x, y, w, h])| {
+                let (mut r, mut g, mut b, mut a, mut n) = (0u64, 0u64, 0u64, 0u64, 0u64);
+                for py in y..(y + h).min(atlas.height()) {
+                    for px in x..(x + w).min(atlas.width()) {
+                        let p = atlas.get_pixel(px, py).0;
+                        let alpha = p[3] as u64;
+                        r += p[0] as u64 * alpha;
+                        g += p[1] as u64 * alpha;
+                        b += p[2] as u64 * alpha;
+                        a += alpha;
+                        n += 1;
+                    }
+                }
+                let color = if a == 0 {
+                    [0, 0, 0, 0]
+                } else {
+                    [(r / a) as u8, (g / a) as u8, (b / a) as u8, (a / n.max(1)) as u8]
+                };
+                (name.clone(), color)
+            })
+            .collect();
+
+        let definitions =
+            serde_json::from_str(&fs::read_to_string(root.join("block_definition/data.min.json"))?)?;
+        let models = serde_json::from_str(&fs::read_to_string(root.join("model/data.min.json"))?)?;
+        let opaque: JsonValue =
+            serde_json::from_str(&fs::read_to_string(root.join("opaque/blocks.json"))?)?;
+        let opaque = opaque["opaque"]
+            .as_array()
+            .map(|list| {
+                list.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|s| strip_namespace(s).to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            atlas,
+            regions,
+            textures,
+            definitions,
+            models,
+            opaque,
+            fallback: HashMap::new(),
+        })
+    }
+
+    pub fn add_fallback(&mut self, map_arts: &MapArtsData) {
+        for blocks in map_arts.categories.values() {
+            for (name, color) in blocks {
+                if color.average.len() >= 3 {
+                    self.fallback.insert(
+                        strip_namespace(name).to_string(),
+                        [color.average[0], color.average[1], color.average[2], 255],
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn is_opaque(&self, name: &str) -> bool {
+        self.opaque.contains(strip_namespace(name))
+    }
+
+    pub fn is_invisible(name: &str) -> bool {
+        matches!(
+            strip_namespace(name),
+            "air" | "cave_air" | "void_air" | "structure_void" | "barrier" | "light" | "moving_piston"
+        )
+    }
+
+    fn model_for(&self, block: &BlockData) -> Option<String> {
+        let definition = self.definitions.get(strip_namespace(&block.id.name))?;
+        let first_model = |v: &JsonValue| -> Option<String> {
+            match v {
+                JsonValue::Array(list) => list.first()?.get("model")?.as_str().map(str::to_string),
+                other => other.get("model")?.as_str().map(str::to_string),
+            }
+        };
+        if let Some(JsonValue::Object(variants)) = definition.get("variants") {
+            let matched = variants.iter().find(|(key, _)| {
+                key.is_empty()
+                    || key.split(',').all(|cond| match cond.split_once('=') {
+                        Some((k, v)) => block.properties.get(k).map(|p| p.as_ref() == v).unwrap_or(true),
+                        None => true,
+                    })
+            });
+            return matched.or_else(|| variants.iter().next()).and_then(|(_, v)| first_model(v));
+        }
+        if let Some(JsonValue::Array(parts)) = definition.get("multipart") {
+            return parts
+                .iter()
+                .find(|p| p.get("when").is_none())
+                .or_else(|| parts.first())
+                .and_then(|p| p.get("apply"))
+                .and_then(first_model);
+        }
+        None
+    }
+
+    pub fn model_textures(&self, model: &str) -> HashMap<String, String> {
+        let mut chain = Vec::new();
+        let mut current = Some(strip_namespace(model).to_string());
+        while let Some(name) = current.take() {
+            if chain.len() > 16 {
+                break;
+            }
+            let Some(model) = self.models.get(&name) else {
+                break;
+            };
+            chain.push(model);
+            current = model
+                .get("parent")
+                .and_then(|p| p.as_str())
+                .map(|p| strip_namespace(p).to_string());
+        }
+
+        let mut textures: HashMap<String, String> = HashMap::new();
+        for model in chain.iter().rev() {
+            if let Some(JsonValue::Object(map)) = model.get("textures") {
+                for (k, v) in map {
+                    if let Some(v) = v.as_str() {
+                        textures.insert(k.clone(), v.to_string());
+                    }
+                }
+            }
+        }
+        let keys: Vec<String> = textures.keys().cloned().collect();
+        for key in keys {
+            let mut value = textures[&key].clone();
+            let mut depth = 0;
+            while let Some(reference) = value.strip_prefix('#') {
+                match textures.get(reference) {
+                    Some(next) if depth < 8 => {
+                        value = next.clone();
+                        depth += 1;
+                    }
+                    _ => break,
+                }
+            }
+            textures.insert(key, value);
+        }
+        textures
+    }
+
+    pub fn texture_tint(texture: &str) -> Option<[u8; 3]> {
+        let name = strip_namespace(texture).trim_start_matches("block/");
+        if name.starts_with("water") {
+            Some(WATER_TINT)
+        } else if name.starts_with("spruce_leaves") {
+            Some(SPRUCE_TINT)
+        } else if name.starts_with("birch_leaves") {
+            Some(BIRCH_TINT)
+        } else if name.ends_with("leaves")
+            && !name.starts_with("cherry")
+            && !name.contains("azalea")
+        {
+            Some(FOLIAGE_TINT)
+        } else if matches!(name, "vine" | "lily_pad") {
+            Some(FOLIAGE_TINT)
+        } else if matches!(
+            name,
+            "grass_block_top" | "grass_block_side_overlay" | "short_grass" | "grass" | "tall_grass_top" | "tall_grass_bottom"
+                | "fern" | "large_fern_top" | "large_fern_bottom" | "sugar_cane"
+        ) {
+            Some(GRASS_TINT)
+        } else if name.starts_with("redstone_dust") {
+            Some(REDSTONE_TINT)
+        } else {
+            None
+        }
+    }
+
+    fn texture_color(&self, texture: &str) -> Option<Rgba> {
+        let color = *self.textures.get(strip_namespace(texture))?;
+        if color[3] == 0 {
+            return None;
+        }
+        Some(apply_tint(color, Self::texture_tint(texture)))
+    }
+
+    pub fn atlas(&self) -> &RgbaImage {
+        &self.atlas
+    }
+
+    /// 需要群系着色的材质预先乘上默认色，供网格导出使用
+    pub fn tinted_atlas(&self) -> RgbaImage {
+        let mut atlas = self.atlas.clone();
+        for (name, &[x, y, w, h]) in &self.regions {
+            let Some(tint) = Self::texture_tint(name) else {
+                continue;
+            };
+            for py in y..(y + h).min(atlas.height()) {
+                for px in x..(x + w).min(atlas.width()) {
+                    let pixel = atlas.get_pixel_mut(px, py);
+                    pixel.0 = apply_tint(pixel.0, Some(tint));
+                }
+            }
+        }
+        atlas
+    }
+
+    /// 材质在图集中的区域，动画材质只取第一帧
+    pub fn texture_region(&self, texture: &str) -> Option<[u32; 4]> {
+        let [x, y, w, h] = *self.regions.get(strip_namespace(texture))?;
+        Some([x, y, w, h.min(w)])
+    }
+
+    /// 按方块状态选出要叠加的模型，multipart 会返回所有满足条件的部分
+    pub fn block_models(&self, block: &BlockData) -> Vec<AppliedModel> {
+        let Some(definition) = self.definitions.get(strip_namespace(&block.id.name)) else {
+            return Vec::new();
+        };
+        let matches = |key: &str, value: &str| {
+            block
+                .properties
+                .get(key)
+                .map(|p| value.split('|').any(|v| v == p.as_ref()))
+                .unwrap_or(false)
+        };
+        if let Some(JsonValue::Object(variants)) = definition.get("variants") {
+            let matched = variants.iter().find(|(key, _)| {
+                key.is_empty()
+                    || key.split(',').all(|cond| match cond.split_once('=') {
+                        Some((k, v)) => block.properties.get(k).map(|p| p.as_ref() == v).unwrap_or(true),
+                        None => true,
+                    })
+            });
+            return matched
+                .or_else(|| variants.iter().next())
+                .and_then(|(_, v)| AppliedModel::from_json(v))
+                .into_iter()
+                .collect();
+        }
+        if let Some(JsonValue::Array(parts)) = definition.get("multipart") {
+            fn check(when: &JsonValue, matches: &dyn Fn(&str, &str) -> bool) -> bool {
+                let Some(map) = when.as_object() else {
+                    return true;
+                };
+                if let Some(JsonValue::Array(list)) = map.get("OR") {
+                    return list.iter().any(|w| check(w, matches));
+                }
+                if let Some(JsonValue::Array(list)) = map.get("AND") {
+                    return list.iter().all(|w| check(w, matches));
+                }
+                map.iter().all(|(k, v)| match v {
+                    JsonValue::String(v) => matches(k, v),
+                    other => matches(k, &other.to_string()),
+                })
+            }
+            return parts
+                .iter()
+                .filter(|p| p.get("when").map(|w| check(w, &matches)).unwrap_or(true))
+                .filter_map(|p| p.get("apply").and_then(AppliedModel::from_json))
+                .collect();
+        }
+        Vec::new()
+    }
+
+    /// 沿 parent 链找到最近一层定义的 elements
+    pub fn model_elements(&self, model: &str) -> Option<&Vec<JsonValue>> {
+        let mut current = Some(strip_namespace(model).to_string());
+        let mut depth = 0;
+        while let Some(name) = current.take() {
+            let model = self.models.get(&name)?;
+            if let Some(JsonValue::Array(elements)) = model.get("elements") {
+                return Some(elements);
+            }
+            depth += 1;
+            if depth > 16 {
+                break;
+            }
+            current = model
+                .get("parent")
+                .and_then(|p| p.as_str())
+                .map(|p| strip_namespace(p).to_string());
+        }
+        None
+    }
+
+    fn face_texture(&self, block: &BlockData, keys: &[&str]) -> Option<String> {
+        let model = self.model_for(block)?;
+        let textures = self.model_textures(&model);
+        keys.iter()
+            .filter_map(|k| textures.get(*k))
+            .find(|t| self.texture_color(t).is_some())
+            .map(|t| strip_namespace(t).to_string())
+    }
+
+    /// 代表方块颜色的材质名：与方块同名的材质优先，其次侧面、顶面
+    pub fn texture_name(&self, block: &BlockData) -> Option<String> {
+        let name = format!("block/{}", strip_namespace(&block.id.name));
+        if self.regions.contains_key(&name) {
+            return Some(name);
+        }
+        self.face_texture(block, &SIDE_KEYS)
+            .or_else(|| self.face_texture(block, &TOP_KEYS))
+    }
+
+    /// 顶面材质像素(已着色)，找不到材质时返回 None
+    pub fn top_texture(&self, block: &BlockData) -> Option<RgbaImage> {
+        if Self::is_invisible(&block.id.name) {
+            return None;
+        }
+        let texture = self
+            .face_texture(block, &TOP_KEYS)
+            .or_else(|| self.face_texture(block, &SIDE_KEYS))
+            .or_else(|| {
+                let name = format!("block/{}", strip_namespace(&block.id.name));
+                self.regions.contains_key(&name).then_some(name)
+            })?;
+        let [x, y, w, h] = self.texture_region(&texture)?;
+        let tint = Self::texture_tint(&texture);
+        let mut image = image::imageops::crop_imm(&self.atlas, x, y, w, h).to_image();
+        for pixel in image.pixels_mut() {
+            pixel.0 = apply_tint(pixel.0, tint);
+        }
+        Some(image)
+    }
+
+    pub fn face_colors(&self, block: &BlockData) -> Option<FaceColors> {
+        if Self::is_invisible(&block.id.name) {
+            return None;
+        }
+        let name = strip_namespace(&block.id.name);
+        if let Some(model) = self.model_for(block) {
+            let textures = self.model_textures(&model);
+            let pick = |keys: &[&str]| {
+                keys.iter()
+                    .filter_map(|k| textures.get(*k))
+                    .find_map(|t| self.texture_color(t))
+            };
+            let top = pick(&TOP_KEYS);
+            let side = pick(&SIDE_KEYS);
+            if let Some(color) = top.or(side) {
+                return Some(FaceColors {
+                    top: top.unwrap_or(color),
+                    side: side.unwrap_or(color),
+                });
+            }
+        }
+        if let Some(color) = self.texture_color(&format!("block/{}", name)) {
+            return Some(FaceColors { top: color, side: color });
+        }
+        if let Some(color) = self.fallback.get(name) {
+            return Some(FaceColors { top: *color, side: *color });
+        }
+        // 找不到材质时按名字生成稳定的灰度色
+        let hash = name.bytes().fold(0u32, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u32));
+        let v = 96 + (hash % 96) as u8;
+        Some(FaceColors { top: [v, v, v, 255], side: [v, v, v, 255] })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedModel {
+    pub model: String,
+    pub x: i32,
+    pub y: i32,
+    pub uvlock: bool,
+}
+
+impl AppliedModel {
+    fn from_json(value: &JsonValue) -> Option<Self> {
+        // 同一状态下的多个随机模型只取第一个
+        let value = match value {
+            JsonValue::Array(list) => list.first()?,
+            other => other,
+        };
+        Some(Self {
+            model: value.get("model")?.as_str()?.to_string(),
+            x: value.get("x").and_then(|v| v.as_i64()).unwrap_or(0) as i32,
+            y: value.get("y").and_then(|v| v.as_i64()).unwrap_or(0) as i32,
+            uvlock: value.get("uvlock").and_then(|v| v.as_bool()).unwrap_or(false),
+        })
+    }
+}
+
+pub fn apply_tint(color: Rgba, tint: Option<[u8; 3]>) -> Rgba {
+    match tint {
+        Some(t) => [
+            (color[0] as u16 * t[0] as u16 / 255) as u8,
+            (color[1] as u16 * t[1] as u16 / 255) as u8,
+            (color[2] as u16 * t[2] as u16 / 255) as u8,
+            color[3],
+        ],
+        None => color,
+    }
+}
+
+pub fn strip_namespace(name: &str) -> &str {
+    name.strip_prefix("minecraft:").unwrap_or(name)
+}
@@ -0,0 +1,242 @@
+use crate::render::block_colors::{BlockColors, FaceColors, Rgba};
+use crate::utils::block_state_pos_list::BlockData;
+use crate::utils::schematic_data::SchematicData;
+use anyhow::Result;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, Rgba as Pixel, RgbaImage};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::Arc;
+
+pub const THUMBNAIL_SIZE: u32 = 256;
+pub const LM_PREVIEW_SIZE: u32 = 140;
+
+// 画布像素上限，超出时先合并体素再绘制
+const MAX_CANVAS: i64 = 2048;
+
+const SHADE_TOP: u16 = 255;
+const SHADE_LEFT: u16 = 210;
+const SHADE_RIGHT: u16 = 165;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Face {
+    None,
+    Top,
+    Left,
+    Right,
+}
+
+struct Cell {
+    colors: FaceColors,
+    opaque: bool,
+}
+
+fn face_mask(u: i32) -> Vec<Face> {
+    let size = (2 * u) as usize;
+    let mut mask = vec![Face::None; size * size];
+    let half = u as f32 / 2.0;
+    for j in 0..2 * u {
+        for i in 0..2 * u {
+            let (fx, fy) = (i as f32 + 0.5, j as f32 + 0.5);
+            let top = (fx - u as f32).abs() / u as f32 + (fy - half).abs() / half <= 1.0;
+            let face = if top {
+                Face::Top
+            } else if fx < u as f32 {
+                let upper = half + fx / 2.0;
+                if fy >= upper && fy <= upper + u as f32 { Face::Left } else { Face::None }
+            } else {
+                let upper = u as f32 - (fx - u as f32) / 2.0;
+                if fy >= upper && fy <= upper + u as f32 { Face::Right } else { Face::None }
+            };
+            mask[j as usize * size + i as usize] = face;
+        }
+    }
+    mask
+}
+
+fn shade(color: Rgba, factor: u16) -> Rgba {
+    [
+        (color[0] as u16 * factor / 255) as u8,
+        (color[1] as u16 * factor / 255) as u8,
+        (color[2] as u16 * factor / 255) as u8,
+        color[3],
+    ]
+}
+
+fn blend(dst: &mut Pixel<u8>, src: Rgba) {
+    let a = src[3] as u16;
+    for c in 0..3 {
+        dst.0[c] = ((src[c] as u16 * a + dst.0[c] as u16 * (255 - a)) / 255) as u8;
+    }
+    dst.0[3] = dst.0[3].max(src[3]);
+}
+
+/// 无 GPU 的等轴测渲染，视角从 +x +y +z 方向看向原点
+pub fn render_isometric(data: &SchematicData, colors: &BlockColors, max_size: u32) -> RgbaImage {
+    let blocks = &data.blocks.elements;
+    let mut palette: HashMap<Arc<BlockData>, Option<FaceColors>> = HashMap::new();
+    let (mut min, mut max) = ([i32::MAX; 3], [i32::MIN; 3]);
+    for block in blocks.iter() {
+        let entry = palette
+            .entry(block.block.clone())
+            .or_insert_with(|| colors.face_colors(&block.block));
+        if entry.is_none() {
+            continue;
+        }
+        let p = [block.pos.x, block.pos.y, block.pos.z];
+        for a in 0..3 {
+            min[a] = min[a].min(p[a]);
+            max[a] = max[a].max(p[a]);
+        }
+    }
+    if min[0] > max[0] {
+        return RgbaImage::new(1, 1);
+    }
+    let dims = [
+        (max[0] - min[0] + 1) as i64,
+        (max[1] - min[1] + 1) as i64,
+        (max[2] - min[2] + 1) as i64,
+    ];
+
+    // 体素合并倍率 f 与每格半宽 u，保证画布不超过上限
+    let fit = |f: i64, u: i64| {
+        let (w, h, l) = ((dims[0] + f - 1) / f, (dims[1] + f - 1) / f, (dims[2] + f - 1) / f);
+        ((w + l) * u, (w + l) * u / 2 + h * u)
+    };
+    let mut factor = 1i64;
+    while fit(factor, 2).0.max(fit(factor, 2).1) > MAX_CANVAS {
+        factor += 1;
+    }
+    let mut u = 2i64;
+    while {
+        let (w, h) = fit(factor, u + 2);
+        w.max(h) <= max_size as i64 && u < 32
+    } {
+        u += 2;
+    }
+    let cw = (dims[0] + factor - 1) / factor;
+    let ch = (dims[1] + factor - 1) / factor;
+    let cl = (dims[2] + factor - 1) / factor;
+
+    let mut cells: HashMap<(i32, i32, i32), Cell> = HashMap::new();
+    for block in blocks.iter() {
+        let Some(Some(face)) = palette.get(&block.block) else {
+            continue;
+        };
+        let key = (
+            ((block.pos.x - min[0]) as i64 / factor) as i32,
+            ((block.pos.y - min[1]) as i64 / factor) as i32,
+            ((block.pos.z - min[2]) as i64 / factor) as i32,
+        );
+        let opaque = colors.is_opaque(&block.block.id.name) && face.top[3] >= 200;
+        // 合并格内优先保留不透明方块
+        match cells.get(&key) {
+            Some(existing) if existing.opaque || !opaque => {}
+            _ => {
+                cells.insert(key, Cell { colors: *face, opaque });
+            }
+        }
+    }
+
+    let u = u as i32;
+    let width = ((cw + cl) * u as i64) as u32;
+    let height = ((cw + cl) * u as i64 / 2 + ch * u as i64) as u32;
+    let mut image = RgbaImage::new(width.max(1), height.max(1));
+    let mut depth = vec![i32::MIN; (image.width() * image.height()) as usize];
+    let mask = face_mask(u);
+    let sprite = (2 * u) as usize;
+
+    let mut order: Vec<(&(i32, i32, i32), &Cell)> = cells.iter().collect();
+    order.sort_by_key(|((x, y, z), _)| x + y + z);
+    for (&(x, y, z), cell) in order {
+        let covered = |dx: i32, dy: i32, dz: i32| {
+            cells
+                .get(&(x + dx, y + dy, z + dz))
+                .map(|c| c.opaque)
+                .unwrap_or(false)
+        };
+        let show_top = !covered(0, 1, 0);
+        let show_left = !covered(0, 0, 1);
+        let show_right = !covered(1, 0, 0);
+        if !(show_top || show_left || show_right) {
+            continue;
+        }
+        let px = (x - z + cl as i32 - 1) * u;
+        let py = (x + z) * u / 2 + (ch as i32 - 1 - y) * u;
+        let d = x + y + z;
+        for j in 0..sprite {
+            for i in 0..sprite {
+                let color = match mask[j * sprite + i] {
+                    Face::Top if show_top => shade(cell.colors.top, SHADE_TOP),
+                    Face::Left if show_left => shade(cell.colors.side, SHADE_LEFT),
+                    Face::Right if show_right => shade(cell.colors.side, SHADE_RIGHT),
+                    _ => continue,
+                };
+                let (ix, iy) = (px + i as i32, py + j as i32);
+                if ix < 0 || iy < 0 || ix >= image.width() as i32 || iy >= image.height() as i32 {
+                    continue;
+                }
+                let idx = (iy as u32 * image.width() + ix as u32) as usize;
+                if depth[idx] > d {
+                    continue;
+                }
+                let pixel = image.get_pixel_mut(ix as u32, iy as u32);
+                if cell.opaque || color[3] >= 250 {
+                    *pixel = Pixel([color[0], color[1], color[2], 255]);
+                    depth[idx] = d;
+                } else {
+                    blend(pixel, color);
+                }
+            }
+        }
+    }
+
+    let image = crop_transparent(image);
+    if image.width() > max_size || image.height() > max_size {
+        let scale = max_size as f32 / image.width().max(image.height()) as f32;
+        let w = ((image.width() as f32 * scale) as u32).max(1);
+        let h = ((image.height() as f32 * scale) as u32).max(1);
+        image::imageops::resize(&image, w, h, FilterType::Triangle)
+    } else {
+        image
+    }
+}
+
+fn crop_transparent(image: RgbaImage) -> RgbaImage {
+    let (mut x0, mut y0, mut x1, mut y1) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y, p) in image.enumerate_pixels() {
+        if p.0[3] != 0 {
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x);
+            y1 = y1.max(y);
+        }
+    }
+    if x0 > x1 {
+        return image;
+    }
+    image::imageops::crop_imm(&image, x0, y0, x1 - x0 + 1, y1 - y0 + 1).to_image()
+}
+
+pub fn render_png(data: &SchematicData, colors: &BlockColors, max_size: u32) -> Result<Vec<u8>> {
+    let image = render_isometric(data, colors, max_size);
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgba8(image).write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+/// Litematica 预览图：居中放入正方形画布，按行输出 ARGB
+pub fn render_lm_preview(data: &SchematicData, colors: &BlockColors) -> Vec<i32> {
+    let image = render_isometric(data, colors, LM_PREVIEW_SIZE);
+    let mut canvas = RgbaImage::new(LM_PREVIEW_SIZE, LM_PREVIEW_SIZE);
+    let ox = (LM_PREVIEW_SIZE - image.width().min(LM_PREVIEW_SIZE)) / 2;
+    let oy = (LM_PREVIEW_SIZE - image.height().min(LM_PREVIEW_SIZE)) / 2;
+    image::imageops::overlay(&mut canvas, &image, ox as i64, oy as i64);
+    canvas
+        .pixels()
+        .map(|p| {
+            let [r, g, b, a] = p.0;
+            ((a as u32) << 24 | (r as u32) << 16 | (g as u32) << 8 | b as u32) as i32
+        })
+        .collect()
+}
@@ -0,0 +1,219 @@
+use crate::render::block_colors::BlockColors;
+use crate::utils::block_state_pos_list::{BlockData, BlockStatePosList};
+use crate::utils::schematic_data::SchematicData;
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const BACKGROUND: Rgba<u8> = Rgba([240, 240, 240, 255]);
+const GRID: Rgba<u8> = Rgba([190, 190, 190, 255]);
+const GRID_MAJOR: Rgba<u8> = Rgba([120, 120, 120, 255]);
+const RULER_TEXT: Rgba<u8> = Rgba([40, 40, 40, 255]);
+
+// 3x5 点阵数字，用于坐标标尺
+const DIGITS: [[u8; 5]; 11] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    [0b000, 0b000, 0b111, 0b000, 0b000],
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct LayerOptions {
+    pub cell_size: u32,
+    pub ruler: bool,
+}
+
+impl Default for LayerOptions {
+    fn default() -> Self {
+        Self { cell_size: 16, ruler: true }
+    }
+}
+
+/// 俯视分层渲染，x 向右、z 向下(北在上)
+pub struct LayerRenderer {
+    options: LayerOptions,
+    min: (i32, i32, i32),
+    width: i32,
+    length: i32,
+    layers: Vec<BlockStatePosList>,
+    cells: HashMap<Arc<BlockData>, Option<RgbaImage>>,
+}
+
+impl LayerRenderer {
+    pub fn new(data: &SchematicData, colors: &BlockColors, options: LayerOptions) -> Self {
+        let blocks = &data.blocks.elements;
+        let (mut min, mut max) = ([i32::MAX; 3], [i32::MIN; 3]);
+        for block in blocks.iter() {
+            let p = [block.pos.x, block.pos.y, block.pos.z];
+            for a in 0..3 {
+                min[a] = min[a].min(p[a]);
+                max[a] = max[a].max(p[a]);
+            }
+        }
+        if blocks.is_empty() {
+            min = [0; 3];
+            max = [0; 3];
+        }
+        let height = (max[1] - min[1] + 1) as usize;
+        let mut layers = vec![BlockStatePosList::default(); height];
+        for block in blocks.iter() {
+            layers[(block.pos.y - min[1]) as usize]
+                .elements
+                .push_back(block.clone());
+        }
+
+        let options = LayerOptions {
+            cell_size: options.cell_size.clamp(2, 64),
+            ..options
+        };
+        let mut cells = HashMap::new();
+        for block in blocks.iter() {
+            cells.entry(block.block.clone()).or_insert_with(|| {
+                cell_image(colors, &block.block, options.cell_size)
+            });
+        }
+
+        Self {
+            options,
+            min: (min[0], min[1], min[2]),
+            width: max[0] - min[0] + 1,
+            length: max[2] - min[2] + 1,
+            layers,
+            cells,
+        }
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn layer_y(&self, index: usize) -> i32 {
+        self.min.1 + index as i32
+    }
+
+    pub fn layer_blocks(&self, index: usize) -> &BlockStatePosList {
+        &self.layers[index]
+    }
+
+    fn ruler_scale(&self) -> u32 {
+        (self.options.cell_size / 8).max(1)
+    }
+
+    fn margin(&self) -> (u32, u32) {
+        if !self.options.ruler {
+            return (0, 0);
+        }
+        let s = self.ruler_scale();
+        let digits = self.width.max(self.length).to_string().len() as u32;
+        (digits * 4 * s + 2 * s, 7 * s)
+    }
+
+    pub fn render(&self, index: usize) -> RgbaImage {
+        let c = self.options.cell_size;
+        let (left, top) = self.margin();
+        let w = self.width as u32 * c;
+        let h = self.length as u32 * c;
+        let mut image = RgbaImage::from_pixel(left + w + 1, top + h + 1, BACKGROUND);
+
+        for block in self.layers[index].elements.iter() {
+            let Some(Some(cell)) = self.cells.get(&block.block) else {
+                continue;
+            };
+            let cx = left + (block.pos.x - self.min.0) as u32 * c;
+            let cz = top + (block.pos.z - self.min.2) as u32 * c;
+            for (px, pz, p) in cell.enumerate_pixels() {
+                let dst = image.get_pixel_mut(cx + px, cz + pz);
+                let a = p.0[3] as u16;
+                for k in 0..3 {
+                    dst.0[k] = ((p.0[k] as u16 * a + dst.0[k] as u16 * (255 - a)) / 255) as u8;
+                }
+            }
+        }
+
+        // 网格线，每 5 格加深方便数格
+        if c >= 6 {
+            for i in 0..=self.width as u32 {
+                let color = if i % 5 == 0 { GRID_MAJOR } else { GRID };
+                for y in top..top + h + 1 {
+                    image.put_pixel(left + i * c, y, color);
+                }
+            }
+            for j in 0..=self.length as u32 {
+                let color = if j % 5 == 0 { GRID_MAJOR } else { GRID };
+                for x in left..left + w + 1 {
+                    image.put_pixel(x, top + j * c, color);
+                }
+            }
+        }
+
+        if self.options.ruler {
+            let s = self.ruler_scale();
+            let step = label_step(c, s, self.width.max(self.length));
+            for i in (0..self.width).step_by(step) {
+                let text = i.to_string();
+                let tw = text.len() as u32 * 4 * s;
+                let x = (left + i as u32 * c + c / 2).saturating_sub(tw / 2);
+                draw_number(&mut image, &text, x, s, s);
+            }
+            for j in (0..self.length).step_by(step) {
+                let text = j.to_string();
+                let tw = text.len() as u32 * 4 * s;
+                let y = (top + j as u32 * c + c / 2).saturating_sub(5 * s / 2);
+                draw_number(&mut image, &text, left.saturating_sub(tw + s), y, s);
+            }
+        }
+        image
+    }
+}
+
+fn label_step(cell: u32, scale: u32, max: i32) -> usize {
+    let label = max.to_string().len() as u32 * 4 * scale + scale;
+    [1usize, 2, 5, 10, 20, 50, 100]
+        .into_iter()
+        .find(|step| *step as u32 * cell >= label)
+        .unwrap_or(100)
+}
+
+fn cell_image(colors: &BlockColors, block: &BlockData, cell: u32) -> Option<RgbaImage> {
+    if let Some(texture) = colors.top_texture(block) {
+        let filter = if cell >= texture.width() { FilterType::Nearest } else { FilterType::Triangle };
+        return Some(image::imageops::resize(&texture, cell, cell, filter));
+    }
+    let face = colors.face_colors(block)?;
+    Some(RgbaImage::from_pixel(cell, cell, Rgba(face.top)))
+}
+
+fn draw_number(image: &mut RgbaImage, text: &str, x: u32, y: u32, scale: u32) {
+    for (i, ch) in text.chars().enumerate() {
+        let glyph = match ch {
+            '0'..='9' => DIGITS[ch as usize - '0' as usize],
+            '-' => DIGITS[10],
+            _ => continue,
+        };
+        let gx = x + i as u32 * 4 * scale;
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..3u32 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let (px, py) = (gx + col * scale + dx, y + row as u32 * scale + dy);
+                        if px < image.width() && py < image.height() {
+                            image.put_pixel(px, py, RULER_TEXT);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
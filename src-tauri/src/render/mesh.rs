@@ -0,0 +1,562 @@
+use crate::render::block_colors::{apply_tint, strip_namespace, AppliedModel, BlockColors};
+use crate::utils::block_state_pos_list::BlockData;
+use crate::utils::schematic_data::SchematicData;
+use image::RgbaImage;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dir {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+
+const DIRS: [Dir; 6] = [Dir::Down, Dir::Up, Dir::North, Dir::South, Dir::West, Dir::East];
+
+impl Dir {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "down" | "bottom" => Some(Dir::Down),
+            "up" => Some(Dir::Up),
+            "north" => Some(Dir::North),
+            "south" => Some(Dir::South),
+            "west" => Some(Dir::West),
+            "east" => Some(Dir::East),
+            _ => None,
+        }
+    }
+
+    fn offset(self) -> [i32; 3] {
+        match self {
+            Dir::Down => [0, -1, 0],
+            Dir::Up => [0, 1, 0],
+            Dir::North => [0, 0, -1],
+            Dir::South => [0, 0, 1],
+            Dir::West => [-1, 0, 0],
+            Dir::East => [1, 0, 0],
+        }
+    }
+
+    fn axis(self) -> usize {
+        match self {
+            Dir::West | Dir::East => 0,
+            Dir::Down | Dir::Up => 1,
+            Dir::North | Dir::South => 2,
+        }
+    }
+
+    fn positive(self) -> bool {
+        matches!(self, Dir::Up | Dir::South | Dir::East)
+    }
+
+    fn from_vector(v: [f32; 3]) -> Self {
+        let axis = (0..3)
+            .max_by(|a, b| v[*a].abs().total_cmp(&v[*b].abs()))
+            .unwrap_or(1);
+        match (axis, v[axis] > 0.0) {
+            (0, true) => Dir::East,
+            (0, false) => Dir::West,
+            (1, true) => Dir::Up,
+            (1, false) => Dir::Down,
+            (_, true) => Dir::South,
+            (_, false) => Dir::North,
+        }
+    }
+}
+
+/// 面的四个角，顺序为材质的左上、右上、右下、左下(从面外侧看)
+fn face_corners(dir: Dir, from: [f32; 3], to: [f32; 3]) -> [[f32; 3]; 4] {
+    let ([x0, y0, z0], [x1, y1, z1]) = (from, to);
+    match dir {
+        Dir::North => [[x1, y1, z0], [x0, y1, z0], [x0, y0, z0], [x1, y0, z0]],
+        Dir::South => [[x0, y1, z1], [x1, y1, z1], [x1, y0, z1], [x0, y0, z1]],
+        Dir::West => [[x0, y1, z0], [x0, y1, z1], [x0, y0, z1], [x0, y0, z0]],
+        Dir::East => [[x1, y1, z1], [x1, y1, z0], [x1, y0, z0], [x1, y0, z1]],
+        Dir::Up => [[x0, y1, z0], [x1, y1, z0], [x1, y1, z1], [x0, y1, z1]],
+        Dir::Down => [[x0, y0, z1], [x1, y0, z1], [x1, y0, z0], [x0, y0, z0]],
+    }
+}
+
+/// 未指定 uv 时按坐标自动生成，与原版一致；size 为 0 时用于平铺材质
+fn default_uv(dir: Dir, from: [f32; 3], to: [f32; 3], size: f32) -> [f32; 4] {
+    let ([x0, y0, z0], [x1, y1, z1]) = (from, to);
+    match dir {
+        Dir::Up => [x0, z0, x1, z1],
+        Dir::Down => [x0, size - z1, x1, size - z0],
+        Dir::North => [size - x1, size - y1, size - x0, size - y0],
+        Dir::South => [x0, size - y1, x1, size - y0],
+        Dir::West => [z0, size - y1, z1, size - y0],
+        Dir::East => [size - z1, size - y1, size - z0, size - y0],
+    }
+}
+
+fn uv_corners(uv: [f32; 4], rotation: i32) -> [[f32; 2]; 4] {
+    let corners = [[uv[0], uv[1]], [uv[2], uv[1]], [uv[2], uv[3]], [uv[0], uv[3]]];
+    let shift = (rotation / 90).rem_euclid(4) as usize;
+    std::array::from_fn(|i| corners[(i + 4 - shift) % 4])
+}
+
+/// 方块状态旋转，先绕 x 再绕 y，以模型中心为原点
+fn rotate_state(p: [f32; 3], x: i32, y: i32, center: f32) -> [f32; 3] {
+    let mut p = p;
+    for _ in 0..(x / 90).rem_euclid(4) {
+        let (py, pz) = (p[1] - center, p[2] - center);
+        p[1] = pz + center;
+        p[2] = -py + center;
+    }
+    for _ in 0..(y / 90).rem_euclid(4) {
+        let (px, pz) = (p[0] - center, p[2] - center);
+        p[0] = -pz + center;
+        p[2] = px + center;
+    }
+    p
+}
+
+fn rotate_element(p: [f32; 3], rotation: &JsonValue) -> [f32; 3] {
+    let origin = rotation
+        .get("origin")
+        .and_then(|o| o.as_array())
+        .map(|o| std::array::from_fn(|i| o.get(i).and_then(|v| v.as_f64()).unwrap_or(8.0) as f32))
+        .unwrap_or([8.0; 3]);
+    let angle = rotation.get("angle").and_then(|a| a.as_f64()).unwrap_or(0.0) as f32;
+    if angle == 0.0 {
+        return p;
+    }
+    let axis = match rotation.get("axis").and_then(|a| a.as_str()) {
+        Some("x") => 0,
+        Some("z") => 2,
+        _ => 1,
+    };
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let v = [p[0] - origin[0], p[1] - origin[1], p[2] - origin[2]];
+    let mut r = match axis {
+        0 => [v[0], v[1] * cos - v[2] * sin, v[1] * sin + v[2] * cos],
+        1 => [v[0] * cos + v[2] * sin, v[1], -v[0] * sin + v[2] * cos],
+        _ => [v[0] * cos - v[1] * sin, v[0] * sin + v[1] * cos, v[2]],
+    };
+    if rotation.get("rescale").and_then(|r| r.as_bool()).unwrap_or(false) {
+        let scale = 1.0 / cos.abs().max(1e-3);
+        for (i, value) in r.iter_mut().enumerate() {
+            if i != axis {
+                *value *= scale;
+            }
+        }
+    }
+    [r[0] + origin[0], r[1] + origin[1], r[2] + origin[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn json_vec3(value: Option<&JsonValue>) -> Option<[f32; 3]> {
+    let list = value?.as_array()?;
+    Some([
+        list.first()?.as_f64()? as f32,
+        list.get(1)?.as_f64()? as f32,
+        list.get(2)?.as_f64()? as f32,
+    ])
+}
+
+pub struct MeshMaterial {
+    pub name: String,
+    pub image: RgbaImage,
+    // 贪心合并的面需要材质平铺重复
+    pub repeat: bool,
+}
+
+#[derive(Default)]
+pub struct MeshGroup {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshGroup {
+    fn push_quad(&mut self, corners: [[f32; 3]; 4], uvs: [[f32; 2]; 4]) {
+        let normal = cross(sub(corners[3], corners[0]), sub(corners[1], corners[0]));
+        let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+        if length <= f32::EPSILON {
+            return;
+        }
+        let normal = [normal[0] / length, normal[1] / length, normal[2] / length];
+        let base = self.positions.len() as u32;
+        self.positions.extend_from_slice(&corners);
+        self.normals.extend_from_slice(&[normal; 4]);
+        self.uvs.extend_from_slice(&uvs);
+        self.indices
+            .extend_from_slice(&[base, base + 3, base + 2, base, base + 2, base + 1]);
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+}
+
+/// materials 与 groups 一一对应，0 号为共享图集
+pub struct Mesh {
+    pub materials: Vec<MeshMaterial>,
+    pub groups: Vec<MeshGroup>,
+}
+
+impl Mesh {
+    pub fn triangle_count(&self) -> usize {
+        self.groups.iter().map(MeshGroup::triangle_count).sum()
+    }
+}
+
+struct ModelFace {
+    corners: [[f32; 3]; 4],
+    uvs: [[f32; 2]; 4],
+    cull: Option<Dir>,
+}
+
+enum BlockMesh {
+    Invisible,
+    // 完整立方体，每个面是按顺序叠加的材质，用于贪心合并
+    Cube([Vec<String>; 6]),
+    Model(Vec<ModelFace>),
+}
+
+struct MeshBuilder<'a> {
+    colors: &'a BlockColors,
+    atlas_size: (f32, f32),
+    materials: Vec<MeshMaterial>,
+    groups: Vec<MeshGroup>,
+    tiled: HashMap<Vec<String>, usize>,
+}
+
+impl<'a> MeshBuilder<'a> {
+    fn new(colors: &'a BlockColors) -> Self {
+        let atlas = colors.tinted_atlas();
+        Self {
+            colors,
+            atlas_size: (atlas.width().max(1) as f32, atlas.height().max(1) as f32),
+            materials: vec![MeshMaterial { name: "atlas".to_string(), image: atlas, repeat: false }],
+            groups: vec![MeshGroup::default()],
+            tiled: HashMap::new(),
+        }
+    }
+
+    fn is_fluid(name: &str) -> Option<&'static str> {
+        match strip_namespace(name) {
+            "water" | "bubble_column" => Some("block/water_still"),
+            "lava" => Some("block/lava_still"),
+            _ => None,
+        }
+    }
+
+    fn block_mesh(&self, block: &BlockData) -> BlockMesh {
+        if BlockColors::is_invisible(&block.id.name) {
+            return BlockMesh::Invisible;
+        }
+        if let Some(texture) = Self::is_fluid(&block.id.name) {
+            return BlockMesh::Cube(std::array::from_fn(|_| vec![texture.to_string()]));
+        }
+        let models = self.colors.block_models(block);
+        if let [model] = models.as_slice() {
+            if let Some(faces) = self.cube_faces(model) {
+                return BlockMesh::Cube(faces);
+            }
+        }
+        let faces: Vec<ModelFace> = models.iter().flat_map(|m| self.model_faces(m)).collect();
+        if faces.is_empty() {
+            BlockMesh::Invisible
+        } else {
+            BlockMesh::Model(faces)
+        }
+    }
+
+    fn resolve_texture(&self, textures: &HashMap<String, String>, face: &JsonValue) -> Option<String> {
+        let texture = face.get("texture")?.as_str()?;
+        let texture = match texture.strip_prefix('#') {
+            Some(key) => textures.get(key)?.as_str(),
+            None => texture,
+        };
+        if texture.starts_with('#') {
+            return None;
+        }
+        let texture = strip_namespace(texture).to_string();
+        self.colors.texture_region(&texture).map(|_| texture)
+    }
+
+    /// 只有未旋转、所有元素都是满格且 uv 为默认的模型才走贪心合并
+    fn cube_faces(&self, model: &AppliedModel) -> Option<[Vec<String>; 6]> {
+        if model.x != 0 || model.y != 0 {
+            return None;
+        }
+        let elements = self.colors.model_elements(&model.model)?;
+        let textures = self.colors.model_textures(&model.model);
+        let mut faces: [Vec<String>; 6] = Default::default();
+        for element in elements {
+            if json_vec3(element.get("from"))? != [0.0; 3]
+                || json_vec3(element.get("to"))? != [16.0; 3]
+                || element.get("rotation").is_some()
+            {
+                return None;
+            }
+            let JsonValue::Object(map) = element.get("faces")? else {
+                return None;
+            };
+            for (name, face) in map {
+                let dir = Dir::from_name(name)?;
+                let full_uv = face
+                    .get("uv")
+                    .map(|uv| uv.as_array().map(|l| l.iter().filter_map(|v| v.as_f64()).collect::<Vec<_>>())
+                        == Some(vec![0.0, 0.0, 16.0, 16.0]))
+                    .unwrap_or(true);
+                let rotated = face.get("rotation").and_then(|r| r.as_i64()).unwrap_or(0) != 0;
+                if !full_uv || rotated {
+                    return None;
+                }
+                faces[dir as usize].push(self.resolve_texture(&textures, face)?);
+            }
+        }
+        faces.iter().all(|f| !f.is_empty()).then_some(faces)
+    }
+
+    fn model_faces(&self, model: &AppliedModel) -> Vec<ModelFace> {
+        // uvlock 未处理：旋转后的材质方向可能与游戏内不同
+        let Some(elements) = self.colors.model_elements(&model.model) else {
+            return Vec::new();
+        };
+        let textures = self.colors.model_textures(&model.model);
+        let mut out = Vec::new();
+        for element in elements {
+            let (Some(from), Some(to)) = (json_vec3(element.get("from")), json_vec3(element.get("to"))) else {
+                continue;
+            };
+            let Some(JsonValue::Object(map)) = element.get("faces") else {
+                continue;
+            };
+            for (name, face) in map {
+                let Some(dir) = Dir::from_name(name) else {
+                    continue;
+                };
+                let Some(texture) = self.resolve_texture(&textures, face) else {
+                    continue;
+                };
+                let Some([rx, ry, rw, rh]) = self.colors.texture_region(&texture) else {
+                    continue;
+                };
+                let uv = face
+                    .get("uv")
+                    .and_then(|uv| uv.as_array())
+                    .filter(|uv| uv.len() == 4)
+                    .map(|uv| std::array::from_fn(|i| uv[i].as_f64().unwrap_or(0.0) as f32))
+                    .unwrap_or_else(|| default_uv(dir, from, to, 16.0));
+                let rotation = face.get("rotation").and_then(|r| r.as_i64()).unwrap_or(0) as i32;
+                let uvs = uv_corners(uv, rotation).map(|[u, v]| {
+                    [
+                        (rx as f32 + u / 16.0 * rw as f32) / self.atlas_size.0,
+                        (ry as f32 + v / 16.0 * rh as f32) / self.atlas_size.1,
+                    ]
+                });
+                let corners = face_corners(dir, from, to).map(|p| {
+                    let p = match element.get("rotation") {
+                        Some(r) => rotate_element(p, r),
+                        None => p,
+                    };
+                    let p = rotate_state(p, model.x, model.y, 8.0);
+                    [p[0] / 16.0, p[1] / 16.0, p[2] / 16.0]
+                });
+                let cull = face
+                    .get("cullface")
+                    .and_then(|c| c.as_str())
+                    .and_then(Dir::from_name)
+                    .map(|c| {
+                        let o = c.offset();
+                        let v = rotate_state([o[0] as f32, o[1] as f32, o[2] as f32], model.x, model.y, 0.0);
+                        Dir::from_vector(v)
+                    });
+                out.push(ModelFace { corners, uvs, cull });
+            }
+        }
+        out
+    }
+
+    fn tiled_material(&mut self, layers: &[String]) -> usize {
+        if let Some(index) = self.tiled.get(layers) {
+            return *index;
+        }
+        let mut image: Option<RgbaImage> = None;
+        for texture in layers {
+            let Some([x, y, w, h]) = self.colors.texture_region(texture) else {
+                continue;
+            };
+            let mut layer = image::imageops::crop_imm(self.colors.atlas(), x, y, w, h).to_image();
+            if let Some(tint) = BlockColors::texture_tint(texture) {
+                for pixel in layer.pixels_mut() {
+                    pixel.0 = apply_tint(pixel.0, Some(tint));
+                }
+            }
+            match image.as_mut() {
+                Some(base) if base.dimensions() == layer.dimensions() => {
+                    image::imageops::overlay(base, &layer, 0, 0);
+                }
+                Some(_) => {}
+                None => image = Some(layer),
+            }
+        }
+        let image = image.unwrap_or_else(|| RgbaImage::new(1, 1));
+        let name = layers
+            .iter()
+            .map(|t| t.trim_start_matches("block/").replace('/', "_"))
+            .collect::<Vec<_>>()
+            .join("+");
+        self.materials.push(MeshMaterial { name, image, repeat: true });
+        self.groups.push(MeshGroup::default());
+        let index = self.materials.len() - 1;
+        self.tiled.insert(layers.to_vec(), index);
+        index
+    }
+}
+
+/// 生成网格：满格方块按面贪心合并，其余方块按模型元素逐面输出，被不透明方块挡住的面剔除
+pub fn build_mesh(data: &SchematicData, colors: &BlockColors) -> Mesh {
+    let mut builder = MeshBuilder::new(colors);
+    let blocks = &data.blocks.elements;
+
+    let mut palette: HashMap<Arc<BlockData>, u32> = HashMap::new();
+    let mut kinds: Vec<BlockMesh> = Vec::new();
+    let mut opaque: Vec<bool> = Vec::new();
+    let mut grid: HashMap<(i32, i32, i32), u32> = HashMap::with_capacity(blocks.len());
+    let mut min = [i32::MAX; 3];
+    for block in blocks.iter() {
+        let index = *palette.entry(block.block.clone()).or_insert_with(|| {
+            let kind = builder.block_mesh(&block.block);
+            opaque.push(
+                !matches!(kind, BlockMesh::Invisible)
+                    && colors.is_opaque(&block.block.id.name)
+                    && MeshBuilder::is_fluid(&block.block.id.name).is_none(),
+            );
+            kinds.push(kind);
+            (kinds.len() - 1) as u32
+        });
+        if matches!(kinds[index as usize], BlockMesh::Invisible) {
+            continue;
+        }
+        let p = [block.pos.x, block.pos.y, block.pos.z];
+        for a in 0..3 {
+            min[a] = min[a].min(p[a]);
+        }
+        grid.insert((p[0], p[1], p[2]), index);
+    }
+    if grid.is_empty() {
+        return Mesh { materials: builder.materials, groups: builder.groups };
+    }
+
+    let occluded = |pos: (i32, i32, i32), dir: Dir, self_index: Option<u32>| {
+        let o = dir.offset();
+        match grid.get(&(pos.0 + o[0], pos.1 + o[1], pos.2 + o[2])) {
+            Some(n) => opaque[*n as usize] || Some(*n) == self_index,
+            None => false,
+        }
+    };
+
+    // (方向, 所在平面) -> [(a, b, 材质)]
+    let mut planes: HashMap<(Dir, i32), Vec<(i32, i32, usize)>> = HashMap::new();
+    let mut keys: Vec<(&(i32, i32, i32), &u32)> = grid.iter().collect();
+    keys.sort_unstable();
+    for (&pos, &index) in keys {
+        let local = [pos.0 - min[0], pos.1 - min[1], pos.2 - min[2]];
+        match &kinds[index as usize] {
+            BlockMesh::Invisible => {}
+            BlockMesh::Cube(faces) => {
+                for dir in DIRS {
+                    if occluded(pos, dir, Some(index)) {
+                        continue;
+                    }
+                    let material = builder.tiled_material(&faces[dir as usize]);
+                    let axis = dir.axis();
+                    let plane = local[axis] + dir.positive() as i32;
+                    let (a, b) = (local[(axis + 1) % 3], local[(axis + 2) % 3]);
+                    planes.entry((dir, plane)).or_default().push((a, b, material));
+                }
+            }
+            BlockMesh::Model(faces) => {
+                for face in faces {
+                    if face.cull.map(|c| occluded(pos, c, None)).unwrap_or(false) {
+                        continue;
+                    }
+                    let corners = face.corners.map(|p| {
+                        [p[0] + local[0] as f32, p[1] + local[1] as f32, p[2] + local[2] as f32]
+                    });
+                    builder.groups[0].push_quad(corners, face.uvs);
+                }
+            }
+        }
+    }
+
+    let mut planes: Vec<_> = planes.into_iter().collect();
+    planes.sort_unstable_by_key(|((dir, plane), _)| (*dir as usize, *plane));
+    for ((dir, plane), cells) in planes {
+        greedy_plane(&mut builder.groups, dir, plane, &cells);
+    }
+    Mesh { materials: builder.materials, groups: builder.groups }
+}
+
+fn greedy_plane(groups: &mut [MeshGroup], dir: Dir, plane: i32, cells: &[(i32, i32, usize)]) {
+    let (mut a0, mut b0, mut a1, mut b1) = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
+    for &(a, b, _) in cells {
+        a0 = a0.min(a);
+        b0 = b0.min(b);
+        a1 = a1.max(a);
+        b1 = b1.max(b);
+    }
+    let (w, h) = ((a1 - a0 + 1) as usize, (b1 - b0 + 1) as usize);
+    let mut mask = vec![usize::MAX; w * h];
+    for &(a, b, material) in cells {
+        mask[(b - b0) as usize * w + (a - a0) as usize] = material;
+    }
+
+    let axis = dir.axis();
+    for j in 0..h {
+        let mut i = 0;
+        while i < w {
+            let material = mask[j * w + i];
+            if material == usize::MAX {
+                i += 1;
+                continue;
+            }
+            let mut width = 1;
+            while i + width < w && mask[j * w + i + width] == material {
+                width += 1;
+            }
+            let mut height = 1;
+            'grow: while j + height < h {
+                for k in 0..width {
+                    if mask[(j + height) * w + i + k] != material {
+                        break 'grow;
+                    }
+                }
+                height += 1;
+            }
+            for row in 0..height {
+                mask[(j + row) * w + i..(j + row) * w + i + width].fill(usize::MAX);
+            }
+
+            let mut from = [0.0f32; 3];
+            let mut to = [0.0f32; 3];
+            from[axis] = plane as f32;
+            to[axis] = plane as f32;
+            from[(axis + 1) % 3] = (a0 + i as i32) as f32;
+            to[(axis + 1) % 3] = (a0 + (i + width) as i32) as f32;
+            from[(axis + 2) % 3] = (b0 + j as i32) as f32;
+            to[(axis + 2) % 3] = (b0 + (j + height) as i32) as f32;
+            let uvs = uv_corners(default_uv(dir, from, to, 0.0), 0);
+            groups[material].push_quad(face_corners(dir, from, to), uvs);
+            i += width;
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use crate::render::mesh::Mesh;
+use anyhow::Result;
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use serde_json::{json, Value as JsonValue};
+use std::fmt::Write as _;
+use std::io::Cursor;
+
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgba8(image.clone()).write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+fn texture_file(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect();
+    format!("textures/{}.png", name)
+}
+
+/// OBJ + MTL，材质图片放在 textures/ 下，返回待打包的文件列表
+pub fn write_obj(mesh: &Mesh, name: &str) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut obj = String::new();
+    let mut mtl = String::new();
+    let mut files = Vec::new();
+    writeln!(obj, "mtllib {}.mtl", name)?;
+    writeln!(obj, "o {}", name)?;
+
+    let mut offset = 1usize;
+    for (material, group) in mesh.materials.iter().zip(&mesh.groups) {
+        if group.indices.is_empty() {
+            continue;
+        }
+        let texture = texture_file(&material.name);
+        writeln!(mtl, "newmtl {}", material.name)?;
+        writeln!(mtl, "Ka 1 1 1\nKd 1 1 1\nKs 0 0 0\nillum 1")?;
+        writeln!(mtl, "map_Kd {}\nmap_d {}\n", texture, texture)?;
+        files.push((texture, encode_png(&material.image)?));
+
+        for p in &group.positions {
+            writeln!(obj, "v {} {} {}", p[0], p[1], p[2])?;
+        }
+        // OBJ 的 v 轴向上，材质坐标需要翻转
+        for uv in &group.uvs {
+            writeln!(obj, "vt {} {}", uv[0], 1.0 - uv[1])?;
+        }
+        for n in &group.normals {
+            writeln!(obj, "vn {} {} {}", n[0], n[1], n[2])?;
+        }
+        writeln!(obj, "usemtl {}", material.name)?;
+        for tri in group.indices.chunks(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| i as usize + offset);
+            writeln!(obj, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+        }
+        offset += group.positions.len();
+    }
+
+    files.insert(0, (format!("{}.mtl", name), mtl.into_bytes()));
+    files.insert(0, (format!("{}.obj", name), obj.into_bytes()));
+    Ok(files)
+}
+
+/// 二进制 glTF，所有材质图片内嵌，采样器为最近邻
+pub fn write_glb(mesh: &Mesh) -> Result<Vec<u8>> {
+    let mut bin: Vec<u8> = Vec::new();
+    let mut views: Vec<JsonValue> = Vec::new();
+    let mut accessors: Vec<JsonValue> = Vec::new();
+    let mut images = Vec::new();
+    let mut textures = Vec::new();
+    let mut materials = Vec::new();
+    let mut primitives = Vec::new();
+
+    let mut push_view = |bin: &mut Vec<u8>, bytes: &[u8], target: Option<u32>| {
+        while bin.len() % 4 != 0 {
+            bin.push(0);
+        }
+        let mut view = json!({ "buffer": 0, "byteOffset": bin.len(), "byteLength": bytes.len() });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        bin.extend_from_slice(bytes);
+        views.push(view);
+        views.len() - 1
+    };
+
+    for (material, group) in mesh.materials.iter().zip(&mesh.groups) {
+        if group.indices.is_empty() {
+            continue;
+        }
+        let png = encode_png(&material.image)?;
+        let image_view = push_view(&mut bin, &png, None);
+        images.push(json!({ "bufferView": image_view, "mimeType": "image/png", "name": material.name }));
+        textures.push(json!({ "source": images.len() - 1, "sampler": if material.repeat { 0 } else { 1 } }));
+        materials.push(json!({
+            "name": material.name,
+            "pbrMetallicRoughness": {
+                "baseColorTexture": { "index": textures.len() - 1 },
+                "metallicFactor": 0.0,
+                "roughnessFactor": 1.0
+            },
+            "alphaMode": "MASK",
+            "alphaCutoff": 0.5
+        }));
+
+        let (mut min, mut max) = ([f32::MAX; 3], [f32::MIN; 3]);
+        for p in &group.positions {
+            for a in 0..3 {
+                min[a] = min[a].min(p[a]);
+                max[a] = max[a].max(p[a]);
+            }
+        }
+        let floats = |data: &[f32]| data.iter().flat_map(|f| f.to_le_bytes()).collect::<Vec<u8>>();
+        let count = group.positions.len();
+        let position = push_view(&mut bin, &floats(group.positions.as_flattened()), Some(34962));
+        let normal = push_view(&mut bin, &floats(group.normals.as_flattened()), Some(34962));
+        let uv = push_view(&mut bin, &floats(group.uvs.as_flattened()), Some(34962));
+        let indices: Vec<u8> = group.indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let index = push_view(&mut bin, &indices, Some(34963));
+
+        let base = accessors.len();
+        accessors.push(json!({ "bufferView": position, "componentType": 5126, "count": count, "type": "VEC3", "min": min, "max": max }));
+        accessors.push(json!({ "bufferView": normal, "componentType": 5126, "count": count, "type": "VEC3" }));
+        accessors.push(json!({ "bufferView": uv, "componentType": 5126, "count": count, "type": "VEC2" }));
+        accessors.push(json!({ "bufferView": index, "componentType": 5125, "count": group.indices.len(), "type": "SCALAR" }));
+        primitives.push(json!({
+            "attributes": { "POSITION": base, "NORMAL": base + 1, "TEXCOORD_0": base + 2 },
+            "indices": base + 3,
+            "material": materials.len() - 1
+        }));
+    }
+    while bin.len() % 4 != 0 {
+        bin.push(0);
+    }
+
+    let empty = primitives.is_empty();
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "MCSTools" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0 }],
+        "meshes": [{ "primitives": primitives }],
+        "buffers": [{ "byteLength": bin.len() }],
+        "bufferViews": views,
+        "accessors": accessors,
+        "samplers": [
+            { "magFilter": 9728, "minFilter": 9728, "wrapS": 10497, "wrapT": 10497 },
+            { "magFilter": 9728, "minFilter": 9728, "wrapS": 33071, "wrapT": 33071 }
+        ],
+        "images": images,
+        "textures": textures,
+        "materials": materials
+    });
+    if empty {
+        document["meshes"] = json!([]);
+        document["nodes"] = json!([{}]);
+    }
+
+    let mut json_bytes = serde_json::to_vec(&document)?;
+    while json_bytes.len() % 4 != 0 {
+        json_bytes.push(b' ');
+    }
+    let total = 12 + 8 + json_bytes.len() + 8 + bin.len();
+    let mut out = Vec::with_capacity(total);
+    out.extend_from_slice(b"glTF");
+    out.extend_from_slice(&2u32.to_le_bytes());
+    out.extend_from_slice(&(total as u32).to_le_bytes());
+    out.extend_from_slice(&(json_bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(b"JSON");
+    out.extend_from_slice(&json_bytes);
+    out.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    out.extend_from_slice(b"BIN\0");
+    out.extend_from_slice(&bin);
+    Ok(out)
+}
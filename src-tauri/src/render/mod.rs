@@ -0,0 +1,6 @@
+pub mod block_colors;
+pub mod isometric;
+pub mod layers;
+pub mod pdf;
+pub mod mesh;
+pub mod mesh_export;
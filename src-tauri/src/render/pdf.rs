@@ -0,0 +1,223 @@
+use anyhow::Result;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use image::RgbaImage;
+use std::io::Write;
+
+// A4，单位 pt
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 36.0;
+const TITLE_SIZE: f32 = 16.0;
+const TEXT_SIZE: f32 = 9.0;
+const LINE_HEIGHT: f32 = 11.0;
+const COLUMNS: usize = 3;
+
+pub struct PdfPage {
+    pub title: String,
+    pub image: RgbaImage,
+    pub lines: Vec<String>,
+}
+
+// 1、2 号对象是目录和页面树，最后写出；3-6 号是字体
+const FIRST_PAGE_ID: usize = 7;
+
+/// 极简 PDF 输出：每页一个标题、一张图片和若干行文字
+/// 纯 ASCII 用内置 Helvetica，其余用不嵌入的 STSong-Light（Adobe-GB1），阅读器会换成本机中文字体
+/// 每页写完即丢弃，内存不随层数增长
+pub struct PdfWriter<W: Write> {
+    out: W,
+    position: usize,
+    // 下标为对象号 - 1
+    offsets: Vec<usize>,
+    pages: Vec<usize>,
+}
+
+impl<W: Write> PdfWriter<W> {
+    pub fn new(out: W) -> Result<Self> {
+        let mut writer = Self {
+            out,
+            position: 0,
+            offsets: vec![0; FIRST_PAGE_ID - 1],
+            pages: Vec::new(),
+        };
+        writer.write(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n")?;
+        writer.write_object(3, b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>")?;
+        writer.write_object(
+            4,
+            b"<< /Type /Font /Subtype /Type0 /BaseFont /STSong-Light /Encoding /UniGB-UTF16-H /DescendantFonts [5 0 R] >>",
+        )?;
+        writer.write_object(
+            5,
+            b"<< /Type /Font /Subtype /CIDFontType0 /BaseFont /STSong-Light /CIDSystemInfo << /Registry (Adobe) /Ordering (GB1) /Supplement 4 >> /FontDescriptor 6 0 R /DW 1000 /W [1 95 500] >>",
+        )?;
+        writer.write_object(
+            6,
+            b"<< /Type /FontDescriptor /FontName /STSong-Light /Flags 6 /FontBBox [-25 -254 1000 880] /ItalicAngle 0 /Ascent 880 /Descent -120 /CapHeight 880 /StemV 93 >>",
+        )?;
+        Ok(writer)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.out.write_all(bytes)?;
+        self.position += bytes.len();
+        Ok(())
+    }
+
+    fn write_object(&mut self, id: usize, body: &[u8]) -> Result<()> {
+        if self.offsets.len() < id {
+            self.offsets.resize(id, 0);
+        }
+        self.offsets[id - 1] = self.position;
+        self.write(format!("{} 0 obj\n", id).as_bytes())?;
+        self.write(body)?;
+        self.write(b"\nendobj\n")
+    }
+
+    pub fn add_page(&mut self, page: &PdfPage) -> Result<()> {
+        let id = self.offsets.len() + 1;
+        self.pages.push(id);
+        self.write_object(
+            id,
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH, PAGE_HEIGHT, id + 2, id + 1
+            )
+            .as_bytes(),
+        )?;
+        let content = page_content(page);
+        self.write_object(id + 1, &stream(&format!("/Length {}", content.len()), content.as_bytes()))?;
+
+        let mut rgb = Vec::with_capacity((page.image.width() * page.image.height() * 3) as usize);
+        for p in page.image.pixels() {
+            // 透明部分按白底合成
+            let a = p.0[3] as u16;
+            for c in 0..3 {
+                rgb.push(((p.0[c] as u16 * a + 255 * (255 - a)) / 255) as u8);
+            }
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&rgb)?;
+        let compressed = encoder.finish()?;
+        self.write_object(
+            id + 2,
+            &stream(
+                &format!(
+                    "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /FlateDecode /Length {}",
+                    page.image.width(),
+                    page.image.height(),
+                    compressed.len()
+                ),
+                &compressed,
+            ),
+        )
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        self.write_object(1, b"<< /Type /Catalog /Pages 2 0 R >>")?;
+        let kids = self
+            .pages
+            .iter()
+            .map(|id| format!("{} 0 R", id))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let pages = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, self.pages.len());
+        self.write_object(2, pages.as_bytes())?;
+
+        let xref = self.position;
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            table.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        table.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            xref
+        ));
+        self.write(table.as_bytes())?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+fn stream(dict: &str, data: &[u8]) -> Vec<u8> {
+    let mut out = format!("<< {} >>\nstream\n", dict).into_bytes();
+    out.extend_from_slice(data);
+    out.extend_from_slice(b"\nendstream");
+    out
+}
+
+fn page_content(page: &PdfPage) -> String {
+    let mut content = String::new();
+    let title_y = PAGE_HEIGHT - MARGIN - TITLE_SIZE;
+    content.push_str(&format!(
+        "BT /{} {} Tf {} {} Td {} Tj ET\n",
+        font(&page.title),
+        TITLE_SIZE,
+        MARGIN,
+        title_y,
+        text_operand(&page.title)
+    ));
+
+    // 材料清单最多占页面 35%，放不下的折叠为一行
+    let max_rows = ((PAGE_HEIGHT * 0.35) / LINE_HEIGHT) as usize;
+    let capacity = max_rows * COLUMNS;
+    let mut lines: Vec<String> = page.lines.clone();
+    if lines.len() > capacity {
+        let hidden = lines.len() - capacity + 1;
+        lines.truncate(capacity - 1);
+        lines.push(format!("... +{}", hidden));
+    }
+    let rows = lines.len().div_ceil(COLUMNS);
+    let list_height = rows as f32 * LINE_HEIGHT;
+    let column_width = (PAGE_WIDTH - 2.0 * MARGIN) / COLUMNS as f32;
+    for (i, line) in lines.iter().enumerate() {
+        let (col, row) = (i / rows.max(1), i % rows.max(1));
+        let x = MARGIN + col as f32 * column_width;
+        let y = MARGIN + list_height - (row + 1) as f32 * LINE_HEIGHT;
+        content.push_str(&format!(
+            "BT /{} {} Tf {} {} Td {} Tj ET\n",
+            font(line),
+            TEXT_SIZE,
+            x,
+            y,
+            text_operand(line)
+        ));
+    }
+
+    let box_top = title_y - 12.0;
+    let box_bottom = MARGIN + list_height + if rows > 0 { 12.0 } else { 0.0 };
+    let box_width = PAGE_WIDTH - 2.0 * MARGIN;
+    let box_height = (box_top - box_bottom).max(1.0);
+    let (w, h) = (page.image.width() as f32, page.image.height() as f32);
+    let scale = (box_width / w).min(box_height / h);
+    let (dw, dh) = (w * scale, h * scale);
+    let x = MARGIN + (box_width - dw) / 2.0;
+    let y = box_top - dh;
+    content.push_str(&format!("q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im0 Do Q\n", dw, dh, x, y));
+    content
+}
+
+fn font(text: &str) -> &'static str {
+    if text.is_ascii() { "F1" } else { "F2" }
+}
+
+// ASCII 写成字面字符串，其余写成 UTF-16BE 十六进制串，与 UniGB-UTF16-H 编码对应
+fn text_operand(text: &str) -> String {
+    if text.is_ascii() {
+        format!("({})", escape(text))
+    } else {
+        let hex: String = text.encode_utf16().map(|u| format!("{:04X}", u)).collect();
+        format!("<{}>", hex)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{}", c),
+            ' '..='~' => c.to_string(),
+            _ => "?".to_string(),
+        })
+        .collect()
+}
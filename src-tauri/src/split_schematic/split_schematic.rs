@@ -4,9 +4,15 @@ use std::sync::Arc;
 use rayon::prelude::*;
 use tauri::State;
 use crate::data_files::files::FileManager;
+use crate::database::db_apis::links_api::{find_schematic_links, replace_schematic_links, SPLIT_LINK};
 use crate::database::db_apis::schematics_api::find_schematic;
 use crate::database::db_control::DatabaseState;
 use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos, BlockStatePos, BlockStatePosList};
+use crate::modules::schematic::save_schematic_entry;
+use crate::render::block_colors::BlockColors;
+use crate::utils::extend_write::write_zip;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::requirements::{get_requirements, shulker_boxes, Requirements};
 use crate::utils::schematic_data::{SchematicData, Size};
 use anyhow::{anyhow, Result};
 use crate::be_schematic::to_be_schematic::ToBESchematic;
@@ -18,13 +24,81 @@ use crate::utils::tile_entities::TileEntitiesList;
 use crate::word_edit::to_we_schematic::ToWeSchematic;
 use fastnbt::Value;
 use fastnbt::Value::Compound;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::collections::HashMap;
 
-#[derive(Clone, Debug)]
-struct Offset {
-    x: i32,
-    y: i32,
-    z: i32,
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Offset {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetKind {
+    // 方块总数
+    Blocks,
+    // 方块种类数
+    Types,
+    // 按堆叠规则装满的潜影盒数
+    Shulkers,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitBudget {
+    pub kind: BudgetKind,
+    pub limit: u64,
+    // 0 x，1 y，2 z
+    pub axis: i64,
+    // 从坐标大的一端开始，例如自上而下分层
+    #[serde(default)]
+    pub descending: bool,
+}
+
+fn block_count(req: &Requirements) -> u64 {
+    req.get_requirements().values().map(|&n| n.max(0) as u64).sum()
+}
+
+impl SplitBudget {
+    fn cost(&self, req: &Requirements) -> u64 {
+        match self.kind {
+            BudgetKind::Blocks => block_count(req),
+            BudgetKind::Types => req.get_requirements().len() as u64,
+            BudgetKind::Shulkers => shulker_boxes(req),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartMaterial {
+    pub id: String,
+    pub zh_cn: String,
+    pub num: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetPart {
+    pub index: i64,
+    pub size: Size,
+    pub offset: Offset,
+    pub bytes: Vec<u8>,
+    pub blocks: u64,
+    pub types: u64,
+    pub shulkers: u64,
+    // 单层就超出预算时仍单独成块
+    pub over_budget: bool,
+    pub materials: Vec<PartMaterial>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPart {
+    pub index: i64,
+    pub id: i64,
+    pub size: Size,
+    // 分块最小角相对原蓝图最小角
+    pub offset: Offset,
 }
 
 #[tauri::command]
@@ -60,37 +134,77 @@ pub async fn schematic_split(
         let mut results = Vec::new();
         for (index, (blocks, tile_entities, entities, part_size, _offset)) in split_parts.into_iter().enumerate() {
             let schematic = SchematicData::new(blocks, tile_entities, entities, part_size.clone());
-            let temp_file = match v_type {
-                1 => {
-                    let data = ToCreateSchematic::new(&schematic)?.create_schematic(false);
-                    file_manager.save_nbt_value_temp(data, v_type, true)?
-                }
-                2 => {
-                    let data = ToLmSchematic::new(&schematic)?.lm_schematic(6);
-                    file_manager.save_nbt_value_temp(data, v_type, true)?
-                }
-                3 => {
-                    let data = ToWeSchematic::new(&schematic)?.we_schematic(sub_version)?;
-                    file_manager.save_nbt_value_temp(data, v_type, true)?
-                }
-                4 => {
-                    let data = ToBgSchematic::new(&schematic)?.bg_schematic(sub_version)?;
-                    file_manager.save_json_value_temp(data)?
-                }
-                5 => {
-                    let data = ToBESchematic::new(&schematic)?.to_be_value();
-                    file_manager.save_nbt_value_le_temp(data, v_type)?
-                }
-                _ => {
-                    anyhow::bail!("unknown schematic type: {}", v_type);
-                }
-            };
+            let bytes = part_bytes(&file_manager, &schematic, v_type, sub_version)?;
+            results.push((index as i64, part_size, bytes));
+        }
 
-            let mut file = temp_file.into_file();
-            let mut bytes = Vec::new();
-            file.read_to_end(&mut bytes)?;
+        Ok(results)
+    }
+        .await
+        .map_err(|e: anyhow::Error| e.to_string())
+}
 
-            results.push((index as i64, part_size, bytes));
+/// 沿一个轴按整层累加，直到再加一层就超出预算
+#[tauri::command]
+pub async fn schematic_split_budget(
+    db: State<'_, DatabaseState>,
+    je_blocks: State<'_, BlocksData>,
+    file_manager: State<'_, FileManager>,
+    schematic_id: i64,
+    budget: SplitBudget,
+    air_frame: bool,
+) -> Result<Vec<BudgetPart>, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, schematic_id)?;
+        let version = schematic.version;
+        let sub_version = schematic.sub_type;
+        let v_type = schematic.schematic_type;
+        let data = file_manager.get_schematic_data(schematic_id, version, sub_version, v_type)?;
+
+        let split_blocks = split_by_budget(&data.blocks.elements, &data.size, &budget)?;
+        let requirements: Vec<Requirements> = split_blocks.iter().map(|(_, _, _, req)| req.clone()).collect();
+        let split_parts = assemble_parts(
+            split_blocks
+                .into_iter()
+                .map(|(blocks, size, offset, _)| (blocks, size, offset))
+                .collect(),
+            &data.tile_entities_list,
+            &data.entities_list,
+            air_frame,
+        );
+
+        let mut results = Vec::new();
+        for (index, ((blocks, tile_entities, entities, part_size, offset), req)) in
+            split_parts.into_iter().zip(requirements).enumerate()
+        {
+            let schematic = SchematicData::new(blocks, tile_entities, entities, part_size.clone());
+            let bytes = part_bytes(&file_manager, &schematic, v_type, sub_version)?;
+            let mut materials: Vec<PartMaterial> = req
+                .get_requirements()
+                .iter()
+                .map(|(id, &num)| {
+                    let name = id.name.strip_prefix("minecraft:").unwrap_or(&id.name);
+                    PartMaterial {
+                        id: id.name.to_string(),
+                        zh_cn: je_blocks.get_zh_cn(name).unwrap_or(name).to_string(),
+                        num,
+                    }
+                })
+                .collect();
+            materials.sort_by(|a, b| b.num.cmp(&a.num).then(a.id.cmp(&b.id)));
+            let cost = budget.cost(&req);
+            results.push(BudgetPart {
+                index: index as i64,
+                size: part_size,
+                offset,
+                bytes,
+                blocks: block_count(&req),
+                types: req.get_requirements().len() as u64,
+                shulkers: shulker_boxes(&req),
+                over_budget: cost > budget.limit,
+                materials,
+            });
         }
 
         Ok(results)
@@ -99,6 +213,225 @@ pub async fn schematic_split(
         .map_err(|e: anyhow::Error| e.to_string())
 }
 
+/// budget 不为空时按预算拆分，忽略 split_type 和 split_number
+fn split_parts_with(
+    data: &SchematicData,
+    split_type: i64,
+    split_number: i64,
+    budget: Option<&SplitBudget>,
+    air_frame: bool,
+) -> Result<Vec<(BlockStatePosList, TileEntitiesList, EntitiesList, Size, Offset)>> {
+    match budget {
+        Some(budget) => {
+            let split_blocks = split_by_budget(&data.blocks.elements, &data.size, budget)?
+                .into_iter()
+                .map(|(blocks, size, offset, _)| (blocks, size, offset))
+                .collect();
+            Ok(assemble_parts(split_blocks, &data.tile_entities_list, &data.entities_list, air_frame))
+        }
+        None => split_schematic_parts(
+            &data.blocks.elements,
+            &data.tile_entities_list,
+            &data.entities_list,
+            &data.size,
+            split_type,
+            split_number as usize,
+            air_frame,
+        ),
+    }
+}
+
+/// 分块存为子蓝图并关联到原蓝图，记录每块的放置偏移
+#[tauri::command]
+pub async fn schematic_split_save(
+    db: State<'_, DatabaseState>,
+    je_blocks: State<'_, BlocksData>,
+    block_colors: State<'_, BlockColors>,
+    file_manager: State<'_, FileManager>,
+    schematic_id: i64,
+    split_type: i64,
+    split_number: i64,
+    budget: Option<SplitBudget>,
+    air_frame: bool,
+) -> Result<Vec<SavedPart>, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, schematic_id)?;
+        let v_type = schematic.schematic_type;
+        let sub_version = schematic.sub_type;
+        let data = file_manager.get_schematic_data(schematic_id, schematic.version, sub_version, v_type)?;
+        let parent_min = block_min(&data.blocks.elements);
+        let split_parts = split_parts_with(&data, split_type, split_number, budget.as_ref(), air_frame)?;
+        let mut results = Vec::with_capacity(split_parts.len());
+        for (blocks, tile_entities, entities, part_size, _) in split_parts {
+            // 空白分块加了空气框后也不为空，按有无实际方块判断
+            if blocks.elements.iter().all(|b| b.block.id.name.as_ref() == "minecraft:air") {
+                continue;
+            }
+            // 跳过空块后连续编号
+            let index = results.len();
+            // 各格式导出时都以分块自身的最小角为原点
+            let part_min = block_min(&blocks.elements);
+            let offset = Offset {
+                x: part_min.x - parent_min.x,
+                y: part_min.y - parent_min.y,
+                z: part_min.z - parent_min.z,
+            };
+            let part = SchematicData::new(blocks, tile_entities, entities, part_size.clone());
+            let child_id = save_schematic_entry(
+                &mut conn,
+                &file_manager,
+                &je_blocks,
+                &block_colors,
+                &part,
+                format!("{}_part_{}", schematic.name, index + 1),
+                v_type,
+                sub_version,
+            )?;
+            results.push(SavedPart {
+                index: index as i64,
+                id: child_id,
+                size: part_size,
+                offset,
+            });
+        }
+        // 子蓝图全部保存后再替换上一次拆分的关联，地图画等其他来源的关联不动
+        let links: Vec<_> = results
+            .iter()
+            .map(|part| (part.id, part.index as i32, [part.offset.x, part.offset.y, part.offset.z]))
+            .collect();
+        replace_schematic_links(&mut conn, schematic_id, SPLIT_LINK, &links)?;
+
+        Ok(results)
+    }
+        .await
+        .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// 导出分块清单：manifest.json、投影放置说明和各分块文件，打包为 zip
+#[tauri::command]
+pub async fn export_split_manifest(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    parent_id: i64,
+    target: String,
+    origin: Option<[i32; 3]>,
+) -> Result<usize, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let parent = find_schematic(&mut conn, parent_id)?;
+        let links: Vec<_> = find_schematic_links(&mut conn, parent_id)?
+            .into_iter()
+            .filter(|link| link.source == SPLIT_LINK)
+            .collect();
+        if links.is_empty() {
+            anyhow::bail!("schematic {} has no split parts", parent_id);
+        }
+        let origin = origin.unwrap_or([0, 0, 0]);
+
+        let mut files = Vec::with_capacity(links.len() + 2);
+        let mut parts = Vec::with_capacity(links.len());
+        let mut notes = vec![
+            format!("{} - {} parts", parent.name, links.len()),
+            format!("origin: {} {} {}", origin[0], origin[1], origin[2]),
+            "Litematica: load each file, Schematic Placements -> Configure -> set Origin to the values below, no rotation or mirror".to_string(),
+            String::new(),
+        ];
+        for link in &links {
+            let child = find_schematic(&mut conn, link.child_id)?;
+            let ext = match child.schematic_type {
+                1 => "nbt",
+                2 => "litematic",
+                3 => "schem",
+                4 => "json",
+                5 => "mcstructure",
+                _ => "unknown",
+            };
+            let file = format!("part_{:03}.{}", link.part_index + 1, ext);
+            let placed = [
+                origin[0] + link.offset[0],
+                origin[1] + link.offset[1],
+                origin[2] + link.offset[2],
+            ];
+            parts.push(json!({
+                "index": link.part_index,
+                "id": child.id,
+                "name": child.name,
+                "file": file,
+                "size": child.sizes,
+                "offset": link.offset,
+                "origin": placed,
+            }));
+            notes.push(format!(
+                "#{} {} ({}): origin {} {} {}, offset {} {} {}, size {}",
+                link.part_index + 1,
+                file,
+                child.name,
+                placed[0],
+                placed[1],
+                placed[2],
+                link.offset[0],
+                link.offset[1],
+                link.offset[2],
+                child.sizes.replace(',', "x"),
+            ));
+            let data = file_manager.get_schematic_data(child.id, child.version, child.sub_type, child.schematic_type)?;
+            files.push((file, part_bytes(&file_manager, &data, child.schematic_type, child.sub_type)?));
+        }
+        let manifest = json!({
+            "id": parent.id,
+            "name": parent.name,
+            "size": parent.sizes,
+            "origin": origin,
+            "parts": parts,
+        });
+        files.push(("manifest.json".to_string(), serde_json::to_vec_pretty(&manifest)?));
+        files.push(("placement.txt".to_string(), notes.join("\n").into_bytes()));
+
+        write_zip(&files, &target, &format!("schematic_{}_parts.zip", parent_id))?;
+        Ok(links.len())
+    }
+        .await
+        .map_err(|e: anyhow::Error| e.to_string())
+}
+
+fn part_bytes(
+    file_manager: &FileManager,
+    schematic: &SchematicData,
+    v_type: i32,
+    sub_version: i32,
+) -> Result<Vec<u8>> {
+    let temp_file = match v_type {
+        1 => {
+            let data = ToCreateSchematic::new(schematic)?.create_schematic(false);
+            file_manager.save_nbt_value_temp(data, v_type, true)?
+        }
+        2 => {
+            let data = ToLmSchematic::new(schematic)?.lm_schematic(6);
+            file_manager.save_nbt_value_temp(data, v_type, true)?
+        }
+        3 => {
+            let data = ToWeSchematic::new(schematic)?.we_schematic(sub_version)?;
+            file_manager.save_nbt_value_temp(data, v_type, true)?
+        }
+        4 => {
+            let data = ToBgSchematic::new(schematic)?.bg_schematic(sub_version)?;
+            file_manager.save_json_value_temp(data)?
+        }
+        5 => {
+            let data = ToBESchematic::new(schematic)?.to_be_value();
+            file_manager.save_nbt_value_le_temp(data, v_type)?
+        }
+        _ => {
+            anyhow::bail!("unknown schematic type: {}", v_type);
+        }
+    };
+
+    let mut file = temp_file.into_file();
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
 
 fn split_schematic_parts(
     blocks: &VecDeque<BlockStatePos>,
@@ -113,7 +446,16 @@ fn split_schematic_parts(
         return Err(anyhow!("Split number must be at least 1"));
     }
     let split_blocks = split_block_positions(blocks, size, split_type, split_number)?;
+    Ok(assemble_parts(split_blocks, tile_entities, entities, air_frame && split_type != 3))
+}
 
+/// 给每个分块补上空气框、分配方块实体，实体全部放在第一块
+fn assemble_parts(
+    split_blocks: Vec<(BlockStatePosList, Size, Offset)>,
+    tile_entities: &TileEntitiesList,
+    entities: &EntitiesList,
+    air_frame: bool,
+) -> Vec<(BlockStatePosList, TileEntitiesList, EntitiesList, Size, Offset)> {
     let air = Arc::new(BlockData {
         id: BlockId {
             name: Arc::from("minecraft:air"),
@@ -121,7 +463,7 @@ fn split_schematic_parts(
         properties: BTreeMap::new(),
     });
 
-    let mut results = Vec::with_capacity(split_number);
+    let mut results = Vec::with_capacity(split_blocks.len());
     for (i, (mut block_list, part_size, offset)) in split_blocks.into_iter().enumerate() {
         let corner_positions = [
             BlockPos { x: offset.x - 1, y: offset.y, z: offset.z - 1 },
@@ -134,10 +476,8 @@ fn split_schematic_parts(
             BlockPos { x: offset.x + part_size.width, y: offset.y + part_size.height - 1, z: offset.z + part_size.length },
         ];
         if air_frame {
-            if split_type != 3 {
-                for pos in corner_positions {
-                    block_list.elements.push_back(BlockStatePos::new(pos, Arc::clone(&air)));
-                }
+            for pos in corner_positions {
+                block_list.elements.push_back(BlockStatePos::new(pos, Arc::clone(&air)));
             }
         }
         let mut part_tile_entities = TileEntitiesList {
@@ -226,7 +566,7 @@ fn split_schematic_parts(
         results.push((block_list, part_tile_entities, part_entities, part_size, offset));
     }
 
-    Ok(results)
+    results
 }
 
 
@@ -240,43 +580,7 @@ fn split_block_positions(
     if split_number == 0 {
         return Err(anyhow!("Split number must be at least 1"));
     }
-    let min = {
-        let global_min = blocks
-            .par_iter()
-            .with_min_len(1_000_000)
-            .fold(
-                || BlockPos {
-                    x: i32::MAX,
-                    y: i32::MAX,
-                    z: i32::MAX,
-                },
-                |mut acc, bp| {
-                    acc.x = std::cmp::min(acc.x, bp.pos.x);
-                    acc.y = std::cmp::min(acc.y, bp.pos.y);
-                    acc.z = std::cmp::min(acc.z, bp.pos.z);
-                    acc
-                },
-            )
-            .reduce(
-                || BlockPos {
-                    x: i32::MAX,
-                    y: i32::MAX,
-                    z: i32::MAX,
-                },
-                |mut rel, tem| {
-                    rel.x = std::cmp::min(rel.x, tem.x);
-                    rel.y = std::cmp::min(rel.y, tem.y);
-                    rel.z = std::cmp::min(rel.z, tem.z);
-                    rel
-                },
-            );
-
-        BlockPos {
-            x: global_min.x,
-            y: global_min.y,
-            z: global_min.z,
-        }
-    };
+    let min = block_min(blocks);
 
     match split_type {
         1 | 2 => {
@@ -460,3 +764,117 @@ fn split_block_positions(
     }
 }
 
+/// 按层分组后贪心合并，返回每块的方块、尺寸、偏移和材料
+pub fn split_by_budget(
+    blocks: &VecDeque<BlockStatePos>,
+    size: &Size,
+    budget: &SplitBudget,
+) -> Result<Vec<(BlockStatePosList, Size, Offset, Requirements)>> {
+    if budget.limit == 0 {
+        return Err(anyhow!("Split budget must be at least 1"));
+    }
+    if blocks.is_empty() {
+        return Err(anyhow!("Schematic has no blocks to split"));
+    }
+    let axis = usize::try_from(budget.axis)
+        .ok()
+        .filter(|axis| *axis < 3)
+        .ok_or(anyhow!("Invalid split axis: {}", budget.axis))?;
+    let min = block_min(blocks);
+    let dims = [size.width, size.height, size.length];
+    let dim = dims[axis].max(1);
+
+    let mut layers = vec![BlockStatePosList::default(); dim as usize];
+    for block in blocks {
+        let pos = [block.pos.x - min.x, block.pos.y - min.y, block.pos.z - min.z][axis];
+        layers[pos.clamp(0, dim - 1) as usize].elements.push_back(block.clone());
+    }
+    let layer_requirements = layers
+        .par_iter()
+        .map(get_requirements)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut order: Vec<usize> = (0..dim as usize).collect();
+    if budget.descending {
+        order.reverse();
+    }
+    let mut groups: Vec<(Vec<usize>, Requirements)> = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+    let mut current_req = Requirements::new();
+    for i in order {
+        let mut merged = current_req.clone();
+        for (id, &n) in layer_requirements[i].get_requirements() {
+            merged.add_requirement(id.clone(), n);
+        }
+        if !current.is_empty() && budget.cost(&merged) > budget.limit {
+            groups.push((std::mem::take(&mut current), current_req));
+            current_req = layer_requirements[i].clone();
+        } else {
+            current_req = merged;
+        }
+        current.push(i);
+    }
+    if !current.is_empty() {
+        groups.push((current, current_req));
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|(indices, req)| {
+            let lo = *indices.iter().min().unwrap_or(&0);
+            let hi = *indices.iter().max().unwrap_or(&0);
+            let mut elements = VecDeque::new();
+            for layer in &mut layers[lo..=hi] {
+                elements.append(&mut layer.elements);
+            }
+            let mut part_dims = dims;
+            part_dims[axis] = (hi - lo + 1) as i32;
+            let mut origin = [0, 0, 0];
+            origin[axis] = lo as i32;
+            (
+                BlockStatePosList { elements },
+                Size { width: part_dims[0], height: part_dims[1], length: part_dims[2] },
+                Offset { x: origin[0], y: origin[1], z: origin[2] },
+                req,
+            )
+        })
+        .collect())
+}
+
+fn block_min(blocks: &VecDeque<BlockStatePos>) -> BlockPos {
+    let global_min = blocks
+        .par_iter()
+        .with_min_len(1_000_000)
+        .fold(
+            || BlockPos {
+                x: i32::MAX,
+                y: i32::MAX,
+                z: i32::MAX,
+            },
+            |mut acc, bp| {
+                acc.x = std::cmp::min(acc.x, bp.pos.x);
+                acc.y = std::cmp::min(acc.y, bp.pos.y);
+                acc.z = std::cmp::min(acc.z, bp.pos.z);
+                acc
+            },
+        )
+        .reduce(
+            || BlockPos {
+                x: i32::MAX,
+                y: i32::MAX,
+                z: i32::MAX,
+            },
+            |mut rel, tem| {
+                rel.x = std::cmp::min(rel.x, tem.x);
+                rel.y = std::cmp::min(rel.y, tem.y);
+                rel.z = std::cmp::min(rel.z, tem.z);
+                rel
+            },
+        );
+
+    BlockPos {
+        x: global_min.x,
+        y: global_min.y,
+        z: global_min.z,
+    }
+}
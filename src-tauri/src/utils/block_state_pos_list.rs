@@ -35,6 +35,17 @@ pub struct BlockData {
     pub properties: BTreeMap<Arc<str>, Arc<str>>,
 }
 
+impl BlockData {
+    /// 按名称创建无属性的方块，不带命名空间时默认 minecraft
+    pub fn from_name(name: &str) -> Arc<BlockData> {
+        let name = if name.contains(':') { name.to_string() } else { format!("minecraft:{}", name) };
+        Arc::new(BlockData {
+            id: BlockId { name: Arc::from(name) },
+            properties: BTreeMap::new(),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct BlockStatePos {
     pub pos: BlockPos,
@@ -3,6 +3,7 @@ use flate2::write::GzEncoder;
 use flate2::Compression;
 use std::fs::File;
 use std::io::Write;
+use std::path::PathBuf;
 
 pub fn to_writer_gzip(
     data: &impl serde::Serialize,
@@ -17,3 +18,51 @@ pub fn to_writer_gzip(
 
     Ok(())
 }
+
+pub fn to_bytes_gzip(data: &impl serde::Serialize) -> Result<Vec<u8>, SchematicError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let bytes = fastnbt::to_bytes(&data)?;
+    encoder.write_all(&bytes)?;
+    Ok(encoder.finish()?)
+}
+
+pub fn to_writer_zip(
+    files: &[(String, Vec<u8>)],
+    output_path: &str,
+) -> Result<(), SchematicError> {
+    let file = File::create(output_path)?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for (name, bytes) in files {
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| SchematicError::Io(e.into()))?;
+        zip.write_all(bytes)?;
+    }
+    zip.finish().map_err(|e| SchematicError::Io(e.into()))?;
+    Ok(())
+}
+
+/// 目标是目录时拼上默认文件名，并创建上级目录
+pub fn resolve_target(target: &str, default_name: &str) -> Result<PathBuf, SchematicError> {
+    let path = PathBuf::from(target);
+    let dest_path = if path.is_dir() {
+        path.join(default_name)
+    } else {
+        path
+    };
+    if let Some(parent) = dest_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    Ok(dest_path)
+}
+
+pub fn write_zip(
+    files: &[(String, Vec<u8>)],
+    target: &str,
+    default_name: &str,
+) -> Result<PathBuf, SchematicError> {
+    let dest_path = resolve_target(target, default_name)?;
+    to_writer_zip(files, &dest_path.to_string_lossy())?;
+    Ok(dest_path)
+}
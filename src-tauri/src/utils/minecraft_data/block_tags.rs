@@ -0,0 +1,72 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Deserialize)]
+struct RawTag {
+    values: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BlockTags {
+    pub tags: HashMap<String, HashSet<String>>,
+}
+
+fn normalize(name: &str) -> String {
+    let name = name.trim_start_matches('#');
+    if name.contains(':') {
+        name.to_string()
+    } else {
+        format!("minecraft:{}", name)
+    }
+}
+
+impl BlockTags {
+    pub fn new(app: &AppHandle) -> Result<Self> {
+        let path = app.path().resolve("data/block_tags.json", BaseDirectory::Resource)?;
+        let str = fs::read_to_string(path)?;
+        Self::parse(str.as_str())
+    }
+
+    pub fn parse(json: &str) -> Result<Self> {
+        let raw: HashMap<String, RawTag> = serde_json::from_str(json)?;
+        let mut tags = HashMap::new();
+        for name in raw.keys() {
+            let mut ids = HashSet::new();
+            Self::resolve(&raw, name, &mut ids, &mut Vec::new())?;
+            tags.insert(name.clone(), ids);
+        }
+        Ok(Self { tags })
+    }
+
+    // 展开 #引用 的嵌套标签
+    fn resolve(
+        raw: &HashMap<String, RawTag>,
+        name: &str,
+        ids: &mut HashSet<String>,
+        stack: &mut Vec<String>,
+    ) -> Result<()> {
+        if stack.iter().any(|s| s == name) {
+            return Err(anyhow!("cyclic block tag: {}", name));
+        }
+        let tag = raw.get(name).ok_or_else(|| anyhow!("unknown block tag: #{}", name))?;
+        stack.push(name.to_string());
+        for value in &tag.values {
+            if value.starts_with('#') {
+                Self::resolve(raw, &normalize(value), ids, stack)?;
+            } else {
+                ids.insert(normalize(value));
+            }
+        }
+        stack.pop();
+        Ok(())
+    }
+
+    /// 接受 `#minecraft:logs`、`minecraft:logs` 或 `logs`
+    pub fn get(&self, tag: &str) -> Option<&HashSet<String>> {
+        self.tags.get(&normalize(tag))
+    }
+}
@@ -88,4 +88,9 @@ impl BlocksData {
     pub fn get_zh_cn(&self, block_name: &str) -> Option<&str> {
         self.block_to_cn.get(block_name).map(|s| s.as_str())
     }
+
+    pub fn contains(&self, block_name: &str) -> bool {
+        let name = block_name.strip_prefix("minecraft:").unwrap_or(block_name);
+        self.block_to_cn.contains_key(name)
+    }
 }
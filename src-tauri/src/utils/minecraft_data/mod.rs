@@ -1,3 +1,4 @@
+pub mod block_tags;
 pub mod je_blocks_data;
 pub mod map_art_data;
 pub mod versions_data;
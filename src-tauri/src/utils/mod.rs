@@ -7,4 +7,5 @@ pub mod requirements;
 pub mod schematic_data;
 pub mod tile_entities;
 pub mod open_dev;
-pub mod entities;
\ No newline at end of file
+pub mod entities;
+pub mod structure_tiles;
\ No newline at end of file
@@ -180,3 +180,32 @@ pub fn get_requirements(blocks: &BlockStatePosList) -> Result<Requirements, Sche
         requirements: requirements_map,
     })
 }
+
+// 潜影盒格数
+pub const SHULKER_SLOTS: u64 = 27;
+
+/// 材料的最大堆叠数，水、岩浆、细雪按桶计
+pub fn stack_size(id: &str) -> u64 {
+    let name = id.strip_prefix("minecraft:").unwrap_or(id);
+    let unstackable = name.ends_with("_bed")
+        || name.ends_with("shulker_box")
+        || matches!(name, "cake" | "water" | "lava" | "powder_snow");
+    let sixteen = name.ends_with("_sign") || name.ends_with("_banner");
+    if unstackable {
+        1
+    } else if sixteen {
+        16
+    } else {
+        64
+    }
+}
+
+/// 按堆叠规则装进潜影盒所需的数量
+pub fn shulker_boxes(req: &Requirements) -> u64 {
+    let stacks: u64 = req
+        .get_requirements()
+        .iter()
+        .map(|(id, &count)| (count.max(0) as u64).div_ceil(stack_size(&id.name)))
+        .sum();
+    stacks.div_ceil(SHULKER_SLOTS)
+}
@@ -42,6 +42,8 @@ pub enum SchematicError {
     },
     #[error("Missing required field: {0}")]
     MissingField(String),
+    #[error("Command error at line {line}: {message}")]
+    Command { line: usize, message: String },
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
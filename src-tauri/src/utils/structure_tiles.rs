@@ -0,0 +1,239 @@
+use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos, BlockStatePos, BlockStatePosList};
+use crate::utils::entities::{Entities, EntitiesList};
+use crate::utils::schematic_data::{SchematicData, Size};
+use crate::utils::tile_entities::{TileEntities, TileEntitiesList};
+use fastnbt::Value;
+use fastnbt::Value::Compound;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+// 原版结构方块单次最大保存尺寸
+pub const STRUCTURE_LIMIT: i32 = 48;
+
+#[derive(Debug, Clone)]
+pub struct StructureTile {
+    pub index: (i32, i32, i32),
+    pub offset: BlockPos,
+    pub size: Size,
+    pub data: SchematicData,
+}
+
+#[derive(Debug, Clone)]
+pub struct StructureTiles {
+    pub counts: (i32, i32, i32),
+    pub size: Size,
+    pub tiles: Vec<StructureTile>,
+}
+
+impl StructureTiles {
+    pub fn get(&self, x: i32, y: i32, z: i32) -> Option<&StructureTile> {
+        if x < 0 || y < 0 || z < 0 || x >= self.counts.0 || y >= self.counts.1 || z >= self.counts.2 {
+            return None;
+        }
+        self.tiles
+            .get(((x * self.counts.1 + y) * self.counts.2 + z) as usize)
+    }
+}
+
+// 按轴均分，保证每段不超过 limit 且各段尺寸接近
+fn axis_segments(len: i32, limit: i32) -> Vec<(i32, i32)> {
+    let count = ((len + limit - 1) / limit).max(1);
+    let step = len / count;
+    let remainder = len % count;
+    let mut start = 0;
+    (0..count)
+        .map(|i| {
+            let part = step + if i < remainder { 1 } else { 0 };
+            let seg = (start, part);
+            start += part;
+            seg
+        })
+        .collect()
+}
+
+fn find_segment(segments: &[(i32, i32)], v: i32) -> usize {
+    segments
+        .iter()
+        .position(|(start, len)| v >= *start && v < start + len)
+        .unwrap_or(segments.len() - 1)
+}
+
+fn shift_entity(nbt: &Value, offset: BlockPos) -> Value {
+    let Compound(map) = nbt else {
+        return nbt.clone();
+    };
+    let mut map = map.clone();
+    if let Some(Value::List(pos)) = map.get("pos") {
+        let shifted = pos
+            .iter()
+            .zip([offset.x, offset.y, offset.z])
+            .map(|(v, o)| match v {
+                Value::Double(d) => Value::Double(d - o as f64),
+                other => other.clone(),
+            })
+            .collect();
+        map.insert("pos".to_string(), Value::List(shifted));
+    }
+    if let Some(Value::List(pos)) = map.get("blockPos") {
+        let shifted = pos
+            .iter()
+            .zip([offset.x, offset.y, offset.z])
+            .map(|(v, o)| match v {
+                Value::Int(i) => Value::Int(i - o),
+                other => other.clone(),
+            })
+            .collect();
+        map.insert("blockPos".to_string(), Value::List(shifted));
+    }
+    Compound(map)
+}
+
+fn entity_block_pos(nbt: &Value) -> Option<BlockPos> {
+    let Compound(map) = nbt else {
+        return None;
+    };
+    match map.get("blockPos") {
+        Some(Value::List(pos)) if pos.len() == 3 => {
+            let v: Vec<i32> = pos
+                .iter()
+                .filter_map(|p| match p {
+                    Value::Int(i) => Some(*i),
+                    _ => None,
+                })
+                .collect();
+            (v.len() == 3).then(|| BlockPos { x: v[0], y: v[1], z: v[2] })
+        }
+        _ => None,
+    }
+}
+
+/// 把任意尺寸的原理图切成不超过 limit³ 的方块，坐标归一化到各自原点。
+/// 方块实体统一转为原版结构格式(original_type = 1)。
+pub fn tile_schematic(data: &SchematicData, limit: i32) -> StructureTiles {
+    let limit = limit.max(1);
+    let blocks = &data.blocks.elements;
+    let (min, max) = blocks.iter().fold(
+        (
+            BlockPos { x: i32::MAX, y: i32::MAX, z: i32::MAX },
+            BlockPos { x: i32::MIN, y: i32::MIN, z: i32::MIN },
+        ),
+        |(mut min, mut max), bp| {
+            min.x = min.x.min(bp.pos.x);
+            min.y = min.y.min(bp.pos.y);
+            min.z = min.z.min(bp.pos.z);
+            max.x = max.x.max(bp.pos.x);
+            max.y = max.y.max(bp.pos.y);
+            max.z = max.z.max(bp.pos.z);
+            (min, max)
+        },
+    );
+    let (min, max) = if blocks.is_empty() {
+        (BlockPos { x: 0, y: 0, z: 0 }, BlockPos { x: 0, y: 0, z: 0 })
+    } else {
+        (min, max)
+    };
+    let size = Size {
+        width: max.x - min.x + 1,
+        height: max.y - min.y + 1,
+        length: max.z - min.z + 1,
+    };
+    let xs = axis_segments(size.width, limit);
+    let ys = axis_segments(size.height, limit);
+    let zs = axis_segments(size.length, limit);
+    let counts = (xs.len() as i32, ys.len() as i32, zs.len() as i32);
+    let tile_index = |x: i32, y: i32, z: i32| -> usize {
+        let (i, j, k) = (find_segment(&xs, x), find_segment(&ys, y), find_segment(&zs, z));
+        (i * ys.len() + j) * zs.len() + k
+    };
+
+    let mut tiles = Vec::with_capacity(xs.len() * ys.len() * zs.len());
+    for (i, (x0, w)) in xs.iter().enumerate() {
+        for (j, (y0, h)) in ys.iter().enumerate() {
+            for (k, (z0, l)) in zs.iter().enumerate() {
+                let size = Size { width: *w, height: *h, length: *l };
+                tiles.push(StructureTile {
+                    index: (i as i32, j as i32, k as i32),
+                    offset: BlockPos { x: *x0, y: *y0, z: *z0 },
+                    size,
+                    data: SchematicData::new(
+                        BlockStatePosList::default(),
+                        TileEntitiesList { original_type: 1, elements: vec![] },
+                        EntitiesList { original_type: 1, elements: vec![] },
+                        size,
+                    ),
+                });
+            }
+        }
+    }
+
+    for block in blocks {
+        let (x, y, z) = (block.pos.x - min.x, block.pos.y - min.y, block.pos.z - min.z);
+        let tile = &mut tiles[tile_index(x, y, z)];
+        tile.data.blocks.elements.push_back(BlockStatePos::new(
+            BlockPos { x: x - tile.offset.x, y: y - tile.offset.y, z: z - tile.offset.z },
+            block.block.clone(),
+        ));
+    }
+
+    for te in &data.tile_entities_list.elements {
+        let (x, y, z) = (te.pos.x - min.x, te.pos.y - min.y, te.pos.z - min.z);
+        if x < 0 || y < 0 || z < 0 || x >= size.width || y >= size.height || z >= size.length {
+            continue;
+        }
+        let tile = &mut tiles[tile_index(x, y, z)];
+        let pos = BlockPos { x: x - tile.offset.x, y: y - tile.offset.y, z: z - tile.offset.z };
+        tile.data.tile_entities_list.elements.push(TileEntities {
+            pos,
+            nbt: TileEntities { pos, nbt: te.nbt.clone() }.structure_nbt(),
+        });
+    }
+
+    // 只有原版结构格式的实体带有可用坐标
+    if data.entities_list.original_type == 1 {
+        for entity in &data.entities_list.elements {
+            let Some(pos) = entity_block_pos(&entity.nbt) else {
+                continue;
+            };
+            let (x, y, z) = (pos.x - min.x, pos.y - min.y, pos.z - min.z);
+            let tile = &mut tiles[tile_index(x, y, z)];
+            let shift = BlockPos {
+                x: min.x + tile.offset.x,
+                y: min.y + tile.offset.y,
+                z: min.z + tile.offset.z,
+            };
+            tile.data.entities_list.elements.push(Entities {
+                nbt: shift_entity(&entity.nbt, shift),
+            });
+        }
+    }
+
+    // ToCreateSchematic 以方块最小坐标为原点，用空气补齐每块的原点角
+    let air = Arc::new(BlockData {
+        id: BlockId { name: Arc::from("minecraft:air") },
+        properties: BTreeMap::new(),
+    });
+    for tile in tiles.iter_mut() {
+        let has_origin = tile
+            .data
+            .blocks
+            .elements
+            .iter()
+            .any(|b| b.pos.x == 0 && b.pos.y == 0 && b.pos.z == 0);
+        if !has_origin {
+            tile.data.blocks.add_to_first(0, 0, 0, &air);
+        }
+    }
+
+    StructureTiles { counts, size, tiles }
+}
+
+/// 按位置索引方块，便于在切块边界上查找/替换方块
+pub fn block_map(tile: &StructureTile) -> HashMap<(i32, i32, i32), usize> {
+    tile.data
+        .blocks
+        .elements
+        .iter()
+        .enumerate()
+        .map(|(i, b)| ((b.pos.x, b.pos.y, b.pos.z), i))
+        .collect()
+}
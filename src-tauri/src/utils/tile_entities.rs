@@ -70,3 +70,30 @@ impl TileEntitiesList {
         })
     }
 }
+
+impl TileEntities {
+    // 转为原版结构格式：去掉坐标，展开 WE 的 Data/Id
+    pub fn structure_nbt(&self) -> Value {
+        let Compound(map) = &self.nbt else {
+            return self.nbt.clone();
+        };
+        let mut data = match (map.get("Data"), map.get("Id")) {
+            (Some(Compound(data)), Some(id)) => {
+                let mut data = data.clone();
+                data.insert("id".to_string(), id.clone());
+                data
+            }
+            _ => {
+                let mut data = map.clone();
+                if let Some(id) = map.get("Id") {
+                    data.entry("id".to_string()).or_insert_with(|| id.clone());
+                }
+                data
+            }
+        };
+        for key in ["x", "y", "z", "Pos", "Id", "keepPacked"] {
+            data.remove(key);
+        }
+        Compound(data)
+    }
+}
@@ -0,0 +1,297 @@
+import {invoke} from "@tauri-apps/api/core";
+import {toast} from "./others.ts";
+import {DitherMode, Size} from "./map_art/image_utils.ts";
+
+export interface GenerateResult {
+    id: number,
+    size: Size,
+    blocks: number,
+    parts: number[],
+}
+
+export interface MuralOptions {
+    width: number,
+    height: number,
+    rotation?: 0 | 90 | 180 | 270,
+    dither?: DitherMode,
+    facing?: 'north' | 'south' | 'east' | 'west' | 'up' | 'down',
+    depth?: number,
+    invert_depth?: boolean,
+    fill?: boolean,
+    blocks?: string[],
+    exclude?: string[],
+}
+
+export const previewMural = async (
+    image: Uint8Array,
+    options: MuralOptions,
+    pack?: string
+): Promise<string> => {
+    return await invoke<string>(
+        'preview_mural',
+        {
+            image: Array.from(image),
+            options: options,
+            pack: pack,
+        }
+    )
+}
+
+export const createMural = async (
+    image: Uint8Array,
+    file_name: string,
+    options: MuralOptions,
+    schematic_type: number,
+    sub_version: number,
+    pack?: string
+): Promise<GenerateResult> => {
+    try {
+        return await invoke<GenerateResult>(
+            'create_mural',
+            {
+                image: Array.from(image),
+                fileName: file_name,
+                options: options,
+                schematicType: schematic_type,
+                subVersion: sub_version,
+                pack: pack,
+            }
+        )
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`生成墙画失败: ${error}`);
+    }
+}
+
+export interface HeightLayer {
+    min_height?: number,
+    max_height?: number,
+    surface: string,
+    subsurface?: string,
+    depth?: number,
+}
+
+export interface TerrainMask {
+    image: number[],
+    threshold?: number,
+    surface: string,
+    subsurface?: string,
+    depth?: number,
+}
+
+export interface TerrainOptions {
+    width?: number,
+    length?: number,
+    vertical_scale?: number,
+    base_height?: number,
+    fill_block?: string,
+    layers?: HeightLayer[],
+    masks?: TerrainMask[],
+    water_level?: number | null,
+    water_block?: string,
+    chunk_size?: number,
+}
+
+export const createTerrain = async (
+    heightmap: Uint8Array,
+    file_name: string,
+    options: TerrainOptions,
+    schematic_type: number,
+    sub_version: number
+): Promise<GenerateResult> => {
+    try {
+        return await invoke<GenerateResult>(
+            'create_terrain',
+            {
+                heightmap: Array.from(heightmap),
+                fileName: file_name,
+                options: options,
+                schematicType: schematic_type,
+                subVersion: sub_version,
+            }
+        )
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`生成地形失败: ${error}`);
+    }
+}
+
+export interface VoxelOptions {
+    size?: number,
+    solid?: boolean,
+    fill_block?: string,
+    z_up?: boolean,
+    use_color?: boolean,
+    default_block?: string,
+    blocks?: string[],
+    exclude?: string[],
+}
+
+export const createVoxelModel = async (
+    path: string,
+    options: VoxelOptions,
+    schematic_type: number,
+    sub_version: number,
+    pack?: string
+): Promise<GenerateResult> => {
+    try {
+        return await invoke<GenerateResult>(
+            'create_voxel_model',
+            {
+                path: path,
+                options: options,
+                schematicType: schematic_type,
+                subVersion: sub_version,
+                pack: pack,
+            }
+        )
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`模型体素化失败: ${error}`);
+    }
+}
+
+export type ShapeKind = 'sphere' | 'cylinder' | 'torus' | 'dome' | 'arch' | 'spiral';
+
+export interface WeightedBlock {
+    block: string,
+    weight: number,
+}
+
+export interface ShapeOptions {
+    shape?: ShapeKind,
+    radius?: number,
+    minor_radius?: number,
+    height?: number,
+    length?: number,
+    turns?: number,
+    hollow?: boolean,
+    thickness?: number,
+    capped?: boolean,
+    pattern?: WeightedBlock[],
+    seed?: number,
+    axis?: 'x' | 'y' | 'z',
+    stair_block?: string,
+}
+
+export const createShape = async (
+    file_name: string,
+    options: ShapeOptions,
+    schematic_type: number,
+    sub_version: number
+): Promise<GenerateResult> => {
+    try {
+        return await invoke<GenerateResult>(
+            'create_shape',
+            {
+                fileName: file_name,
+                options: options,
+                schematicType: schematic_type,
+                subVersion: sub_version,
+            }
+        )
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`生成几何体失败: ${error}`);
+    }
+}
+
+export interface TextOptions {
+    text: string,
+    font?: '5x7' | '3x5',
+    font_path?: string,
+    height?: number,
+    scale?: number,
+    letter_spacing?: number,
+    line_spacing?: number,
+    depth?: number,
+    facing?: string,
+    foreground?: string,
+    background?: string,
+    outline?: string,
+    padding?: number,
+    smooth?: boolean,
+    stair_block?: string,
+    slab_block?: string,
+}
+
+export const createText = async (
+    file_name: string,
+    options: TextOptions,
+    schematic_type: number,
+    sub_version: number
+): Promise<GenerateResult> => {
+    try {
+        return await invoke<GenerateResult>(
+            'create_text',
+            {
+                fileName: file_name,
+                options: options,
+                schematicType: schematic_type,
+                subVersion: sub_version,
+            }
+        )
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`生成文字失败: ${error}`);
+    }
+}
+
+export type NoteLayout = 'linear' | 'folded' | 'multi_track';
+
+export interface NoteBlockOptions {
+    layout?: NoteLayout,
+    fold_length?: number,
+    support_block?: string,
+}
+
+export interface SongInfo {
+    name: string,
+    author: string,
+    tempo: number,
+    length: number,
+    seconds: number,
+    notes: number,
+    placed: number,
+    dropped: number,
+    transposed: number,
+    lanes: number,
+}
+
+export interface NoteBlockResult extends GenerateResult {
+    song: SongInfo,
+}
+
+export const createNoteBlockSong = async (
+    path: string,
+    options: NoteBlockOptions,
+    schematic_type: number,
+    sub_version: number
+): Promise<NoteBlockResult> => {
+    try {
+        return await invoke<NoteBlockResult>(
+            'create_note_block_song',
+            {
+                path: path,
+                options: options,
+                schematicType: schematic_type,
+                subVersion: sub_version,
+            }
+        )
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`生成音符盒线路失败: ${error}`);
+    }
+}
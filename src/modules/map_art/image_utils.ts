@@ -64,6 +64,7 @@ export const createMapArt = async (
                 size: size,
                 schematicType: schematic_type,
                 subVersion: sub_version,
+                pack: pack,
             }
         )
     } catch (error) {
@@ -73,3 +74,144 @@ export const createMapArt = async (
         throw new Error(`创建地图画失败: ${error}`);
     }
 }
+
+export type DitherMode = 'none' | 'floyd_steinberg' | 'atkinson' | 'ordered'
+
+export interface MapArtOptions {
+    width: number,
+    height: number,
+    rotation?: 0 | 90 | 180 | 270,
+    dither?: DitherMode,
+    staircase?: boolean,
+    max_height?: number,
+    replace_air?: boolean,
+    axis?: 'x' | 'y' | 'z',
+    base_block?: string,
+    split_maps?: boolean,
+    support?: boolean,
+    support_block?: string,
+    valley?: boolean,
+}
+
+export interface MapArtResult {
+    id: number,
+    preview: string,
+    size: Size,
+    blocks: number,
+    parts: number[],
+}
+
+export const previewMapArt = async (
+    image: Uint8Array,
+    blocks: string[],
+    options: MapArtOptions,
+    pack?: string
+): Promise<string> => {
+    return await invoke<string>(
+        'preview_map_art',
+        {
+            image: Array.from(image),
+            blocks: blocks,
+            options: options,
+            pack: pack,
+        }
+    )
+}
+
+export const createMapArtFromImage = async (
+    image: Uint8Array,
+    file_name: string,
+    blocks: string[],
+    options: MapArtOptions,
+    schematic_type: number,
+    sub_version: number,
+    pack?: string
+): Promise<MapArtResult> => {
+    try {
+        return await invoke<MapArtResult>(
+            'create_map_art_from_image',
+            {
+                image: Array.from(image),
+                fileName: file_name,
+                blocks: blocks,
+                options: options,
+                schematicType: schematic_type,
+                subVersion: sub_version,
+                pack: pack,
+            }
+        )
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`创建地图画失败: ${error}`);
+    }
+}
+
+export interface MapItemOptions {
+    start_id: number,
+    dimension?: string,
+    locked?: boolean,
+    x_center?: number,
+    z_center?: number,
+    data_version?: number,
+    // 存档当前的地图 id，给出时才写 idcounts.dat
+    current_map_id?: number,
+    staircase?: boolean,
+}
+
+export interface MapItemsResult {
+    start_id: number,
+    count: number,
+    columns: number,
+    rows: number,
+    commands: string[],
+}
+
+export const exportMapItemsFromImage = async (
+    image: Uint8Array,
+    file_name: string,
+    options: MapArtOptions,
+    map_options: MapItemOptions,
+    target: string
+): Promise<MapItemsResult> => {
+    try {
+        return await invoke<MapItemsResult>(
+            'export_map_items_from_image',
+            {
+                image: Array.from(image),
+                fileName: file_name,
+                options: options,
+                mapOptions: map_options,
+                target: target,
+            }
+        )
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`导出地图文件失败: ${error}`);
+    }
+}
+
+export const exportMapItems = async (
+    id: number,
+    map_options: MapItemOptions,
+    target: string
+): Promise<MapItemsResult> => {
+    try {
+        return await invoke<MapItemsResult>(
+            'export_map_items',
+            {
+                id: id,
+                mapOptions: map_options,
+                target: target,
+            }
+        )
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`导出地图文件失败: ${error}`);
+    }
+}
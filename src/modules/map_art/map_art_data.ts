@@ -78,4 +78,24 @@ export async function fetchMapArtsData(
         });
         throw new Error(`err: ${error}`);
     }
-}
\ No newline at end of file
+}
+export async function fetchPackMapArtsData(
+    pack: string
+): Promise<RawData[]> {
+    try {
+        let data = await invoke<CategoryBlocks>('get_pack_map_arts', { pack: pack })
+        return Object.entries(data).map(([category, blocks]) => ({
+            name: category,
+            zh_cn: categoryData[category],
+            items: Object.entries(blocks).map(([id, data]) => ({
+                id,
+                ...data
+            }))
+        }))
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`err: ${error}`);
+    }
+}
@@ -33,4 +33,176 @@ export async function fetchUniqueBlocks(
         throw new Error(`err: ${error}`);
     }
 
-}
\ No newline at end of file
+}
+export interface ThemePair {
+    from: string,
+    to: string,
+}
+
+export interface ThemePreset {
+    id: number,
+    name: string,
+    description: string,
+    pairs: ThemePair[],
+    builtin: boolean,
+    created_at?: string,
+    updated_at?: string,
+}
+
+export const fetchThemePresets = async (): Promise<ThemePreset[]> => {
+    try {
+        return await invoke<ThemePreset[]>('get_theme_presets');
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`err: ${error}`);
+    }
+}
+
+export const saveThemePreset = async (preset: ThemePreset): Promise<number> => {
+    try {
+        return await invoke<number>('save_theme_preset', { preset: preset });
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`err: ${error}`);
+    }
+}
+
+export const deleteThemePreset = async (id: number): Promise<number> => {
+    try {
+        return await invoke<number>('delete_theme_preset', { id: id });
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`err: ${error}`);
+    }
+}
+
+export const applyThemePreset = async (
+    preset_id: number,
+    schematic_ids: number[]
+): Promise<number[]> => {
+    try {
+        return await invoke<number[]>('apply_theme_preset', {
+            presetId: preset_id,
+            schematicIds: schematic_ids,
+        });
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`套用主题失败: ${error}`);
+    }
+}
+
+export interface RecolorMapping {
+    original: string,
+    replacement: string,
+    count: number,
+    error: number,
+}
+
+export interface RecolorResult {
+    id: number,
+    replaced: number,
+    skipped: string[],
+    mean_error: number,
+    max_error: number,
+    mappings: RecolorMapping[],
+}
+
+export const recolorSchematic = async (
+    schematic_id: number,
+    palette: string[]
+): Promise<RecolorResult> => {
+    try {
+        return await invoke<RecolorResult>('schematic_recolor', {
+            schematicId: schematic_id,
+            palette: palette,
+        });
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`重新配色失败: ${error}`);
+    }
+}
+
+export interface RuleStats {
+    matched: number,
+    replaced: number,
+    samples: BlockPos[],
+}
+
+export interface RequirementDelta {
+    id: string,
+    zh_cn: string,
+    before: number,
+    after: number,
+    delta: number,
+}
+
+export interface ReplacementResult {
+    id: number | null,
+    version: number,
+    dry_run: boolean,
+    rules: RuleStats[],
+    delta: RequirementDelta[],
+}
+
+export const previewReplacement = async (rules: any[]): Promise<ReplacementResult> => {
+    try {
+        return await invoke<ReplacementResult>('schematic_replacement', {
+            rules: rules,
+            dryRun: true,
+        });
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`err: ${error}`);
+    }
+}
+
+export interface BlockPredicate {
+    mode: number,
+    original_id?: string,
+    original_details?: BlockDataNew,
+    match_properties?: Record<string, string | string[]>,
+}
+
+export interface ExtractOptions {
+    mode: 'keep' | 'remove',
+    blocks: BlockPredicate[],
+    entities: BlockPredicate[],
+}
+
+export interface ExtractResult {
+    id: number,
+    blocks: number,
+    tile_entities: number,
+    entities: number,
+    removed_blocks: number,
+    removed_entities: number,
+}
+
+export const extractSchematic = async (
+    schematic_id: number,
+    options: ExtractOptions
+): Promise<ExtractResult> => {
+    try {
+        return await invoke<ExtractResult>('schematic_extract', {
+            schematicId: schematic_id,
+            options: options,
+        });
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`提取失败: ${error}`);
+    }
+}
@@ -71,3 +71,98 @@ export const splitSchematicParts = async (
         throw err;
     }
 };
+
+export type BudgetKind = 'blocks' | 'types' | 'shulkers';
+
+export interface SplitBudget {
+    kind: BudgetKind;
+    limit: number;
+    // 0 x，1 y，2 z
+    axis: number;
+    descending?: boolean;
+}
+
+export interface PartMaterial {
+    id: string;
+    zh_cn: string;
+    num: number;
+}
+
+export interface BudgetPartFile extends SchematicPartFile {
+    offset: { x: number; y: number; z: number };
+    blocks: number;
+    types: number;
+    shulkers: number;
+    over_budget: boolean;
+    materials: PartMaterial[];
+}
+
+interface BudgetPart extends Omit<BudgetPartFile, 'file'> {
+    bytes: number[];
+}
+
+export const splitSchematicBudget = async (
+    schematicId: number,
+    budget: SplitBudget,
+    airFrame: boolean,
+    vType?: number,
+): Promise<BudgetPartFile[]> => {
+    try {
+        const parts = await invoke<BudgetPart[]>(`schematic_split_budget`, {
+            schematicId,
+            budget,
+            airFrame,
+        });
+        const extension = getExtensions(vType);
+        return parts.map(({bytes, ...part}) => ({
+            ...part,
+            file: new File([new Uint8Array(bytes)], `schematic_part_${part.index}${extension}`),
+        }));
+    } catch (err) {
+        console.error('处理示意图时出错:', err);
+        throw err;
+    }
+};
+
+export interface SavedPart {
+    index: number;
+    id: number;
+    size: SizeInfo;
+    offset: { x: number; y: number; z: number };
+}
+
+// budget 不为空时按预算拆分
+export const saveSplitParts = async (
+    params: SchematicReplacementParams,
+    budget?: SplitBudget,
+): Promise<SavedPart[]> => {
+    try {
+        return await invoke<SavedPart[]>(`schematic_split_save`, {
+            schematicId: params.schematicId,
+            splitType: params.splitType,
+            splitNumber: params.splitNumber,
+            budget: budget ?? null,
+            airFrame: params.airFrame,
+        });
+    } catch (err) {
+        console.error('保存分块时出错:', err);
+        throw err;
+    }
+};
+
+export const exportSplitManifest = async (
+    parentId: number,
+    target: string,
+    origin?: [number, number, number],
+): Promise<number> => {
+    try {
+        return await invoke<number>(`export_split_manifest`, {
+            parentId,
+            target,
+            origin: origin ?? null,
+        });
+    } catch (err) {
+        console.error('导出分块清单时出错:', err);
+        throw err;
+    }
+};